@@ -0,0 +1,151 @@
+//! A synthetic Rust project for the benchmarks, generated from a fixed
+//! recipe so every run measures the same code: modules of structs, impl
+//! blocks, doc comments and free functions calling each other within and
+//! across modules, with the string literals, closures and `match` arms
+//! brace matching has to step over.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lines per generated module, about what a real source file holds.
+const LINES_PER_MODULE: usize = 500;
+
+/// Free functions per module; each takes about 17 lines.
+const STEPS_PER_MODULE: usize = 25;
+
+pub struct Synthetic {
+    pub root: PathBuf,
+    /// Lines of source, which throughput is measured in
+    pub lines: usize,
+    /// A function whose call tree spans many modules
+    #[allow(dead_code)] // Not every benchmark gathers
+    pub entry: String,
+}
+
+/// The project of about `lines` lines, generated into the temporary
+/// directory on first use and reused afterwards.
+pub fn project(lines: usize) -> Synthetic {
+    let modules = lines.div_ceil(LINES_PER_MODULE).max(1);
+    let root = std::env::temp_dir().join(format!("gather-context-bench-{}", modules));
+    let src = root.join("src");
+
+    let mut lib = String::new();
+    let mut total = 0;
+    for module in 0..modules {
+        let source = module_source(module, modules);
+        total += source.lines().count();
+        writeln!(lib, "pub mod m{};", module).unwrap();
+        write_if_changed(&src.join(format!("m{}.rs", module)), &source);
+    }
+    total += lib.lines().count();
+    write_if_changed(&src.join("lib.rs"), &lib);
+    write_if_changed(
+        &root.join("Cargo.toml"),
+        "[package]\nname = \"synthetic\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    );
+
+    Synthetic {
+        root,
+        lines: total,
+        entry: "src::m0::step_0".to_string(),
+    }
+}
+
+/// Leaves files that are already up to date alone, so later runs read the
+/// same files rather than freshly written ones.
+fn write_if_changed(path: &Path, content: &str) {
+    if fs::read_to_string(path).ok().as_deref() != Some(content) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
+
+fn module_source(module: usize, modules: usize) -> String {
+    let next = (module + 1) % modules;
+    let mut source = format!(
+        "//! Module {0} of the synthetic project.\n\nuse crate::m{1}::Record{1};\n\n",
+        module, next
+    );
+    write!(
+        source,
+        r#"/// A record with an id, a name and its values.
+#[derive(Debug, Clone, Default)]
+pub struct Record{0} {{
+    pub id: u64,
+    pub name: String,
+    pub values: Vec<u32>,
+}}
+
+impl Record{0} {{
+    /// Creates an empty record.
+    pub fn new(id: u64) -> Self {{
+        Record{0} {{
+            id,
+            name: format!("record-{{}}", id),
+            values: Vec::new(),
+        }}
+    }}
+
+    /// The sum of the values.
+    pub fn total(&self) -> u32 {{
+        self.values.iter().copied().filter(|v| *v > 0).sum()
+    }}
+
+    /// Links this record to the next module's.
+    pub fn link(&self) -> Record{1} {{
+        Record{1}::new(self.id + 1)
+    }}
+}}
+
+"#,
+        module, next
+    )
+    .unwrap();
+
+    for step in 0..STEPS_PER_MODULE {
+        let helper = (step + 1) % STEPS_PER_MODULE;
+        let across = (step * 7 + 3) % STEPS_PER_MODULE;
+        write!(
+            source,
+            r#"/// Step {1} of module {0}: folds the input into a total.
+pub fn step_{1}(input: &[u32]) -> Result<u32, String> {{
+    let mut total = 0;
+    for value in input {{
+        if *value % 3 == 0 {{
+            total += step_{2}(&[*value / 3])?;
+        }} else if *value > {3} {{
+            total += crate::m{4}::step_{5}(&[*value - 1])?;
+        }}
+    }}
+    let record = Record{0}::new(u64::from(total));
+    let label = format!("{{}} {{{{braces}}}} '{{}}'", record.name, '{{');
+    match label.len() {{
+        0 => Err("empty label }}".to_string()),
+        n => Ok(total + n as u32 + record.total() + record.link().total()),
+    }}
+}}
+
+"#,
+            module,
+            step,
+            helper,
+            step * 10,
+            next,
+            across
+        )
+        .unwrap();
+    }
+
+    // Pad to the module size with a function of plain statements
+    let lines = source.lines().count();
+    source.push_str(&format!(
+        "/// Filler for module {}.\npub fn filler() -> u32 {{\n    let mut n = 0;\n",
+        module
+    ));
+    for line in lines + 4..LINES_PER_MODULE {
+        writeln!(source, "    n += {};", line).unwrap();
+    }
+    source.push_str("    n\n}\n");
+    source
+}
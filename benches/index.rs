@@ -0,0 +1,64 @@
+//! Criterion benchmarks of the stages a gather spends its time in, over a
+//! synthetic project of 100k lines: building the index (parsing every file
+//! and resolving every call), extracting one file, and gathering a call
+//! tree from the built index.
+//!
+//! ```bash
+//! cargo bench --bench index -- --save-baseline main
+//! # after a change
+//! cargo bench --bench index -- --baseline main
+//! ```
+
+mod common;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use gather_context::budget::Tokenizer;
+use gather_context::gatherer::{ContextGatherer, GatherParams};
+use gather_context::index::{IndexOptions, ProjectIndex};
+use gather_context::lang::{self, ExtractOptions};
+use std::fs;
+
+const LINES: usize = 100_000;
+
+fn index(c: &mut Criterion) {
+    let project = common::project(LINES);
+    let mut group = c.benchmark_group("index");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(project.lines as u64));
+    group.bench_function("build", |b| {
+        b.iter(|| ProjectIndex::build(&project.root, &IndexOptions::default()).unwrap())
+    });
+    group.finish();
+}
+
+fn extract(c: &mut Criterion) {
+    let project = common::project(LINES);
+    let path = project.root.join("src").join("m0.rs");
+    let content = fs::read_to_string(&path).unwrap();
+    let mut group = c.benchmark_group("extract");
+    group.throughput(Throughput::Elements(content.lines().count() as u64));
+    group.bench_function("rust_file", |b| {
+        b.iter(|| lang::extract(&content, &path, "m0", &ExtractOptions::default()))
+    });
+    group.finish();
+}
+
+fn gather(c: &mut Criterion) {
+    let project = common::project(LINES);
+    let index = ProjectIndex::build(&project.root, &IndexOptions::default()).unwrap();
+    let tokenizer = Tokenizer::load("chars", None).unwrap();
+    let gatherer = ContextGatherer::new(&index, &tokenizer);
+    let mut group = c.benchmark_group("gather");
+    group.sample_size(20);
+    group.bench_function("call_tree", |b| {
+        b.iter_batched(
+            || GatherParams::function(&project.entry),
+            |params| gatherer.gather(&params).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, index, extract, gather);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+//! Indexing throughput against the target, as a check for CI: builds the
+//! index of a synthetic 100k-line project a few times and reports how many
+//! lines per second the median run indexed. With
+//! `GATHER_CONTEXT_MIN_LINES_PER_SEC` set, a run below that floor fails,
+//! which catches regressions on a machine whose speed the floor was set for.
+//!
+//! ```bash
+//! cargo bench --bench throughput
+//! GATHER_CONTEXT_MIN_LINES_PER_SEC=12000 cargo bench --bench throughput
+//! ```
+
+mod common;
+
+use gather_context::index::{IndexOptions, ProjectIndex};
+use std::env;
+use std::process;
+use std::time::{Duration, Instant};
+
+const LINES: usize = 100_000;
+
+/// Lines indexed per second, parse and call resolution included, that the
+/// index is to reach on one core.
+const TARGET_LINES_PER_SEC: f64 = 100_000.0;
+
+const RUNS: usize = 5;
+
+fn main() {
+    // `cargo bench` passes libtest flags such as `--bench`; a name filter
+    // that isn't this benchmark's skips it
+    if env::args()
+        .skip(1)
+        .any(|arg| !arg.starts_with('-') && !"throughput".contains(&arg))
+    {
+        return;
+    }
+
+    let floor: Option<f64> = env::var("GATHER_CONTEXT_MIN_LINES_PER_SEC")
+        .ok()
+        .map(|value| {
+            value.parse().unwrap_or_else(|_| {
+                eprintln!(
+                    "GATHER_CONTEXT_MIN_LINES_PER_SEC must be a number, not '{}'",
+                    value
+                );
+                process::exit(2);
+            })
+        });
+
+    let project = common::project(LINES);
+    // Warms the file system cache
+    ProjectIndex::build(&project.root, &IndexOptions::default()).unwrap();
+    let mut runs: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            ProjectIndex::build(&project.root, &IndexOptions::default()).unwrap();
+            start.elapsed()
+        })
+        .collect();
+    runs.sort();
+    let median = runs[RUNS / 2];
+    let lines_per_sec = project.lines as f64 / median.as_secs_f64();
+
+    println!(
+        "Indexed {} lines in {:.2?} (median of {} runs): {:.0} lines/s, {:.0}% of the {:.0} lines/s target",
+        project.lines,
+        median,
+        RUNS,
+        lines_per_sec,
+        lines_per_sec / TARGET_LINES_PER_SEC * 100.0,
+        TARGET_LINES_PER_SEC
+    );
+    if let Some(floor) = floor.filter(|floor| lines_per_sec < *floor) {
+        println!("Below the floor of {:.0} lines/s", floor);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,79 @@
+//! Criterion benchmark of finding the source files of a large tree: the
+//! walk `ProjectIndex::build` starts with, on one thread and on one per
+//! core, over a synthetic tree of 20k source files in 1,100 directories
+//! with ignore files and build output to skip.
+//!
+//! ```bash
+//! cargo bench --bench walk
+//! ```
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use gather_context::index::collect_source_files;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PACKAGES: usize = 100;
+const MODULES_PER_PACKAGE: usize = 10;
+const FILES_PER_MODULE: usize = 20;
+
+/// The tree, generated into the temporary directory on first use and
+/// reused afterwards: packages of modules of small source files, each
+/// package with a `.gitignore`, an ignored directory and a `target/`.
+fn tree() -> PathBuf {
+    let root = std::env::temp_dir().join("gather-context-bench-walk");
+    let done = root.join(".complete");
+    if done.is_file() {
+        return root;
+    }
+    for package in 0..PACKAGES {
+        let dir = root.join(format!("crates/p{}", package));
+        write(
+            &dir.join("Cargo.toml"),
+            &format!("[package]\nname = \"p{}\"\n", package),
+        );
+        write(&dir.join(".gitignore"), "generated/\n");
+        write(&dir.join("generated/out.rs"), "fn generated() {}\n");
+        write(&dir.join("target/debug/build.rs"), "fn build() {}\n");
+        for module in 0..MODULES_PER_PACKAGE {
+            let module_dir = dir.join(format!("src/m{}", module));
+            write(&module_dir.join("README.md"), "Not source.\n");
+            for file in 0..FILES_PER_MODULE {
+                write(
+                    &module_dir.join(format!("f{}.rs", file)),
+                    &format!("pub fn f{}() {{}}\n", file),
+                );
+            }
+        }
+    }
+    write(&done, "");
+    root
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn walk(c: &mut Criterion) {
+    let root = tree();
+    let files = collect_source_files(&root, true, 1).unwrap();
+    assert_eq!(
+        files.len(),
+        PACKAGES * MODULES_PER_PACKAGE * FILES_PER_MODULE
+    );
+    assert_eq!(files, collect_source_files(&root, true, 0).unwrap());
+
+    let mut group = c.benchmark_group("walk");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(files.len() as u64));
+    group.bench_function("one_thread", |b| {
+        b.iter(|| collect_source_files(&root, true, 1).unwrap())
+    });
+    group.bench_function("all_cores", |b| {
+        b.iter(|| collect_source_files(&root, true, 0).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, walk);
+criterion_main!(benches);
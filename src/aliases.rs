@@ -0,0 +1,48 @@
+//! Type aliases (`type UserId = Uuid;`) and newtypes (`struct Port(u16);`)
+//! named in gathered signatures. They are tiny, but without them a model
+//! has to guess what a `UserId` argument actually is.
+
+use crate::index::ProjectIndex;
+use crate::{FunctionInfo, Language, TypeInfo};
+use regex::Regex;
+use std::collections::HashSet;
+
+/// The alias and newtype definitions used by the signatures of `gathered`,
+/// as a section to append after the code; empty when there are none.
+pub fn render(index: &ProjectIndex, gathered: &[&FunctionInfo]) -> String {
+    let small_types = small_types(index, gathered);
+    if small_types.is_empty() {
+        return String::new();
+    }
+
+    let mut output = String::from("\n=== TYPE ALIASES AND NEWTYPES ===\n");
+    for type_info in small_types {
+        output.push_str(&format!(
+            "\n=== {}:{} ===\n{}\n",
+            type_info.path.to_string_lossy(),
+            type_info.line_number,
+            type_info.definition
+        ));
+    }
+    output.push('\n');
+    output
+}
+
+/// The Rust aliases and newtypes named in the signatures of `gathered`.
+pub fn small_types<'a>(index: &'a ProjectIndex, gathered: &[&FunctionInfo]) -> Vec<&'a TypeInfo> {
+    let alias_regex =
+        Regex::new(r"(?m)^\s*(pub(\([^)]*\))?\s+)?type\s+[A-Za-z0-9_]+(<[^=]*>)?\s*=").unwrap();
+    let newtype_regex = Regex::new(
+        r"(?m)^\s*(pub(\([^)]*\))?\s+)?struct\s+[A-Za-z0-9_]+(<[^(]*>)?\s*\(([^,;]*),?\s*\)\s*(where[^;]*)?;",
+    )
+    .unwrap();
+
+    let mut seen = HashSet::new();
+    gathered
+        .iter()
+        .flat_map(|info| index.signature_types(info))
+        .filter(|t| t.language == Language::Rust)
+        .filter(|t| alias_regex.is_match(&t.definition) || newtype_regex.is_match(&t.definition))
+        .filter(|t| seen.insert((&t.path, t.line_number)))
+        .collect()
+}
@@ -0,0 +1,176 @@
+//! `api <crate>`: the public surface of one crate of the project, its
+//! crate-level documentation, public types and public function signatures,
+//! as a briefing before writing code that uses the crate.
+
+use crate::index::{flat_signature, without_preamble, ProjectIndex};
+use crate::{FunctionInfo, Language, TypeInfo};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The rendered briefing, with what it counted.
+pub struct Briefing {
+    pub text: String,
+    pub functions: usize,
+    pub types: usize,
+}
+
+/// The briefing for the crate `name` in `crate_dir`. Only the library
+/// sources under `src/` are public surface; binaries are left out when the
+/// crate has a `lib.rs`. Functions indexed with their `cfg` condition
+/// (`all_variants`) are labelled with it.
+pub fn render(index: &ProjectIndex, name: &str, crate_dir: &Path) -> Briefing {
+    let src = crate_dir.join("src");
+    let lib = src.join("lib.rs");
+    let has_lib = lib.is_file();
+    let in_library = |path: &Path| {
+        path.starts_with(&src)
+            && !(has_lib && (path == src.join("main.rs") || path.starts_with(src.join("bin"))))
+    };
+
+    let mut types: Vec<&TypeInfo> = index
+        .type_definitions
+        .values()
+        .flatten()
+        .filter(|t| t.language == Language::Rust && in_library(&t.path))
+        .filter(|t| is_public(&t.definition))
+        .collect();
+    types.sort_by(|a, b| (&a.path, a.line_number).cmp(&(&b.path, b.line_number)));
+
+    // Functions by module, and within a module by the type they belong to
+    let mut modules: BTreeMap<&str, BTreeMap<Option<&str>, Vec<&FunctionInfo>>> = BTreeMap::new();
+    let mut functions = 0;
+    for (qualified_name, info) in &index.function_definitions {
+        if info.language != Language::Rust
+            || info.is_test
+            || qualified_name.ends_with('!')
+            || !in_library(&info.path)
+            || !is_public(&info.definition)
+        {
+            continue;
+        }
+        modules
+            .entry(&info.module_path)
+            .or_default()
+            .entry(info.self_type.as_deref())
+            .or_default()
+            .push(info);
+        functions += 1;
+    }
+
+    let root = if has_lib { lib } else { src.join("main.rs") };
+    let mut text = format!("=== CRATE {} ({}) ===\n", name, crate_dir.display());
+    match crate_docs(&root) {
+        Some(docs) => text.push_str(&format!("{}\n", docs)),
+        None => text.push_str("(no crate-level documentation)\n"),
+    }
+
+    if !types.is_empty() {
+        text.push_str("\n=== PUBLIC TYPES ===\n");
+        for type_info in &types {
+            text.push_str(&format!(
+                "\n=== {}:{} ===\n{}{}\n",
+                type_info.path.to_string_lossy(),
+                type_info.line_number,
+                doc_lines(type_info.doc.as_deref(), ""),
+                type_info.definition
+            ));
+        }
+    }
+
+    if functions > 0 {
+        text.push_str("\n=== PUBLIC FUNCTIONS ===\n");
+    }
+    for (module, by_type) in &modules {
+        text.push_str(&format!("\n=== {} ===\n", module));
+        for (i, (self_type, infos)) in by_type.iter().enumerate() {
+            let mut infos = infos.clone();
+            infos.sort_by_key(|info| (&info.path, info.line_number));
+            let indent = if self_type.is_some() { "    " } else { "" };
+            if let Some(self_type) = self_type {
+                if i > 0 {
+                    text.push('\n');
+                }
+                text.push_str(&format!("impl {} {{\n", self_type));
+            }
+            for info in infos {
+                text.push_str(&doc_lines(info.doc.as_deref(), indent));
+                // The cfg variants of a function share its signature
+                if let Some(condition) = &info.cfg {
+                    text.push_str(&format!("{}#[{}]\n", indent, condition));
+                }
+                text.push_str(&format!("{}{};\n", indent, declaration(&info.definition)));
+            }
+            if self_type.is_some() {
+                text.push_str("}\n");
+            }
+        }
+    }
+
+    Briefing {
+        text,
+        functions,
+        types: types.len(),
+    }
+}
+
+/// Visible outside the crate: `pub`, not `pub(crate)` or `pub(super)`.
+fn is_public(definition: &str) -> bool {
+    without_preamble(definition)
+        .trim_start()
+        .starts_with("pub ")
+}
+
+/// A function's signature, or a global's name and type, on one line.
+fn declaration(definition: &str) -> String {
+    let code = without_preamble(definition).trim_start();
+    match code.strip_prefix("pub static ") {
+        Some(rest) => {
+            let declaration = rest
+                .split_once('=')
+                .map_or(rest, |(declaration, _)| declaration);
+            let declaration: Vec<&str> = declaration.split_whitespace().collect();
+            format!("pub static {}", declaration.join(" "))
+        }
+        None => flat_signature(definition),
+    }
+}
+
+/// A doc comment as `///` lines at `indent`.
+fn doc_lines(doc: Option<&str>, indent: &str) -> String {
+    doc.map(|doc| {
+        doc.lines()
+            .map(|line| match line {
+                "" => format!("{}///\n", indent),
+                line => format!("{}/// {}\n", indent, line),
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// The `//!` documentation at the top of the crate root, or the file an
+/// `#![doc = include_str!("...")]` attribute pulls in.
+fn crate_docs(root: &Path) -> Option<String> {
+    let content = fs::read_to_string(root).ok()?;
+    let inner: Vec<&str> = content
+        .lines()
+        .map(str::trim_start)
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| line.starts_with("//!"))
+        .map(|line| {
+            let text = &line["//!".len()..];
+            text.strip_prefix(' ').unwrap_or(text)
+        })
+        .collect();
+    if !inner.is_empty() {
+        return Some(inner.join("\n").trim().to_string());
+    }
+
+    let include_regex =
+        Regex::new(r#"#!\[doc\s*=\s*include_str!\(\s*"([^"]+)"\s*\)\s*\]"#).unwrap();
+    let included = include_regex.captures(&content)?;
+    let path: PathBuf = root.parent()?.join(&included[1]);
+    Some(fs::read_to_string(path).ok()?.trim().to_string())
+}
@@ -0,0 +1,72 @@
+//! Output files are written to a temporary file next to the target and
+//! renamed over it, so a reader never sees a half-written context document.
+//! `--output-lock` additionally holds an advisory lock while writing, for
+//! consumers that read several files (`--layered-output`) as one set.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Replaces `path` with `contents` in one step.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", path.display()),
+        )
+    })?;
+    // Same directory, so the rename never crosses file systems
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+
+    let written = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    match written.and_then(|_| fs::rename(&temp_path, path)) {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(err)
+        }
+    }
+}
+
+/// An exclusive advisory lock on `<path>.lock`, released on drop. Readers
+/// that want a consistent view take a shared lock on the same file
+/// (`flock -s out.md.lock cat out.md`).
+pub struct OutputLock {
+    file: File,
+}
+
+impl OutputLock {
+    /// Blocks until no other writer or locking reader holds the lock.
+    pub fn acquire(path: &Path) -> io::Result<OutputLock> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path(path))?;
+        file.lock()?;
+        Ok(OutputLock { file })
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// `out.md.lock` for `out.md`, and `layers.lock` next to a `layers/`
+/// directory.
+fn lock_path(path: &Path) -> PathBuf {
+    match path.file_name() {
+        Some(name) => path.with_file_name(format!("{}.lock", name.to_string_lossy())),
+        None => path.join(".lock"),
+    }
+}
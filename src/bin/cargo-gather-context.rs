@@ -0,0 +1,32 @@
+//! `cargo gather-context ...`: runs gather-context on the crate cargo was
+//! invoked in, so the project root never has to be typed.
+
+use std::env;
+use std::process::{self, Command};
+
+fn main() {
+    // Cargo passes the subcommand name as the first argument
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("gather-context") {
+        args.remove(0);
+    }
+
+    // Prefer the gather-context binary installed alongside this one
+    let sibling = env::current_exe().ok().and_then(|exe| {
+        let path = exe
+            .parent()?
+            .join(format!("gather-context{}", env::consts::EXE_SUFFIX));
+        path.exists().then_some(path)
+    });
+    let binary = sibling.unwrap_or_else(|| "gather-context".into());
+
+    let status = Command::new(&binary)
+        .args(&args)
+        .env("GATHER_CONTEXT_FROM_CARGO", "1")
+        .status()
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to run {}: {}", binary.display(), err);
+            process::exit(1);
+        });
+    process::exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,266 @@
+//! Token estimation and budget-driven traversal.
+
+use crate::index::ProjectIndex;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::Path;
+use tiktoken_rs::{cl100k_base, o200k_base, CoreBPE};
+
+/// Rough token count: about four characters per token for code.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// How tokens are counted, so budgets match the model the output is for.
+pub enum Tokenizer {
+    /// The four-characters-per-token heuristic
+    Chars,
+    /// OpenAI BPE encodings (`cl100k`, `o200k`)
+    Bpe(CoreBPE),
+    /// A Hugging Face `tokenizer.json`, as shipped with Llama models
+    HuggingFace(Box<tokenizers::Tokenizer>),
+}
+
+impl Tokenizer {
+    pub const NAMES: &'static [&'static str] = &["chars", "cl100k", "o200k", "llama"];
+
+    /// Loads a tokenizer by name; `llama` reads the `tokenizer.json` at
+    /// `file`, since its vocabulary is not bundled.
+    pub fn load(name: &str, file: Option<&Path>) -> Result<Tokenizer, String> {
+        match name {
+            "chars" => Ok(Tokenizer::Chars),
+            "cl100k" => cl100k_base().map(Tokenizer::Bpe).map_err(|e| e.to_string()),
+            "o200k" => o200k_base().map(Tokenizer::Bpe).map_err(|e| e.to_string()),
+            "llama" => {
+                let file =
+                    file.ok_or("--tokenizer llama needs --tokenizer-file <tokenizer.json>")?;
+                tokenizers::Tokenizer::from_file(file)
+                    .map(|t| Tokenizer::HuggingFace(Box::new(t)))
+                    .map_err(|e| format!("{}: {}", file.display(), e))
+            }
+            _ => Err(format!(
+                "Unknown tokenizer '{}'. Available: {}",
+                name,
+                Tokenizer::NAMES.join(", ")
+            )),
+        }
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Tokenizer::Chars => estimate_tokens(text),
+            Tokenizer::Bpe(bpe) => bpe.encode_with_special_tokens(text).len(),
+            Tokenizer::HuggingFace(tokenizer) => tokenizer
+                .encode(text, false)
+                .map(|encoding| encoding.len())
+                .unwrap_or_else(|_| estimate_tokens(text)),
+        }
+    }
+}
+
+/// Tokens a function costs in the output, including its section header.
+pub fn function_tokens(index: &ProjectIndex, tokenizer: &Tokenizer, qualified_name: &str) -> usize {
+    let info = &index.function_definitions[qualified_name];
+    let header = format!("\n=== {} ===\n", info.path.to_string_lossy());
+    tokenizer.count(&info.definition) + tokenizer.count(&header) + 1
+}
+
+/// The order in which the call graph is expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strategy {
+    /// Closest to the roots first: the breadth of what the target uses
+    #[default]
+    Bfs,
+    /// One call path to its end before the next: the depth of one path
+    Dfs,
+    /// Most called from the gathered functions first, then closest, then
+    /// smallest: the shared helpers everything leans on
+    BestFirst,
+}
+
+impl Strategy {
+    pub const NAMES: &'static [&'static str] = &["bfs", "dfs", "best-first"];
+
+    pub fn parse(name: &str) -> Option<Strategy> {
+        match name {
+            "bfs" => Some(Strategy::Bfs),
+            "dfs" => Some(Strategy::Dfs),
+            "best-first" => Some(Strategy::BestFirst),
+            _ => None,
+        }
+    }
+}
+
+/// Every function reachable from `roots` within `max_depth` calls, in the
+/// order `strategy` visits them. The set is the same for every strategy;
+/// the order decides what a later `truncate` keeps.
+pub fn traverse(
+    index: &ProjectIndex,
+    tokenizer: &Tokenizer,
+    roots: &[String],
+    max_depth: Option<usize>,
+    strategy: Strategy,
+) -> Vec<String> {
+    match strategy {
+        Strategy::Bfs => index.reachable_within(roots, max_depth),
+        _ => fill(index, tokenizer, roots, usize::MAX, max_depth, strategy).functions,
+    }
+}
+
+/// Result of filling a token budget.
+pub struct Filled {
+    pub functions: Vec<String>,
+    pub used_tokens: usize,
+    /// Reachable functions that did not fit
+    pub omitted: Vec<String>,
+}
+
+/// Expands the call graph from `roots` in priority order, adding every
+/// function that still fits, until the frontier is exhausted.
+///
+/// With `Strategy::Bfs`, priority favors functions closer to the roots, then
+/// functions called by more of the already included ones, then smaller
+/// functions so more of the budget is put to use. `BestFirst` puts the
+/// number of callers first, and `Dfs` the most recently reached function.
+/// The roots are always included, and nothing more than `max_depth` calls
+/// away from them is, so `used_tokens` only exceeds `budget` when the roots
+/// alone do.
+pub fn fill(
+    index: &ProjectIndex,
+    tokenizer: &Tokenizer,
+    roots: &[String],
+    budget: usize,
+    max_depth: Option<usize>,
+    strategy: Strategy,
+) -> Filled {
+    // Depth is the shortest distance from the roots whatever the order, so
+    // every strategy stays within the same `max_depth` reach
+    let depths = index.depths_within(roots, max_depth);
+    let mut frontier = Frontier {
+        heap: BinaryHeap::new(),
+        fan_in: HashMap::new(),
+        pushed: 0,
+        strategy,
+    };
+    let mut included: HashSet<String> = HashSet::new();
+    let mut skipped: HashSet<String> = HashSet::new();
+    let mut functions = Vec::new();
+    let mut used_tokens = 0;
+
+    for root in roots {
+        if index.function_definitions.contains_key(root) && included.insert(root.clone()) {
+            used_tokens += function_tokens(index, tokenizer, root);
+            functions.push(root.clone());
+        }
+    }
+    let within_depth = |depth: usize| max_depth.is_none_or(|max_depth| depth < max_depth);
+    for root in functions.iter().filter(|_| within_depth(0)) {
+        frontier.push_callees(index, tokenizer, root, &depths, &included);
+    }
+
+    while let Some((_, tokens, name)) = frontier.heap.pop() {
+        // Stale entries remain for functions whose priority was raised
+        if included.contains(&name) || skipped.contains(&name) {
+            continue;
+        }
+        if used_tokens + tokens > budget {
+            skipped.insert(name);
+            continue;
+        }
+
+        used_tokens += tokens;
+        included.insert(name.clone());
+        functions.push(name.clone());
+        if !within_depth(depths[&name]) {
+            continue;
+        }
+        frontier.push_callees(index, tokenizer, &name, &depths, &included);
+    }
+
+    let mut omitted: Vec<String> = skipped.into_iter().collect();
+    omitted.sort();
+
+    Filled {
+        functions,
+        used_tokens,
+        omitted,
+    }
+}
+
+/// Cuts a gather off at the first function that would take it over
+/// `budget`, keeping the traversal order (closest to the target first). The
+/// first function is always kept; everything from the cut on is omitted, in
+/// order.
+pub fn truncate(
+    index: &ProjectIndex,
+    tokenizer: &Tokenizer,
+    gathered_names: Vec<String>,
+    budget: usize,
+) -> Filled {
+    let mut functions = Vec::new();
+    let mut omitted = Vec::new();
+    let mut used_tokens = 0;
+
+    for name in gathered_names {
+        let tokens = function_tokens(index, tokenizer, &name);
+        if !omitted.is_empty() || (!functions.is_empty() && used_tokens + tokens > budget) {
+            omitted.push(name);
+            continue;
+        }
+        used_tokens += tokens;
+        functions.push(name);
+    }
+
+    Filled {
+        functions,
+        used_tokens,
+        omitted,
+    }
+}
+
+/// Functions waiting to be included, ordered by the strategy's priority.
+struct Frontier {
+    /// (priority, tokens, name); the heap pops the highest priority first
+    heap: BinaryHeap<((i64, i64, i64), usize, String)>,
+    fan_in: HashMap<String, usize>,
+    /// Entries pushed so far, which orders `Dfs` newest first
+    pushed: i64,
+    strategy: Strategy,
+}
+
+impl Frontier {
+    /// Queues the not yet included callees of `name` with updated fan-in.
+    fn push_callees(
+        &mut self,
+        index: &ProjectIndex,
+        tokenizer: &Tokenizer,
+        name: &str,
+        depths: &HashMap<String, usize>,
+        included: &HashSet<String>,
+    ) {
+        // Sorted, and reversed so a depth-first walk takes them in order
+        let mut callees: Vec<&String> = index
+            .function_calls
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|callee| !included.contains(*callee) && depths.contains_key(*callee))
+            .collect();
+        callees.sort_unstable_by(|a, b| b.cmp(a));
+
+        for callee in callees {
+            let count = self.fan_in.entry(callee.clone()).or_default();
+            *count += 1;
+            let fan_in = *count as i64;
+            let depth = depths[callee] as i64;
+            let tokens = function_tokens(index, tokenizer, callee);
+            let size = tokens as i64;
+            self.pushed += 1;
+            let priority = match self.strategy {
+                Strategy::Bfs => (-depth, fan_in, -size),
+                Strategy::BestFirst => (fan_in, -depth, -size),
+                Strategy::Dfs => (self.pushed, 0, 0),
+            };
+            self.heap.push((priority, tokens, callee.clone()));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! The on-disk index in `.gather-context/index.bin`: what each source file
+//! was parsed into, keyed by its modification time, size and content hash,
+//! so a later build only re-parses the files that changed. Call resolution
+//! still runs over the whole project, since one changed file can change
+//! how calls in any other resolve.
+
+use crate::atomic;
+use crate::lang::{self, ExtractOptions, FileItems};
+use crate::lock::content_hash;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Directory at the project root holding the index.
+pub const DIR: &str = ".gather-context";
+pub const FILE_NAME: &str = "index.bin";
+/// Bumped whenever extraction changes what a source file parses into, so
+/// items stored by an earlier layout are parsed again.
+const ITEMS_VERSION: u32 = 14;
+
+/// Parsed source files, reused while they are unchanged.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FileCache {
+    /// The project root as given, and the build and extraction options the
+    /// items were parsed with; any difference invalidates all of them
+    format: String,
+    files: HashMap<PathBuf, CachedFile>,
+    #[serde(skip)]
+    changed: bool,
+    #[serde(skip)]
+    reparsed: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: String,
+    module_path: String,
+    items: FileItems,
+}
+
+/// Where the index of `project_root` is stored.
+pub fn path_for(project_root: &Path) -> PathBuf {
+    project_root.join(DIR).join(FILE_NAME)
+}
+
+impl FileCache {
+    /// The stored index, or an empty one when there is none or it was
+    /// written by another version or with other extraction options.
+    pub fn load(project_root: &Path, options: &ExtractOptions) -> FileCache {
+        let format = format_key(project_root, options);
+        let stored = fs::read(path_for(project_root)).ok().and_then(|bytes| {
+            bincode::serde::decode_from_slice::<FileCache, _>(&bytes, bincode::config::standard())
+                .ok()
+        });
+        match stored {
+            Some((cache, _)) if cache.format == format => cache,
+            _ => FileCache {
+                format,
+                changed: true,
+                ..FileCache::default()
+            },
+        }
+    }
+
+    /// What `path` parses into: the stored items when the file is unchanged,
+    /// otherwise freshly extracted ones, which replace them.
+    pub fn items(
+        &mut self,
+        path: &Path,
+        module_path: &str,
+        options: &ExtractOptions,
+    ) -> io::Result<FileItems> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified().ok();
+        if let Some(cached) = self.files.get(path) {
+            if cached.module_path == module_path
+                && cached.len == metadata.len()
+                && modified.is_some()
+                && cached.modified == modified
+            {
+                return Ok(cached.items.clone());
+            }
+        }
+
+        // Touched but identical files (a checkout, a formatter run that
+        // changed nothing) are still reused
+        let content = lang::read_source(path)?;
+        let hash = content_hash(&content);
+        if let Some(cached) = self.files.get_mut(path) {
+            if cached.module_path == module_path && cached.hash == hash {
+                cached.modified = modified;
+                cached.len = metadata.len();
+                self.changed = true;
+                return Ok(cached.items.clone());
+            }
+        }
+
+        let items = lang::extract(&content, path, module_path, options);
+        self.files.insert(
+            path.to_path_buf(),
+            CachedFile {
+                modified,
+                len: metadata.len(),
+                hash,
+                module_path: module_path.to_string(),
+                items: items.clone(),
+            },
+        );
+        self.changed = true;
+        self.reparsed += 1;
+        Ok(items)
+    }
+
+    /// Files parsed rather than taken from the stored index.
+    pub fn reparsed(&self) -> usize {
+        self.reparsed
+    }
+
+    /// Writes the index if anything in it changed, dropping files that no
+    /// longer exist. Returns where it was written.
+    pub fn save(&mut self, project_root: &Path) -> io::Result<Option<PathBuf>> {
+        let before = self.files.len();
+        self.files.retain(|path, _| path.is_file());
+        if !self.changed && self.files.len() == before {
+            return Ok(None);
+        }
+
+        let path = path_for(project_root);
+        fs::create_dir_all(project_root.join(DIR))?;
+        let bytes = bincode::serde::encode_to_vec(&*self, bincode::config::standard())
+            .map_err(io::Error::other)?;
+        atomic::write(&path, bytes)?;
+        self.changed = false;
+        Ok(Some(path))
+    }
+}
+
+/// Identifies what produced the items: the root their paths start with, the
+/// tool and item layout versions, the parser backends it was built with and
+/// the extraction options.
+fn format_key(project_root: &Path, options: &ExtractOptions) -> String {
+    format!(
+        "{} {} items={} syn={} regex={} combinators={} rust={:?}",
+        project_root.display(),
+        env!("CARGO_PKG_VERSION"),
+        ITEMS_VERSION,
+        cfg!(feature = "syn-backend"),
+        cfg!(feature = "regex-backend"),
+        options.expand_combinators,
+        options.rust_backend
+    )
+}
@@ -0,0 +1,342 @@
+//! Support for running as `cargo gather-context`, where the project root
+//! comes from cargo instead of the command line.
+
+use crate::{lang, FunctionInfo};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Asks cargo for the manifest of the crate containing the working
+/// directory (or `manifest_path`), and returns the directory holding it.
+pub fn locate_project(manifest_path: Option<&str>) -> io::Result<PathBuf> {
+    // Cargo tells subcommands which cargo binary invoked them
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+
+    let mut command = Command::new(cargo);
+    command.args(["locate-project", "--message-format", "plain"]);
+    if let Some(manifest_path) = manifest_path {
+        command.args(["--manifest-path", manifest_path]);
+    }
+
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let manifest = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(manifest
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(".")))
+}
+
+/// The crate name `manifest` declares if it is a proc-macro crate, with `-`
+/// turned into `_` as in paths.
+pub fn proc_macro_crate_name(manifest: &Path) -> Option<String> {
+    let text = fs::read_to_string(manifest).ok()?;
+    let proc_macro = Regex::new(r"(?m)^\s*proc[-_]macro\s*=\s*true").unwrap();
+    if !proc_macro.is_match(&text) {
+        return None;
+    }
+
+    let package = text.split("[package]").nth(1)?;
+    let package = package.split("\n[").next().unwrap_or(package);
+    let name = Regex::new(r#"(?m)^\s*name\s*=\s*"([^"]+)""#).unwrap();
+    let captures = name.captures(package)?;
+    Some(captures[1].replace('-', "_"))
+}
+
+/// The name `manifest`'s library is used by in paths, as its binaries and
+/// tests refer to it: `[lib] name`, else the package name with `-` turned
+/// into `_`.
+pub fn library_name(manifest: &Path) -> Option<String> {
+    let table = read_manifest(manifest)?;
+    let lib_name = table.get("lib").and_then(|lib| lib.get("name")?.as_str());
+    match lib_name {
+        Some(name) => Some(name.replace('-', "_")),
+        None => package_name(&table),
+    }
+}
+
+/// The name `cargo --bin` selects `manifest`'s `src/main.rs` by: the
+/// package name as written.
+pub fn binary_name(manifest: &Path) -> Option<String> {
+    let table = read_manifest(manifest)?;
+    Some(table.get("package")?.get("name")?.as_str()?.to_string())
+}
+
+/// The crates of the project at `root`, as (package name, directory): the
+/// root package, if any, and the members its `[workspace]` lists, with
+/// `crates/*` globs expanded. Names have `-` turned into `_`.
+pub fn workspace_members(root: &Path) -> Vec<(String, PathBuf)> {
+    let Some(table) = read_manifest(&root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let mut members = Vec::new();
+    if let Some(name) = package_name(&table) {
+        members.push((name, root.to_path_buf()));
+    }
+
+    let workspace = table.get("workspace");
+    let patterns = |key: &str| -> Vec<String> {
+        workspace
+            .and_then(|w| w.get(key))
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|member| member.as_str().map(str::to_string))
+            .collect()
+    };
+    let excluded: Vec<PathBuf> = patterns("exclude")
+        .iter()
+        .map(|path| root.join(path))
+        .collect();
+    let mut dirs = Vec::new();
+    for pattern in patterns("members") {
+        // Only the last path element may be a glob, as in `crates/*`
+        match pattern.rsplit_once('/') {
+            Some((parent, last)) if last.contains('*') => {
+                let matcher =
+                    Regex::new(&format!("^{}$", regex::escape(last).replace(r"\*", ".*"))).unwrap();
+                let Ok(entries) = fs::read_dir(root.join(parent)) else {
+                    continue;
+                };
+                let mut found: Vec<PathBuf> = entries
+                    .flatten()
+                    .filter(|entry| matcher.is_match(&entry.file_name().to_string_lossy()))
+                    .map(|entry| root.join(parent).join(entry.file_name()))
+                    .collect();
+                found.sort();
+                dirs.extend(found);
+            }
+            _ => dirs.push(root.join(&pattern)),
+        }
+    }
+    for dir in dirs {
+        if excluded.contains(&dir) {
+            continue;
+        }
+        if let Some(name) = read_manifest(&dir.join("Cargo.toml")).and_then(|t| package_name(&t)) {
+            members.push((name, dir));
+        }
+    }
+    members
+}
+
+/// The dependencies `manifest` declares, normal, dev and build, as the
+/// name paths use (`-` turned into `_`, renames applied) to the package
+/// name.
+pub fn dependency_packages(manifest: &Path) -> HashMap<String, String> {
+    let Some(table) = read_manifest(manifest) else {
+        return HashMap::new();
+    };
+    let mut packages = HashMap::new();
+    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        let Some(dependencies) = table.get(section).and_then(toml::Value::as_table) else {
+            continue;
+        };
+        for (name, value) in dependencies {
+            let package = value
+                .get("package")
+                .and_then(toml::Value::as_str)
+                .unwrap_or(name);
+            packages.insert(name.replace('-', "_"), package.to_string());
+        }
+    }
+    packages
+}
+
+/// The version of `package` that the `Cargo.lock` above `manifest` locks
+/// for the crate `manifest` declares: the only locked version, or the one
+/// the crate's own lock entry names when several are.
+pub fn locked_version(manifest: &Path, package: &str) -> Option<String> {
+    let lock = manifest
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lock| lock.is_file())?;
+    let table: toml::Table = fs::read_to_string(lock).ok()?.parse().ok()?;
+    let entries = table.get("package")?.as_array()?;
+    let field = |entry: &toml::Value, key: &str| entry.get(key)?.as_str().map(str::to_string);
+
+    let versions: Vec<String> = entries
+        .iter()
+        .filter(|entry| field(entry, "name").as_deref() == Some(package))
+        .filter_map(|entry| field(entry, "version"))
+        .collect();
+    if let [version] = versions.as_slice() {
+        return Some(version.clone());
+    }
+
+    // Cargo writes `name version` for a dependency locked at several
+    let own_name = read_manifest(manifest)?
+        .get("package")?
+        .get("name")?
+        .as_str()?
+        .to_string();
+    let own = entries
+        .iter()
+        .find(|entry| field(entry, "name").as_deref() == Some(own_name.as_str()))?;
+    own.get("dependencies")?
+        .as_array()?
+        .iter()
+        .filter_map(toml::Value::as_str)
+        .find_map(|dependency| {
+            let (name, version) = dependency.split_once(' ')?;
+            (name == package).then(|| version.to_string())
+        })
+        .or_else(|| versions.last().cloned())
+}
+
+fn read_manifest(path: &Path) -> Option<toml::Table> {
+    fs::read_to_string(path).ok()?.parse().ok()
+}
+
+fn package_name(table: &toml::Table) -> Option<String> {
+    let name = table.get("package")?.get("name")?.as_str()?;
+    Some(name.replace('-', "_"))
+}
+
+/// `--with-manifest`: for each crate the gathered functions belong to, its
+/// name, edition and the dependencies the gathered code actually uses, so
+/// library versions are known.
+pub fn manifest_context(gathered: &[&FunctionInfo]) -> String {
+    // Group the gathered functions by the manifest that owns their file
+    let mut manifests: Vec<(PathBuf, Vec<&FunctionInfo>)> = Vec::new();
+    for info in gathered {
+        let Some(manifest) = owning_manifest(&info.path) else {
+            continue;
+        };
+        match manifests.iter_mut().find(|(path, _)| *path == manifest) {
+            Some((_, functions)) => functions.push(info),
+            None => manifests.push((manifest, vec![info])),
+        }
+    }
+    let mut file_contents: HashMap<&Path, String> = HashMap::new();
+
+    let mut output = String::new();
+    for (manifest, functions) in manifests {
+        let Some(table) = fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|text| text.parse::<toml::Table>().ok())
+        else {
+            continue;
+        };
+        let workspace = workspace_manifest(&manifest);
+
+        output.push_str(&format!("# {}\n[package]\n", manifest.display()));
+        let package = table.get("package").and_then(toml::Value::as_table);
+        for key in ["name", "edition", "rust-version"] {
+            if let Some(value) = package.and_then(|p| p.get(key)) {
+                let value = inherit(value, key, workspace.as_ref(), "package");
+                output.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+
+        for section in ["dependencies", "dev-dependencies"] {
+            let Some(dependencies) = table.get(section).and_then(toml::Value::as_table) else {
+                continue;
+            };
+            let used: Vec<(&String, &toml::Value)> = dependencies
+                .iter()
+                .filter(|(name, _)| {
+                    functions.iter().any(|info| {
+                        let content = file_contents
+                            .entry(&info.path)
+                            .or_insert_with(|| lang::read_source(&info.path).unwrap_or_default());
+                        uses_dependency(info, content, name)
+                    })
+                })
+                .collect();
+            if used.is_empty() {
+                continue;
+            }
+            output.push_str(&format!("\n[{}]\n", section));
+            for (name, value) in used {
+                let value = inherit(value, name, workspace.as_ref(), "dependencies");
+                output.push_str(&format!("{} = {}\n", name, value));
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Whether a function refers to the crate `dependency`, by path
+/// (`serde_json::to_string`) or through a name its file imports from it.
+fn uses_dependency(info: &FunctionInfo, file_content: &str, dependency: &str) -> bool {
+    let crate_name = dependency.replace('-', "_");
+    if info.definition.contains(&format!("{}::", crate_name)) {
+        return true;
+    }
+
+    let import = Regex::new(&format!(
+        r"\buse\s+{}::([^;]+);",
+        regex::escape(&crate_name)
+    ))
+    .unwrap();
+    let words: HashSet<&str> = info
+        .definition
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .collect();
+    let imported = import.captures_iter(file_content).any(|captures| {
+        captures
+            .get(1)
+            .unwrap()
+            .as_str()
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|name| !name.is_empty() && name != "self" && words.contains(name))
+    });
+    imported
+}
+
+/// The nearest `Cargo.toml` above `path`.
+pub fn owning_manifest(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+}
+
+/// The workspace manifest `manifest` belongs to: itself or an ancestor
+/// declaring `[workspace]`.
+fn workspace_manifest(manifest: &Path) -> Option<toml::Table> {
+    manifest
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.toml"))
+        .filter(|candidate| candidate.is_file())
+        .filter_map(|candidate| fs::read_to_string(candidate).ok())
+        .filter_map(|text| text.parse::<toml::Table>().ok())
+        .find(|table| table.contains_key("workspace"))
+}
+
+/// Replaces `{ workspace = true }` values with what the workspace declares
+/// for `key` under `[workspace.<section>]`.
+fn inherit(
+    value: &toml::Value,
+    key: &str,
+    workspace: Option<&toml::Table>,
+    section: &str,
+) -> toml::Value {
+    let inherits = value
+        .as_table()
+        .and_then(|t| t.get("workspace"))
+        .and_then(toml::Value::as_bool)
+        == Some(true);
+    if !inherits {
+        return value.clone();
+    }
+    workspace
+        .and_then(|w| w.get("workspace"))
+        .and_then(|w| w.get(section))
+        .and_then(|s| s.get(key))
+        .cloned()
+        .unwrap_or_else(|| value.clone())
+}
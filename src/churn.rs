@@ -0,0 +1,97 @@
+//! Change frequency ("churn") of gathered functions from git history: how
+//! many commits touched each function's lines in the last months, so code
+//! under active change can be given extra attention and stable code can be
+//! skimmed.
+
+use crate::index::ProjectIndex;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+
+/// Counts the commits of the last `months` months that touched each of
+/// `names` and records them on the index for the output headers. Functions
+/// git can't follow (untracked files, or lines past the committed file)
+/// are left unannotated; returns a note saying how many, if any.
+pub fn annotate(
+    index: &mut ProjectIndex,
+    project_root: &Path,
+    names: &[String],
+    months: u32,
+) -> Option<String> {
+    let ranges: Vec<(&String, String)> = names
+        .iter()
+        .map(|name| {
+            let info = &index.function_definitions[name];
+            let relative = info.path.strip_prefix(project_root).unwrap_or(&info.path);
+            let end_line = info.line_number + info.definition.lines().count().max(1) - 1;
+            let range = format!(
+                "{},{}:{}",
+                info.line_number,
+                end_line,
+                relative.to_string_lossy()
+            );
+            (name, range)
+        })
+        .collect();
+
+    // Each function is its own `git log -L`, which walks the history
+    let workers = thread::available_parallelism().map_or(4, usize::from);
+    let chunk_size = ranges.len().div_ceil(workers).max(1);
+    let counts: Vec<(String, Option<usize>)> = thread::scope(|scope| {
+        let handles: Vec<_> = ranges
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(name, range)| {
+                            (name.to_string(), commit_count(project_root, range, months))
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    let unknown = counts.iter().filter(|(_, count)| count.is_none()).count();
+    let note = if unknown == counts.len() && !counts.is_empty() {
+        Some("No git history found; churn is unknown".to_string())
+    } else if unknown > 0 {
+        Some(format!(
+            "No git history for {} of the gathered functions",
+            unknown
+        ))
+    } else {
+        None
+    };
+    for (name, count) in counts {
+        if let Some(info) = index.function_definitions.get_mut(&name) {
+            info.churn = count;
+        }
+    }
+    note
+}
+
+/// Commits since `months` months ago touching `range` (`start,end:path`).
+fn commit_count(project_root: &Path, range: &str, months: u32) -> Option<usize> {
+    let output = Command::new("git")
+        .current_dir(project_root)
+        .arg("log")
+        .arg(format!("--since={} months ago", months))
+        .args(["--format=%H", "--no-patch", "-L", range])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .count(),
+    )
+}
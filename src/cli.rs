@@ -0,0 +1,703 @@
+//! Command-line interface: one subcommand per task, each taking the project
+//! root first and everything else as named flags.
+
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use gather_context::budget::Strategy;
+use gather_context::graph::GraphFormat;
+use gather_context::prompt::PromptPreset;
+use gather_context::seed::{Combine, Seed};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "gather-context",
+    version,
+    about = "Extract function call trees from Rust (and Swift, Objective-C, TypeScript, JavaScript, Go and C#) projects",
+    long_about = "Extract function call trees from Rust projects.\n\
+                  Also follows Swift (.swift) and Objective-C (.m) call sites into Rust FFI, \
+                  and indexes TypeScript and JavaScript, Go (.go) and C# (.cs) sources.",
+    after_help = "Inside a crate, `cargo gather-context <command> <args without project_root>` \
+                  detects the project root (--manifest-path <Cargo.toml> selects another crate)."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+    /// Take the first of several functions sharing a name instead of asking
+    /// which one (only asked when stdin is a terminal)
+    #[arg(long, global = true)]
+    pub non_interactive: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Gather a function's definition and everything it calls, transitively
+    Gather(GatherArgs),
+    /// Gather the functions that call the target, transitively
+    Callers(GatherArgs),
+    /// Show why a gather includes a function: the call chain from the
+    /// root, the rule behind each edge, and its priority
+    Explain(ExplainArgs),
+    /// Gather the combined context of the functions an issue names in code
+    /// spans, backtraces or file:line locations
+    FromIssue(FromIssueArgs),
+    /// Build the index, store it in .gather-context/index.bin for later runs,
+    /// and summarize what it found
+    Index(IndexArgs),
+    /// List the indexed functions by the name a gather accepts
+    List(ListArgs),
+    /// Print project metrics: file and function counts, the most called and
+    /// largest functions, and the functions nothing calls
+    Stats(StatsArgs),
+    /// List functions whose qualified name or signature (or body, with
+    /// --bodies) matches, by the name a gather accepts
+    Search(SearchArgs),
+    /// Report functions reachable from one root set but not the other
+    Compare(CompareArgs),
+    /// List the test functions whose call trees reach a function, closest
+    /// first
+    Impact(ImpactArgs),
+    /// Gather input types, constructors and Arbitrary/serde impls needed to
+    /// write a cargo-fuzz target
+    FuzzPrep(FuzzPrepArgs),
+    /// Emit public functions with their callees and docs as JSONL
+    Dataset(DatasetArgs),
+    /// Summarize a crate's public API: its crate-level docs, public types
+    /// and public function signatures
+    Api(ApiArgs),
+    /// Measure call-edge precision/recall against rust-analyzer
+    Validate(ValidateArgs),
+    /// Merge gathers saved with --json, dropping duplicate functions
+    Compose(ComposeArgs),
+    /// Report which functions of a saved context changed, were removed or
+    /// call new functions since, from its manifest
+    ChangedSince(ChangedSinceArgs),
+    /// Regenerate the context recorded with --lock, failing if any gathered
+    /// function was added, removed or changed since
+    Replay(ReplayArgs),
+    /// Serve newline-delimited JSON-RPC on stdin/stdout for editor plugins
+    /// and agents, over one or more projects (gather, functions,
+    /// fileChanged, register, projects, $/cancelRequest)
+    Rpc(RpcArgs),
+}
+
+impl Command {
+    /// Names of all subcommands, to tell them from a legacy invocation.
+    pub fn names() -> Vec<String> {
+        Cli::command()
+            .get_subcommands()
+            .map(|command| command.get_name().to_string())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct GatherArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Function to start from; not needed with --seed, --doc-match, --fn or
+    /// --at
+    #[arg(required_unless_present_any = ["seeds", "doc_matches", "functions", "at"])]
+    pub function: Option<String>,
+    /// More functions to start from, comma-separated or repeated; their
+    /// trees are merged and shared callees appear once
+    #[arg(long = "fn", value_name = "FUNCTION", value_delimiter = ',')]
+    pub functions: Vec<String>,
+    /// Start from the function enclosing this line of a file (relative to
+    /// the project root, or absolute); repeatable like --fn
+    #[arg(long, value_name = "FILE:LINE", value_parser = parse_location)]
+    pub at: Vec<(String, usize)>,
+    /// Module to prefer when several functions share the name
+    #[arg(short, long)]
+    pub module: Option<String>,
+    #[command(flatten)]
+    pub traversal: TraversalArgs,
+    #[command(flatten)]
+    pub output: OutputArgs,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ExplainArgs {
+    #[command(flatten)]
+    pub gather: GatherArgs,
+    /// The gathered function to explain
+    #[arg(long, value_name = "FUNCTION")]
+    pub why: String,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct FromIssueArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Issue or pull request URL, any other URL, or a local file
+    pub source: String,
+    #[command(flatten)]
+    pub traversal: TraversalArgs,
+    #[command(flatten)]
+    pub output: OutputArgs,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+/// Where a gather starts and how far it goes.
+#[derive(Debug, Clone, Args)]
+pub struct TraversalArgs {
+    /// Start from a function's tree, or the trees of all functions using a
+    /// type or whose doc comment matches; repeatable, replaces the function
+    /// argument
+    #[arg(long = "seed", value_name = "fn:NAME|type:NAME|doc:REGEX", value_parser = parse_seed)]
+    pub seeds: Vec<Seed>,
+    /// Start from the trees of the functions whose doc comments match
+    /// REGEX, ignoring case (same as --seed doc:REGEX); repeatable
+    #[arg(long = "doc-match", value_name = "REGEX")]
+    pub doc_matches: Vec<String>,
+    /// How multiple seeds are merged
+    #[arg(long, value_name = "union|intersect", default_value = "union", value_parser = parse_combine)]
+    pub combine: Combine,
+    /// Follow at most N calls from the target (default: unlimited); with
+    /// --seed, from each seed
+    #[arg(long, value_name = "N")]
+    pub depth: Option<usize>,
+    /// The order the call graph is walked in: bfs (closest first), dfs (one
+    /// call path at a time) or best-first (most-called first); decides what
+    /// --fill-budget and --max-tokens keep
+    #[arg(long, value_name = "bfs|dfs|best-first", default_value = "bfs", value_parser = parse_strategy)]
+    pub strategy: Strategy,
+    /// Expand the call graph in --strategy order (by default closest,
+    /// most-called, smallest first) until the token budget is filled
+    #[arg(long, value_name = "TOKENS", conflicts_with = "max_tokens")]
+    pub fill_budget: Option<usize>,
+    /// Stop the traversal at the first function that would exceed N tokens,
+    /// listing what was cut off
+    #[arg(long, value_name = "N")]
+    pub max_tokens: Option<usize>,
+    /// Report tokens per module when the gather exceeds N tokens, with
+    /// --within/--exclude-module flags that would fit it
+    #[arg(long, value_name = "N")]
+    pub budget: Option<usize>,
+    /// Keep only functions in MODULE or its submodules (repeatable)
+    #[arg(long, value_name = "MODULE")]
+    pub within: Vec<String>,
+    /// Drop functions in MODULE or its submodules (repeatable)
+    #[arg(long, value_name = "MODULE")]
+    pub exclude_module: Vec<String>,
+    /// How budgets and --dry-run count tokens (chars: about four characters
+    /// per token)
+    #[arg(long, value_name = "chars|cl100k|o200k|llama", default_value = "chars")]
+    pub tokenizer: String,
+    /// Hugging Face tokenizer file for --tokenizer llama
+    #[arg(long, value_name = "tokenizer.json")]
+    pub tokenizer_file: Option<PathBuf>,
+}
+
+/// What is written and where.
+#[derive(Debug, Clone, Args)]
+pub struct OutputArgs {
+    /// Write the output to FILE instead of stdout
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub file: Option<PathBuf>,
+    /// Prepend instructions for an LLM task
+    #[arg(long, value_name = "review|refactor|test-gen|explain", value_parser = parse_prompt)]
+    pub prompt: Option<PromptPreset>,
+    /// Leave out the definitions of the structs, enums and traits the
+    /// gathered functions use
+    #[arg(long)]
+    pub no_types: bool,
+    /// Also include the types used by the fields and variants of included
+    /// types, transitively (3 levels unless --type-depth is given)
+    #[arg(long)]
+    pub type_closure: bool,
+    /// How many levels --type-closure follows; implies it
+    #[arg(long, value_name = "N")]
+    pub type_depth: Option<usize>,
+    /// Start with a table of contents of the gathered functions by file,
+    /// with line counts, and mark each function with its call depth
+    #[arg(long)]
+    pub outline: bool,
+    /// Before writing the output, show the gathered functions as a call
+    /// tree in the terminal and deselect the subtrees to leave out
+    #[arg(long)]
+    pub interactive_prune: bool,
+    /// Start with one-line definitions of the modules, types and functions
+    /// in the context, taken from their doc comments
+    #[arg(long)]
+    pub glossary: bool,
+    /// Start with the crate name, edition and the Cargo.toml dependencies
+    /// the gathered code uses
+    #[arg(long)]
+    pub with_manifest: bool,
+    /// Introduce each module with its README.md or
+    /// #[doc = include_str!(...)] document
+    #[arg(long)]
+    pub module_docs: bool,
+    /// Append one-line signatures of called functions the output leaves out
+    #[arg(long)]
+    pub stubs: bool,
+    /// Append the signatures and doc comments of the dependency functions
+    /// the gathered code calls by path, from the crate sources in Cargo's
+    /// registry
+    #[arg(long)]
+    pub with_deps: bool,
+    /// Append every panic!, unwrap, expect and todo! site in the gathered
+    /// code with its location and message
+    #[arg(long)]
+    pub panics: bool,
+    /// Append the open questions the context leaves (calls into code that
+    /// isn't included, guessed resolutions, #[cfg] code, TODOs) phrased as
+    /// prompts
+    #[arg(long)]
+    pub questions: bool,
+    /// Show functions that only forward to another project function as one
+    /// line naming it, and spend the budget on the implementation
+    #[arg(long)]
+    pub inline_thin_wrappers: bool,
+    /// Write layer0.md (target), layer1.md (direct callees) and layer2.md
+    /// (everything deeper) instead of one output
+    #[arg(long, value_name = "DIR")]
+    pub layered_output: Option<PathBuf>,
+    /// Export the gathered call graph instead of code, colored by distance
+    /// from the target and sized by lines of code
+    #[arg(long, value_name = "dot|mermaid|html", value_parser = parse_graph_format)]
+    pub graph: Option<GraphFormat>,
+    /// Output format: a --graph format, or markdown for each function in a
+    /// fenced code block under a heading with its name and location
+    #[arg(
+        long,
+        value_name = "dot|mermaid|html|markdown",
+        value_parser = parse_format,
+        conflicts_with = "graph"
+    )]
+    pub format: Option<OutputFormat>,
+    /// Write the gathered functions (name, location, hash, definition) and
+    /// the rendered context as a JSON fragment for `compose`
+    #[arg(long)]
+    pub json: bool,
+    /// List the functions that would be included with their line and token
+    /// counts and the tokens per module, without generating the output
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Annotate each function with the number of commits that touched it in
+    /// the last MONTHS months, from git history
+    #[arg(long, value_name = "MONTHS")]
+    pub churn: Option<u32>,
+    /// List the calls that resolve to no project function in the summary
+    #[arg(long)]
+    pub show_unresolved: bool,
+    /// Don't print the summary and next-step hints after the output
+    #[arg(long)]
+    pub no_summary: bool,
+    /// Don't write <output>.manifest.json next to an output file
+    #[arg(long)]
+    pub no_context_manifest: bool,
+    /// Hold an advisory lock on <output>.lock while writing, for readers
+    /// that take a shared lock on it
+    #[arg(long)]
+    pub output_lock: bool,
+    /// Record the arguments, resolved functions and their content hashes
+    /// for a later `replay`
+    #[arg(long, value_name = "FILE")]
+    pub lock: Option<PathBuf>,
+    /// Keep running after writing the output file, and rewrite it whenever a
+    /// file holding a gathered function changes
+    #[arg(long)]
+    pub watch: bool,
+}
+
+/// How the project is indexed.
+#[derive(Debug, Clone, Default, Args)]
+pub struct IndexFlags {
+    /// Only index files owned by this CODEOWNERS owner
+    #[arg(long, value_name = "OWNER")]
+    pub owned_by: Option<String>,
+    /// Annotate each function with its CODEOWNERS owners
+    #[arg(long)]
+    pub show_owners: bool,
+    /// Follow functions passed by name to map/and_then/for_each/...
+    #[arg(long)]
+    pub expand_combinators: bool,
+    /// Follow operator sugar (a + b, map[key], *ptr) on project types to
+    /// their Add/Index/Deref/... impl methods
+    #[arg(long)]
+    pub operators: bool,
+    /// Only follow trait method calls to the implementations for these
+    /// types (comma-separated, e.g. FileWriter,S3Writer); all by default
+    #[arg(long, value_name = "TYPES", value_delimiter = ',')]
+    pub impls: Vec<String>,
+    /// Gather every #[cfg] variant of a function (unix and windows
+    /// versions), each labeled with its condition
+    #[arg(long)]
+    pub all_variants: bool,
+    /// Also index files excluded by .gitignore/.ignore and target/,
+    /// vendor/, bin/, obj/, node_modules/ directories
+    #[arg(long)]
+    pub no_ignore: bool,
+    /// Only index files matching GLOB, relative to the project root
+    /// (repeatable, e.g. 'src/**')
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Don't index files matching GLOB (repeatable, e.g. 'tests/**')
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// Only index files whose license (SPDX header, package license or
+    /// LICENSE file) is one of these SPDX identifiers (comma-separated, e.g.
+    /// MIT,Apache-2.0); files with no license found are indexed and reported
+    #[arg(long, value_name = "LICENSES", value_delimiter = ',')]
+    pub license_allow: Vec<String>,
+    /// Also index the functions macros generate (macro_rules!, derives are
+    /// left out), from `cargo expand` output mapped back to the invoking
+    /// files; needs cargo-expand and a crate that compiles
+    #[arg(long)]
+    pub expand: bool,
+    /// The Cargo profile `--expand` builds under (`release`, `bench` or a
+    /// custom one), which decides `cfg(debug_assertions)`; dev by default
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// Don't follow calls to functions named NAME in this run, as
+    /// `[calls] skip` in .gather-context.toml does (comma-separated or
+    /// repeated); counted toward --learned-excludes
+    #[arg(long, value_name = "NAME", value_delimiter = ',')]
+    pub ban: Vec<String>,
+    /// Also skip calls to the functions this project's runs have left out
+    /// with --ban or --interactive-prune at least 3 times
+    #[arg(long)]
+    pub learned_excludes: bool,
+    /// Parse every file instead of reusing .gather-context/index.bin
+    #[arg(long)]
+    pub no_cache: bool,
+    /// List every source file left out of the index (ignored, in a build
+    /// output directory, not owned, no backend) or only partly indexed
+    /// (generated, invalid UTF-8), with the reason
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct IndexArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Print the summary as JSON
+    #[arg(long)]
+    pub json: bool,
+    /// Also write the indexed functions and types to FILE in universal-ctags
+    /// format, for editors and other tools
+    #[arg(long, value_name = "FILE")]
+    pub export_tags: Option<PathBuf>,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ListArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Only functions in MODULE or its submodules
+    #[arg(long, value_name = "MODULE")]
+    pub within: Option<String>,
+    /// Include test functions
+    #[arg(long)]
+    pub tests: bool,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct StatsArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// How many functions to show in the most called and largest rankings
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub top: usize,
+    /// Rank test functions too
+    #[arg(long)]
+    pub tests: bool,
+    /// Print the metrics as JSON
+    #[arg(long)]
+    pub json: bool,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SearchArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Regular expression; (?i) makes it case-insensitive
+    pub pattern: String,
+    /// Also search function bodies
+    #[arg(long)]
+    pub bodies: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CompareArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Root functions of the first call tree
+    #[arg(long, value_name = "FN,...", value_delimiter = ',', required = true)]
+    pub roots_a: Vec<String>,
+    /// Root functions of the second call tree
+    #[arg(long, value_name = "FN,...", value_delimiter = ',', required = true)]
+    pub roots_b: Vec<String>,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ImpactArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Function whose covering tests to list
+    pub function: String,
+    /// Module to prefer when several functions share the name
+    #[arg(short, long)]
+    pub module: Option<String>,
+    /// Print the tests as JSON, for test selection in CI
+    #[arg(long)]
+    pub json: bool,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct FuzzPrepArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Function the fuzz target will call
+    pub function: String,
+    /// Module to prefer when several functions share the name
+    #[arg(short, long)]
+    pub module: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DatasetArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Write the records to FILE instead of stdout
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub file: Option<PathBuf>,
+    /// Only functions with a doc comment
+    #[arg(long)]
+    pub documented_only: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ApiArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// Package name of the crate (the root package or a workspace member),
+    /// or its directory
+    #[arg(value_name = "CRATE")]
+    pub crate_name: String,
+    /// Write the briefing to FILE instead of stdout
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub file: Option<PathBuf>,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ValidateArgs {
+    /// Path to the project root directory
+    pub project_root: PathBuf,
+    /// How many Rust functions to check
+    #[arg(long = "sample", value_name = "N", default_value_t = 20)]
+    pub sample_size: usize,
+    /// The rust-analyzer binary to ask
+    #[arg(long, value_name = "PATH", default_value = "rust-analyzer")]
+    pub rust_analyzer: String,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ComposeArgs {
+    /// Fragments saved with --json
+    #[arg(required = true, value_name = "FRAGMENT")]
+    pub fragments: Vec<PathBuf>,
+    /// Write the document to FILE instead of stdout
+    #[arg(
+        short = 'o',
+        long = "output",
+        visible_alias = "out",
+        value_name = "FILE"
+    )]
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ChangedSinceArgs {
+    /// Manifest written next to the context (context.md.manifest.json)
+    pub manifest: PathBuf,
+    /// Project root to compare against, instead of the one the manifest
+    /// records
+    #[arg(long, value_name = "DIR")]
+    pub project_root: Option<PathBuf>,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ReplayArgs {
+    /// Lock file written with --lock
+    pub lock_file: PathBuf,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RpcArgs {
+    /// Project root directories to serve, each named after its directory
+    /// or as NAME=PATH; requests pick one with their `project` parameter
+    #[arg(required = true, value_name = "[NAME=]PROJECT_ROOT")]
+    pub project_roots: Vec<String>,
+    /// How the gather method's budgets count tokens
+    #[arg(long, value_name = "chars|cl100k|o200k|llama", default_value = "chars")]
+    pub tokenizer: String,
+    /// Hugging Face tokenizer file for --tokenizer llama
+    #[arg(long, value_name = "tokenizer.json")]
+    pub tokenizer_file: Option<PathBuf>,
+    #[command(flatten)]
+    pub index: IndexFlags,
+}
+
+fn parse_seed(spec: &str) -> Result<Seed, String> {
+    Seed::parse(spec).ok_or_else(|| "expected fn:<name>, type:<name> or doc:<regex>".to_string())
+}
+
+fn parse_location(location: &str) -> Result<(String, usize), String> {
+    location
+        .rsplit_once(':')
+        .and_then(|(file, line)| Some((file.to_string(), line.parse().ok()?)))
+        .filter(|(file, line)| !file.is_empty() && *line > 0)
+        .ok_or_else(|| "expected <file>:<line>, e.g. src/worker.rs:120".to_string())
+}
+
+fn parse_strategy(name: &str) -> Result<Strategy, String> {
+    Strategy::parse(name).ok_or_else(|| format!("available: {}", Strategy::NAMES.join(", ")))
+}
+
+fn parse_combine(name: &str) -> Result<Combine, String> {
+    Combine::parse(name).ok_or_else(|| "available: union, intersect".to_string())
+}
+
+fn parse_prompt(name: &str) -> Result<PromptPreset, String> {
+    PromptPreset::parse(name)
+        .ok_or_else(|| format!("available: {}", PromptPreset::NAMES.join(", ")))
+}
+
+fn parse_graph_format(name: &str) -> Result<GraphFormat, String> {
+    GraphFormat::parse(name).ok_or_else(|| format!("available: {}", GraphFormat::NAMES.join(", ")))
+}
+
+/// What `--format` produces: a call graph export or Markdown code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Graph(GraphFormat),
+    Markdown,
+}
+
+pub fn parse_format(name: &str) -> Result<OutputFormat, String> {
+    match name {
+        "markdown" => Ok(OutputFormat::Markdown),
+        _ => GraphFormat::parse(name)
+            .map(OutputFormat::Graph)
+            .ok_or_else(|| format!("available: {}, markdown", GraphFormat::NAMES.join(", "))),
+    }
+}
+
+/// Rewrites the pre-subcommand form, still found in scripts, editor plugin
+/// settings and `--lock` files, into the current one:
+///
+/// - `<project_root> <function> [preferred_module] [output_file]` becomes
+///   `gather <project_root> <function> [--module M] [--output F]`, guessing
+///   as before that a lone third argument without a slash is the output file
+/// - `<project_root> --seed ... [output_file]` becomes `gather ... --output F`
+/// - `--callers` and `--rpc` become the `callers` and `rpc` subcommands
+///
+/// Anything starting with a subcommand or help/version is returned as is.
+pub fn upgrade_legacy_args(mut args: Vec<String>) -> Vec<String> {
+    let Some(first) = args.get(1) else {
+        return args;
+    };
+    let current = ["help", "-h", "--help", "-V", "--version"];
+    if Command::names().contains(first) || current.contains(&first.as_str()) {
+        return args;
+    }
+
+    let subcommand = if take(&mut args, "--rpc") {
+        "rpc"
+    } else if take(&mut args, "--callers") {
+        "callers"
+    } else {
+        "gather"
+    };
+    args.insert(1, subcommand.to_string());
+    if subcommand == "rpc" {
+        return args;
+    }
+
+    // Positionals are what is left once flags and their values are skipped
+    let gather = Cli::command();
+    let gather = gather.find_subcommand("gather").unwrap();
+    let takes_value = |arg: &str| {
+        gather.get_arguments().any(|known| {
+            known
+                .get_long_and_visible_aliases()
+                .is_some_and(|longs| longs.iter().any(|long| arg == format!("--{}", long)))
+                && known.get_action().takes_values()
+        })
+    };
+    let mut positionals = Vec::new();
+    let mut i = 2;
+    while i < args.len() {
+        if args[i].starts_with('-') {
+            i += if takes_value(&args[i]) { 2 } else { 1 };
+        } else {
+            positionals.push(i);
+            i += 1;
+        }
+    }
+
+    // Only the project root and function are still positional
+    let seeded = args.iter().any(|arg| {
+        ["--seed", "--doc-match", "--fn", "--at"]
+            .iter()
+            .any(|flag| arg == flag || arg.starts_with(&format!("{}=", flag)))
+    });
+    let kept = if seeded { 1 } else { 2 };
+    let extra: Vec<String> = positionals
+        .iter()
+        .skip(kept)
+        .map(|&i| args[i].clone())
+        .collect();
+    let named: Vec<(&str, &String)> = match (seeded, extra.as_slice()) {
+        (true, [output, ..]) => vec![("--output", output)],
+        (false, [module, output, ..]) => vec![("--module", module), ("--output", output)],
+        // A path is a module to prefer; anything else was taken as both
+        (false, [third]) if third.contains(['/', '\\']) => vec![("--module", third)],
+        (false, [third]) => vec![("--module", third), ("--output", third)],
+        _ => Vec::new(),
+    };
+    if named.is_empty() {
+        return args;
+    }
+    eprintln!(
+        "Note: positional module and output file arguments are deprecated; use --module and --output"
+    );
+
+    let mut upgraded: Vec<String> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !positionals.iter().skip(kept).any(|p| p == i))
+        .map(|(_, arg)| arg.clone())
+        .collect();
+    for (flag, value) in named {
+        upgraded.push(flag.to_string());
+        upgraded.push(value.clone());
+    }
+    upgraded
+}
+
+fn take(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
@@ -0,0 +1,88 @@
+//! CODEOWNERS parsing, for scoping gathers to one team's code and annotating
+//! functions with their owners.
+
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+/// Where GitHub looks for the file, in order of precedence.
+const LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+pub struct CodeOwners {
+    rules: Vec<(Regex, Vec<String>)>,
+}
+
+impl CodeOwners {
+    pub fn load(project_root: &Path) -> Option<CodeOwners> {
+        let content = LOCATIONS
+            .iter()
+            .find_map(|location| fs::read_to_string(project_root.join(location)).ok())?;
+
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let Some(pattern) = parts.next() else {
+                continue;
+            };
+            let owners: Vec<String> = parts
+                .take_while(|part| !part.starts_with('#'))
+                .map(String::from)
+                .collect();
+            if let Ok(regex) = Regex::new(&pattern_to_regex(pattern)) {
+                rules.push((regex, owners));
+            }
+        }
+
+        Some(CodeOwners { rules })
+    }
+
+    /// Owners of a path relative to the project root; the last matching
+    /// rule wins, as on GitHub.
+    pub fn owners_of(&self, relative_path: &Path) -> &[String] {
+        let path = relative_path.to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .rev()
+            .find(|(regex, _)| regex.is_match(&path))
+            .map_or(&[], |(_, owners)| owners.as_slice())
+    }
+}
+
+/// Translates a gitignore-style pattern, as CODEOWNERS and `--include`/
+/// `--exclude` write them, into an anchored regex.
+pub fn pattern_to_regex(pattern: &str) -> String {
+    let directory_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    // Patterns containing a slash are relative to the root, others match
+    // at any depth
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+
+    let mut regex = String::from(if anchored { "^" } else { "^(.*/)?" });
+    let mut chars = trimmed.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` matches zero or more directories
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    // A match may name a directory, which owns everything beneath it
+    regex.push_str(if directory_only { "/.*$" } else { "(/.*)?$" });
+    regex
+}
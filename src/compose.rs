@@ -0,0 +1,78 @@
+//! `compose frag1.json frag2.json --out combined.md`: merges fragments saved
+//! with `--json` into one document without re-scanning the project.
+
+use crate::gatherer::{GatherResult, GatheredFunction};
+use crate::{render_output, FunctionInfo, Language};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Reads one fragment.
+pub fn load(path: &Path) -> io::Result<GatherResult> {
+    let text = fs::read_to_string(path)?;
+    let value = serde_json::from_str(&text)?;
+    GatherResult::from_json(&value).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// The functions of all fragments in order of first appearance, keeping one
+/// copy of each qualified name and hash. A name gathered with different
+/// hashes (the code changed between gathers) keeps every version and is
+/// reported in the returned warnings.
+pub fn merge(fragments: &[GatherResult]) -> (Vec<&GatheredFunction>, Vec<String>) {
+    let mut seen = HashSet::new();
+    let mut hashes: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut functions = Vec::new();
+
+    for function in fragments.iter().flat_map(|fragment| &fragment.functions) {
+        if !seen.insert((&function.name, &function.hash)) {
+            continue;
+        }
+        hashes
+            .entry(&function.name)
+            .or_default()
+            .push(&function.hash);
+        functions.push(function);
+    }
+
+    let mut warnings: Vec<String> = hashes
+        .iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, versions)| {
+            format!(
+                "{} differs between fragments ({} versions); keeping all",
+                name,
+                versions.len()
+            )
+        })
+        .collect();
+    warnings.sort();
+    (functions, warnings)
+}
+
+/// Renders merged functions in the standard output format.
+pub fn render(functions: &[&GatheredFunction]) -> String {
+    let infos: Vec<FunctionInfo> = functions
+        .iter()
+        .map(|function| {
+            let path = PathBuf::from(&function.path);
+            FunctionInfo {
+                language: Language::from_path(&path).unwrap_or(Language::Rust),
+                path,
+                module_path: String::new(),
+                definition: function.definition.clone(),
+                line_number: function.line,
+                calls: HashSet::new(),
+                doc: None,
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: None,
+                is_test: false,
+                self_type: None,
+            }
+        })
+        .collect();
+    let infos: Vec<&FunctionInfo> = infos.iter().collect();
+    render_output(&infos)
+}
@@ -0,0 +1,119 @@
+//! `.gather-context.toml`: project defaults committed next to the code, so
+//! everyone gathering from it gets the same behavior without long command
+//! lines.
+//!
+//! ```toml
+//! [index]
+//! # Added to --exclude
+//! exclude = ["benches/**", "examples/**"]
+//! # Only these languages are indexed
+//! languages = ["rust", "csharp"]
+//! # Used when the command line gives no --license-allow
+//! license_allow = ["MIT", "Apache-2.0"]
+//!
+//! [calls]
+//! # Calls to these method names are never followed
+//! skip = ["clone", "to_string", "log"]
+//!
+//! [output]
+//! # Used when the command line gives no --format, --graph or --prompt
+//! format = "markdown"
+//! # Used when the command line gives no --max-tokens or --fill-budget
+//! max_tokens = 24000
+//! ```
+
+use crate::lang::Language;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const FILE_NAME: &str = ".gather-context.toml";
+
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    /// Globs of files never indexed, on top of `--exclude`
+    pub exclude: Vec<String>,
+    /// The languages indexed; all when empty
+    pub languages: Vec<Language>,
+    /// The default `--license-allow`
+    pub license_allow: Vec<String>,
+    /// Method names whose calls are never followed
+    pub skip_calls: Vec<String>,
+    /// The default `--format`, as written
+    pub format: Option<String>,
+    /// The default `--max-tokens`
+    pub max_tokens: Option<usize>,
+}
+
+impl Config {
+    /// Reads the configuration at the project root; the defaults when there
+    /// is none.
+    pub fn load(project_root: &Path) -> io::Result<Config> {
+        let path = project_root.join(FILE_NAME);
+        if !path.is_file() {
+            return Ok(Config::default());
+        }
+
+        let invalid = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), message),
+            )
+        };
+        let table: toml::Table = fs::read_to_string(&path)?
+            .parse()
+            .map_err(|err: toml::de::Error| invalid(err.to_string()))?;
+
+        let strings = |value: &toml::Value, key: &str| {
+            value
+                .as_array()
+                .and_then(|items| {
+                    items
+                        .iter()
+                        .map(|item| item.as_str().map(str::to_string))
+                        .collect::<Option<Vec<String>>>()
+                })
+                .ok_or_else(|| invalid(format!("{} must be a list of strings", key)))
+        };
+
+        let mut config = Config::default();
+        for (section, value) in &table {
+            let keys = value
+                .as_table()
+                .ok_or_else(|| invalid(format!("[{}] must be a table", section)))?;
+            for (key, value) in keys {
+                let name = format!("{}.{}", section, key);
+                match name.as_str() {
+                    "index.exclude" => config.exclude = strings(value, &name)?,
+                    "index.languages" => {
+                        for language in strings(value, &name)? {
+                            let parsed = Language::parse(&language).ok_or_else(|| {
+                                invalid(format!("{}: unknown language '{}'", name, language))
+                            })?;
+                            config.languages.push(parsed);
+                        }
+                    }
+                    "index.license_allow" => config.license_allow = strings(value, &name)?,
+                    "calls.skip" => config.skip_calls = strings(value, &name)?,
+                    "output.format" => {
+                        let format = value
+                            .as_str()
+                            .ok_or_else(|| invalid(format!("{} must be a string", name)))?;
+                        config.format = Some(format.to_string());
+                    }
+                    "output.max_tokens" => {
+                        let max_tokens = value
+                            .as_integer()
+                            .and_then(|n| usize::try_from(n).ok())
+                            .ok_or_else(|| {
+                            invalid(format!("{} must be a positive integer", name))
+                        })?;
+                        config.max_tokens = Some(max_tokens);
+                    }
+                    _ => return Err(invalid(format!("unknown setting {}", name))),
+                }
+            }
+        }
+        Ok(config)
+    }
+}
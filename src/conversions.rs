@@ -0,0 +1,80 @@
+//! `From`/`TryFrom`/`Into`/`TryInto` impls between the project types that
+//! gathered functions exchange. The transformation from one layer's type to
+//! the next usually lives in these impls rather than in any function the
+//! call graph reaches.
+
+use crate::index::{signature, ProjectIndex};
+use crate::{FunctionInfo, ImplInfo};
+use std::collections::HashSet;
+
+const CONVERSION_TRAITS: &[&str] = &["From", "TryFrom", "Into", "TryInto"];
+
+/// The conversion impls whose both ends are project types named in the
+/// signatures of `gathered`, as a section to append after the code; empty
+/// when there are none.
+pub fn render(index: &ProjectIndex, gathered: &[&FunctionInfo]) -> String {
+    let type_names: HashSet<&str> = gathered
+        .iter()
+        .flat_map(|info| {
+            signature(&info.definition).split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        })
+        .filter(|word| index.type_definitions.contains_key(*word))
+        .collect();
+
+    let mut conversions: Vec<&ImplInfo> = type_names
+        .iter()
+        .filter_map(|name| index.impls.get(*name))
+        .flatten()
+        .filter(|impl_info| {
+            let Some(trait_name) = impl_info.trait_name.as_deref() else {
+                return false;
+            };
+            CONVERSION_TRAITS.contains(&trait_name)
+                && trait_argument(&impl_info.definition, trait_name)
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .any(|word| word != impl_info.self_type && type_names.contains(word))
+        })
+        .collect();
+
+    if conversions.is_empty() {
+        return String::new();
+    }
+    conversions.sort_by(|a, b| (&a.path, a.line_number).cmp(&(&b.path, b.line_number)));
+
+    let mut output = String::from("\n=== CONVERSIONS ===\n");
+    for impl_info in conversions {
+        output.push_str(&format!(
+            "\n=== {}:{} ===\n{}\n",
+            impl_info.path.to_string_lossy(),
+            impl_info.line_number,
+            impl_info.definition
+        ));
+    }
+    output.push('\n');
+    output
+}
+
+/// The generic argument of `trait_name` in an impl header, e.g. `Request`
+/// for `impl From<Request> for Job`.
+fn trait_argument<'a>(definition: &'a str, trait_name: &str) -> &'a str {
+    let header = definition.split('{').next().unwrap_or(definition);
+    let pattern = format!("{}<", trait_name);
+    let Some(start) = header.find(&pattern).map(|i| i + pattern.len()) else {
+        return "";
+    };
+
+    let mut depth = 1;
+    for (i, c) in header[start..].char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &header[start..start + i];
+                }
+            }
+            _ => {}
+        }
+    }
+    ""
+}
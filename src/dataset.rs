@@ -0,0 +1,93 @@
+//! `dataset`: (function, direct callees, doc comment) records as JSONL, for
+//! building code-completion or summarization fine-tuning corpora.
+
+use crate::index::{signature, without_preamble, ProjectIndex};
+use crate::{FunctionInfo, Language};
+use serde_json::json;
+
+/// One JSON object per line for every public, non-test function, sorted by
+/// qualified name so repeated runs produce identical files.
+pub fn render(index: &ProjectIndex, documented_only: bool) -> String {
+    let mut names: Vec<&String> = index
+        .function_definitions
+        .iter()
+        .filter(|(_, info)| is_public(info) && !info.is_test)
+        .filter(|(_, info)| !documented_only || info.doc.is_some())
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+
+    let mut output = String::new();
+    for name in names {
+        let info = &index.function_definitions[name];
+
+        let mut callees: Vec<&String> = index
+            .function_calls
+            .get(name)
+            .map(|calls| calls.iter().filter(|c| *c != name).collect())
+            .unwrap_or_default();
+        callees.sort();
+
+        let record = json!({
+            "function": name,
+            "language": info.language.fence(),
+            "path": info.path.to_string_lossy(),
+            "line": info.line_number,
+            "signature": signature(&info.definition).trim(),
+            "doc": info.doc,
+            "body": info.definition,
+            "callees": callees
+                .iter()
+                .map(|callee| {
+                    let callee_info = &index.function_definitions[*callee];
+                    json!({
+                        "function": callee,
+                        "signature": signature(&callee_info.definition).trim(),
+                        "doc": callee_info.doc,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        });
+        output.push_str(&record.to_string());
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Whether a function is part of its module's public surface.
+fn is_public(info: &FunctionInfo) -> bool {
+    let declaration = without_preamble(&info.definition).trim_start();
+    match info.language {
+        Language::Rust => declaration.starts_with("pub ") || declaration.starts_with("pub("),
+        Language::Swift => signature(declaration)
+            .split_whitespace()
+            .any(|word| word == "public" || word == "open"),
+        // Methods are visible to any caller; C functions unless static
+        Language::ObjectiveC => !declaration.starts_with("static "),
+        // Exported functions, and methods not marked private
+        Language::TypeScript | Language::JavaScript => match info.self_type {
+            Some(_) => {
+                !(declaration.starts_with("private ")
+                    || declaration.starts_with("protected ")
+                    || declaration.starts_with('#'))
+            }
+            None => declaration.starts_with("export "),
+        },
+        // Exported names start with an upper case letter
+        Language::Go => {
+            let name = match declaration.strip_prefix("func").map(str::trim_start) {
+                // Past the receiver
+                Some(rest) if rest.starts_with('(') => rest
+                    .split_once(')')
+                    .map_or("", |(_, name)| name.trim_start()),
+                Some(rest) => rest,
+                None => declaration,
+            };
+            name.starts_with(|c: char| c.is_uppercase())
+        }
+        Language::CSharp => signature(declaration)
+            .split_whitespace()
+            .any(|word| word == "public"),
+    }
+}
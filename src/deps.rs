@@ -0,0 +1,228 @@
+//! `--with-deps`: the signatures and doc comments of the dependency
+//! functions gathered code calls by path (`serde_json::to_string`,
+//! `Regex::new` after `use regex::Regex`), read from the crate sources
+//! Cargo downloaded into its registry, so the shapes of external APIs are in
+//! the context too.
+
+use crate::cargo;
+use crate::index::{flat_signature, ProjectIndex};
+use crate::lang::{self, ExtractOptions};
+use crate::{FunctionInfo, Language};
+use ignore::WalkBuilder;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The dependency section, with what it found.
+pub struct Signatures {
+    /// Empty when the gathered functions call no dependency function found
+    /// in the registry
+    pub text: String,
+    pub found: usize,
+    /// Calls into dependencies whose signature was looked for
+    pub calls: usize,
+}
+
+/// The dependency section to append after the code, or `None` without a
+/// Cargo registry to read.
+pub fn render(index: &ProjectIndex, gathered_names: &[String]) -> Option<Signatures> {
+    let registry = registry_sources()?;
+
+    // Unresolved calls by path whose first segment is a dependency of the
+    // calling crate, with the package and version they lead to
+    let mut calls: BTreeMap<String, (String, String)> = BTreeMap::new();
+    let mut manifests: HashMap<PathBuf, HashMap<String, String>> = HashMap::new();
+    for name in gathered_names {
+        let info = &index.function_definitions[name];
+        if info.language != Language::Rust {
+            continue;
+        }
+        let Some(manifest) = cargo::owning_manifest(&info.path) else {
+            continue;
+        };
+        let dependencies = manifests
+            .entry(manifest.clone())
+            .or_insert_with(|| cargo::dependency_packages(&manifest));
+        for (call, resolution) in index.call_resolutions(name) {
+            let Some((first, _)) = call.split_once("::") else {
+                continue;
+            };
+            if resolution.is_some() || calls.contains_key(&call) {
+                continue;
+            }
+            let Some(package) = dependencies.get(first) else {
+                continue;
+            };
+            if let Some(version) = cargo::locked_version(&manifest, package) {
+                calls.insert(call, (package.clone(), version));
+            }
+        }
+    }
+
+    let mut crates: HashMap<(String, String), Option<CrateSource>> = HashMap::new();
+    let mut entries = Vec::new();
+    for (call, (package, version)) in &calls {
+        let source = crates
+            .entry((package.clone(), version.clone()))
+            .or_insert_with(|| CrateSource::find(&registry, package, version));
+        let Some(source) = source else {
+            continue;
+        };
+        let Some(info) = source.function(call) else {
+            continue;
+        };
+        let mut entry = format!(
+            "// {}: {} {}, {}:{}\n",
+            call,
+            package,
+            version,
+            info.path
+                .strip_prefix(&source.dir)
+                .unwrap_or(&info.path)
+                .to_string_lossy(),
+            info.line_number
+        );
+        let summary = info.doc.as_deref().and_then(|doc| doc.split("\n\n").next());
+        for line in summary.into_iter().flat_map(str::lines) {
+            entry.push_str(&format!("/// {}\n", line));
+        }
+        entry.push_str(&flat_signature(&info.definition));
+        entry.push('\n');
+        entries.push(entry);
+    }
+    let text = match entries.is_empty() {
+        true => String::new(),
+        false => format!(
+            "\n=== DEPENDENCY SIGNATURES ===\n\n{}\n",
+            entries.join("\n")
+        ),
+    };
+    Some(Signatures {
+        text,
+        found: entries.len(),
+        calls: calls.len(),
+    })
+}
+
+/// `$CARGO_HOME/registry/src`, where Cargo unpacks downloaded crates.
+fn registry_sources() -> Option<PathBuf> {
+    let cargo_home = match env::var_os("CARGO_HOME") {
+        Some(cargo_home) => PathBuf::from(cargo_home),
+        None => PathBuf::from(env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?)
+            .join(".cargo"),
+    };
+    Some(cargo_home.join("registry").join("src")).filter(|dir| dir.is_dir())
+}
+
+/// The unpacked sources of one dependency version.
+struct CrateSource {
+    dir: PathBuf,
+    /// Each `.rs` file under `src/`, with its module path in the crate
+    files: Vec<(PathBuf, String, String)>,
+}
+
+impl CrateSource {
+    /// The sources of `package` `version` in any of the registries.
+    fn find(registry: &Path, package: &str, version: &str) -> Option<CrateSource> {
+        let dir = fs::read_dir(registry)
+            .ok()?
+            .flatten()
+            .map(|index| index.path().join(format!("{}-{}", package, version)))
+            .find(|dir| dir.is_dir())?;
+        let src = dir.join("src");
+        let mut files = Vec::new();
+        for entry in WalkBuilder::new(&src)
+            .standard_filters(false)
+            .build()
+            .flatten()
+        {
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "rs") {
+                continue;
+            }
+            let Ok(content) = lang::read_source(path) else {
+                continue;
+            };
+            let relative = path.strip_prefix(&src).unwrap_or(path).with_extension("");
+            let mut module: Vec<String> = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            if module
+                .last()
+                .is_some_and(|last| last == "lib" || last == "mod")
+            {
+                module.pop();
+            }
+            module.insert(0, package.replace('-', "_"));
+            files.push((path.to_path_buf(), module.join("::"), content));
+        }
+        files.sort();
+        Some(CrateSource { dir, files })
+    }
+
+    /// The public function `call` names: `Type::name` for a call through a
+    /// type, else a free function `name`. Most public items are defined in
+    /// private modules and re-exported, so one defined in the module the
+    /// call's path names comes first, then one whose module that module
+    /// re-exports with `pub use`.
+    fn function(&self, call: &str) -> Option<FunctionInfo> {
+        let segments: Vec<&str> = call.split("::").collect();
+        let (name, path) = segments.split_last()?;
+        let key = match path.last() {
+            Some(qualifier) if qualifier.starts_with(char::is_uppercase) => {
+                format!("{}::{}", qualifier, name)
+            }
+            _ => name.to_string(),
+        };
+        // The module the path names, without the type the call goes through
+        let module_segments = if key.contains("::") {
+            &path[..path.len() - 1]
+        } else {
+            path
+        };
+        let module = module_segments.join("::");
+        let module_source = self
+            .files
+            .iter()
+            .find(|(_, module_path, _)| *module_path == module)
+            .map_or("", |(_, _, content)| content.as_str());
+        let rank = |module_path: &str| {
+            let below_crate = module_path.split_once("::").map_or("", |(_, rest)| rest);
+            if module_path == module {
+                0
+            } else if !below_crate.is_empty()
+                && module_source.lines().any(|line| {
+                    line.trim_start().starts_with("pub use") && line.contains(below_crate)
+                })
+            {
+                1
+            } else {
+                2
+            }
+        };
+
+        let declares = format!("fn {}", name);
+        let mut found: Vec<(String, FunctionInfo)> = Vec::new();
+        for (path, module_path, content) in &self.files {
+            if !content.contains(&declares) {
+                continue;
+            }
+            let items = lang::extract(content, path, module_path, &ExtractOptions::default());
+            found.extend(
+                items
+                    .functions
+                    .into_iter()
+                    .filter(|(function, info)| {
+                        function.split('#').next() == Some(key.as_str())
+                            && !info.is_test
+                            && flat_signature(&info.definition).starts_with("pub ")
+                    })
+                    .map(|(_, info)| (module_path.clone(), info)),
+            );
+        }
+        found.sort_by_key(|(module_path, _)| rank(module_path));
+        found.into_iter().next().map(|(_, info)| info)
+    }
+}
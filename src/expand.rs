@@ -0,0 +1,205 @@
+//! `--expand`: the functions `macro_rules!` and other macros generate
+//! (`define_handlers! { create_user, delete_user }`), which aren't in the
+//! source the index reads. `cargo expand` prints each crate with its macros
+//! expanded; its inline modules are mapped back to the files they came
+//! from, so a generated function is indexed under the file and module of
+//! the invocation that produced it.
+
+use crate::cargo;
+use crate::lang::{self, Language};
+use regex::Regex;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// One module of the expanded source, with the modules inside it and the
+/// code derives generate blanked out.
+pub struct ExpandedModule {
+    /// The source file the module was read from, or the file of the nearest
+    /// enclosing module that has one
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// Expands the library and binary crates of each package in the project at
+/// `project_root` under the Cargo `profile` (dev when `None`), and splits
+/// the output into modules. Fails when the project isn't a Cargo project or
+/// `cargo expand` can't be run at all; a crate that doesn't expand is left
+/// out, with a note added to `notes`.
+pub fn expand(
+    project_root: &Path,
+    profile: Option<&str>,
+    notes: &mut Vec<String>,
+) -> Result<Vec<ExpandedModule>, String> {
+    let packages = cargo::workspace_members(project_root);
+    if packages.is_empty() {
+        return Err("no Cargo packages found".to_string());
+    }
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let module_regex =
+        Regex::new(r"(?m)^[ \t]*(?:pub(?:\([^)]*\))?[ \t]+)?mod[ \t]+(\w+)[ \t]*\{").unwrap();
+    let derived_regex =
+        Regex::new(r"(?m)^[ \t]*(?:#\[automatically_derived\]|const _: \(\) = \{)").unwrap();
+
+    let mut modules = Vec::new();
+    for (_, dir) in packages {
+        let manifest = dir.join("Cargo.toml");
+        let mut targets = Vec::new();
+        if dir.join("src/lib.rs").is_file() {
+            targets.push((vec!["--lib".to_string()], dir.join("src/lib.rs")));
+        }
+        if let Some(name) =
+            cargo::binary_name(&manifest).filter(|_| dir.join("src/main.rs").is_file())
+        {
+            targets.push((vec!["--bin".to_string(), name], dir.join("src/main.rs")));
+        }
+
+        for (selection, root_file) in targets {
+            let output = Command::new(&cargo)
+                .arg("expand")
+                .arg("--manifest-path")
+                .arg(&manifest)
+                .args(&selection)
+                .args(profile.iter().flat_map(|profile| ["--profile", profile]))
+                .args(["--color", "never"])
+                .output()
+                .map_err(|err| format!("could not run cargo: {}", err))?;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("no such command") {
+                return Err(
+                    "cargo expand is not installed; install it with `cargo install cargo-expand`"
+                        .to_string(),
+                );
+            }
+            // A crate that doesn't compile still expands as far as it can
+            let source = String::from_utf8_lossy(&output.stdout);
+            if source.trim().is_empty() {
+                notes.push(format!(
+                    "Could not expand {} {}: {}",
+                    manifest.display(),
+                    selection.join(" "),
+                    stderr
+                        .lines()
+                        .find(|line| line.starts_with("error"))
+                        .or_else(|| stderr.lines().last())
+                        .unwrap_or("no output")
+                ));
+                continue;
+            }
+            split_modules(
+                &source,
+                &root_file,
+                &module_regex,
+                &derived_regex,
+                &mut modules,
+            );
+        }
+    }
+    Ok(modules)
+}
+
+/// Adds the module `source` (read from `path`) and, recursively, the inline
+/// modules it holds to `modules`.
+fn split_modules(
+    source: &str,
+    path: &Path,
+    module_regex: &Regex,
+    derived_regex: &Regex,
+    modules: &mut Vec<ExpandedModule>,
+) {
+    let mut own = source.to_string();
+    let mut end = 0;
+    for captures in module_regex.captures_iter(source) {
+        let whole = captures.get(0).unwrap();
+        if whole.start() < end {
+            continue;
+        }
+        end = lang::find_body_end(source, whole.start(), Language::Rust);
+        let body = &source[whole.end()..end.saturating_sub(1).max(whole.end())];
+        let child = module_file(path, &captures[1]);
+        split_modules(body, &child, module_regex, derived_regex, modules);
+        blank(&mut own, whole.start(), end);
+    }
+
+    // Derived trait impls aren't functions anyone wrote or invoked by name
+    let mut end = 0;
+    for found in derived_regex.find_iter(source) {
+        if found.start() < end {
+            continue;
+        }
+        end = lang::find_body_end(source, found.start(), Language::Rust);
+        if source[end..].starts_with(';') {
+            end += 1;
+        }
+        blank(&mut own, attributes_start(source, found.start()), end);
+    }
+
+    modules.push(ExpandedModule {
+        path: path.to_path_buf(),
+        source: own,
+    });
+}
+
+/// The file the module `name` declared in `parent` is read from
+/// (`name.rs` or `name/mod.rs`), or `parent` itself for a module written
+/// inline.
+fn module_file(parent: &Path, name: &str) -> PathBuf {
+    let Some(dir) = parent.parent() else {
+        return parent.to_path_buf();
+    };
+    let stem = parent.file_stem().unwrap_or_default();
+    let dir = if ["lib", "main", "mod"].iter().any(|root| stem == *root) {
+        dir.to_path_buf()
+    } else {
+        dir.join(stem)
+    };
+    [
+        dir.join(format!("{}.rs", name)),
+        dir.join(name).join("mod.rs"),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+    .unwrap_or_else(|| parent.to_path_buf())
+}
+
+/// The start of the line of the first attribute among those on the lines
+/// right above `start`.
+fn attributes_start(source: &str, start: usize) -> usize {
+    let mut line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    while line_start > 0 {
+        let previous = source[..line_start - 1].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[previous..line_start - 1].trim();
+        if !(line.starts_with("#[") && line.ends_with(']')) {
+            break;
+        }
+        line_start = previous;
+    }
+    line_start
+}
+
+/// Replaces `text[start..end]` with spaces, keeping its line breaks and
+/// length so the code around it stays where it was.
+fn blank(text: &mut String, start: usize, end: usize) {
+    let blanked: String = text[start..end]
+        .chars()
+        .map(|c| match c {
+            '\n' => "\n".to_string(),
+            _ => " ".repeat(c.len_utf8()),
+        })
+        .collect();
+    text.replace_range(start..end, &blanked);
+}
+
+/// The line of `content`, the original source, that a function `name`
+/// generated by a macro is best shown at: the first line naming it outside
+/// a call, else the first macro invocation among the items, else line 1.
+pub fn invocation_line(content: &str, name: &str) -> usize {
+    let named = Regex::new(&format!(r"\b{}\b\s*[^\s(:]", regex::escape(name))).unwrap();
+    let invocation = Regex::new(r"^\s*(?:\w+::)*\w+!\s*[({\[]").unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    lines
+        .iter()
+        .position(|line| named.is_match(line) || line.trim_end().ends_with(name))
+        .or_else(|| lines.iter().position(|line| invocation.is_match(line)))
+        .map_or(1, |i| i + 1)
+}
@@ -0,0 +1,158 @@
+//! `explain <function>`: why a gather included a function, as the chain of
+//! call edges from a root, the rule that resolved each edge, and the
+//! priority `--fill-budget` ranks it by.
+
+use crate::budget::{self, Tokenizer};
+use crate::index::{ProjectIndex, Resolution};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Explains `explained` within the gathered functions. Roots are the first
+/// gathered function (the target) and any other gathered function no
+/// gathered function calls (further seeds' entry points).
+pub fn render(
+    index: &ProjectIndex,
+    tokenizer: &Tokenizer,
+    gathered_names: &[String],
+    explained: &str,
+) -> String {
+    let gathered: HashSet<&String> = gathered_names.iter().collect();
+    let callers_of = |name: &str| -> Vec<&String> {
+        let mut callers: Vec<&String> = gathered_names
+            .iter()
+            .filter(|caller| {
+                index
+                    .function_calls
+                    .get(*caller)
+                    .is_some_and(|calls| calls.contains(name))
+            })
+            .collect();
+        callers.sort();
+        callers
+    };
+
+    let roots: Vec<&String> = gathered_names
+        .iter()
+        .enumerate()
+        .filter(|(i, name)| *i == 0 || callers_of(name).iter().all(|caller| caller == name))
+        .map(|(_, name)| name)
+        .collect();
+
+    if !gathered.contains(&explained.to_string()) {
+        let roots: Vec<String> = roots.iter().map(|root| root.to_string()).collect();
+        let reason = if index.reachable(&roots).iter().any(|name| name == explained) {
+            "it is reachable from the root but was left out, e.g. by --depth, --fill-budget or --combine intersect"
+        } else {
+            "it is not reachable from the root"
+        };
+        return format!("{} was not gathered: {}\n", explained, reason);
+    }
+
+    // Shortest chain of gathered call edges from any root
+    let mut parents: HashMap<&String, &String> = HashMap::new();
+    let mut depths: HashMap<&String, usize> = roots.iter().map(|root| (*root, 0)).collect();
+    let mut queue: VecDeque<&String> = roots.iter().copied().collect();
+    while let Some(current) = queue.pop_front() {
+        let mut callees: Vec<&String> = index
+            .function_calls
+            .get(current)
+            .into_iter()
+            .flatten()
+            .filter(|callee| gathered.contains(callee))
+            .collect();
+        callees.sort();
+        for callee in callees {
+            if !depths.contains_key(callee) {
+                depths.insert(callee, depths[current] + 1);
+                parents.insert(callee, current);
+                queue.push_back(callee);
+            }
+        }
+    }
+
+    let mut chain: Vec<&str> = vec![explained];
+    let mut current = explained.to_string();
+    while let Some(parent) = parents.get(&current) {
+        chain.push(parent);
+        current = parent.to_string();
+    }
+    chain.reverse();
+
+    let mut output = format!("{} was gathered because:\n\n", explained);
+    output.push_str(&format!("  {} is a root\n", chain[0]));
+    for edge in chain.windows(2) {
+        let (caller, callee) = (edge[0], edge[1]);
+        let rule = match index.resolution(caller, callee) {
+            Some((call, resolution)) => describe(&call, resolution),
+            None => "no recorded rule".to_string(),
+        };
+        output.push_str(&format!("  {} -> {}\n      {}\n", caller, callee, rule));
+    }
+
+    let callers = callers_of(explained);
+    let depth = chain.len() - 1;
+    let tokens = budget::function_tokens(index, tokenizer, explained);
+    output.push_str(&format!(
+        "\nPriority: depth {}, fan-in {}, {} tokens (--fill-budget takes the closest, \
+         most-called, smallest functions first)\n",
+        depth,
+        callers.len(),
+        tokens
+    ));
+
+    let other_callers: Vec<&String> = callers
+        .into_iter()
+        .filter(|caller| chain.len() < 2 || **caller != chain[chain.len() - 2])
+        .collect();
+    if !other_callers.is_empty() {
+        output.push_str("\nAlso called by:\n");
+        for caller in other_callers {
+            output.push_str(&format!("  {}\n", caller));
+        }
+    }
+    output
+}
+
+fn describe(call: &str, resolution: Resolution) -> String {
+    match resolution {
+        Resolution::Pinned => format!("`{}` is pinned in gather-overrides.toml", call),
+        Resolution::Unique => format!("`{}` names only this function", call),
+        Resolution::Path => format!(
+            "the caller calls `{}` by its module path or a `use` import",
+            call
+        ),
+        Resolution::Trait => format!(
+            "`{}` names methods of several types and this trait declares it; \
+             the call goes through the trait, which links each implementation",
+            call
+        ),
+        Resolution::Implementation => format!(
+            "the caller declares the trait method `{}`, and this implements it (--impls to narrow)",
+            call
+        ),
+        Resolution::SameModule { candidates } => format!(
+            "`{}` names {} functions; this one is in the caller's module",
+            call, candidates
+        ),
+        Resolution::FirstOfMany { candidates } => format!(
+            "`{}` names {} functions and the caller's module doesn't single one out; \
+             this one was indexed first (pin it in gather-overrides.toml if wrong)",
+            call, candidates
+        ),
+        Resolution::Derive => format!(
+            "the caller names a type with #[{}], which this proc macro expands",
+            call
+        ),
+        Resolution::Global => format!(
+            "the caller reads `{}`, a lazily initialized global defined here with its initializer",
+            call
+        ),
+        Resolution::Operator => format!(
+            "operator sugar in the caller resolves to the `{}` impl (--operators)",
+            call
+        ),
+        Resolution::CfgVariant => format!(
+            "`{}` names another #[cfg] variant of this function (--all-variants)",
+            call
+        ),
+    }
+}
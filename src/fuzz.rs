@@ -0,0 +1,155 @@
+//! `fuzz-prep`: the context needed to write a cargo-fuzz target for one
+//! function.
+
+use crate::index::{parameter_list, return_type, signature, ProjectIndex};
+use crate::{render_output, FunctionInfo, ImplInfo, TypeInfo};
+
+/// Traits whose impls tell the author how fuzz input can be produced.
+const INPUT_TRAITS: &[&str] = &["Arbitrary", "Deserialize", "Serialize"];
+
+/// Renders the target function, the definitions of its input types, the
+/// functions that construct those types, and any existing `Arbitrary` or
+/// serde impls for them.
+pub fn render(index: &ProjectIndex, qualified_name: &str) -> String {
+    let info = &index.function_definitions[qualified_name];
+    let input_types = index.parameter_types(info);
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "Fuzz target context for {} ({}:{})\n",
+        qualified_name,
+        info.path.to_string_lossy(),
+        info.line_number
+    ));
+
+    output.push_str("\n----- FUNCTION -----\n");
+    output.push_str(&render_output(&[info]));
+
+    output.push_str("\n----- INPUT TYPES -----\n");
+    if input_types.is_empty() {
+        output.push_str("\n(parameters only use external or primitive types)\n");
+    }
+    for type_info in &input_types {
+        push_item(&mut output, &type_info.path, &type_info.definition);
+    }
+
+    let type_names: Vec<String> = input_types.iter().map(|t| type_name(t)).collect();
+
+    output.push_str("\n----- CONSTRUCTORS -----\n");
+    let constructors = constructors_of(index, &type_names);
+    if constructors.is_empty() {
+        output.push_str("\n(no constructors found for the input types)\n");
+    }
+    for (_, constructor) in &constructors {
+        push_item(&mut output, &constructor.path, &constructor.definition);
+    }
+
+    output.push_str("\n----- ARBITRARY / SERDE IMPLS -----\n");
+    let input_impls: Vec<&ImplInfo> = type_names
+        .iter()
+        .filter_map(|name| index.impls.get(name))
+        .flatten()
+        .filter(|impl_info| {
+            impl_info
+                .trait_name
+                .as_deref()
+                .is_some_and(|t| INPUT_TRAITS.contains(&t))
+        })
+        .collect();
+    let derived: Vec<&&TypeInfo> = input_types
+        .iter()
+        .filter(|t| INPUT_TRAITS.iter().any(|name| derives(t, name)))
+        .collect();
+
+    if input_impls.is_empty() && derived.is_empty() {
+        output.push_str("\n(no Arbitrary or serde impls; one has to be written)\n");
+    }
+    for type_info in derived {
+        let traits: Vec<&str> = INPUT_TRAITS
+            .iter()
+            .copied()
+            .filter(|name| derives(type_info, name))
+            .collect();
+        output.push_str(&format!(
+            "\n{} derives {}\n",
+            type_name(type_info),
+            traits.join(", ")
+        ));
+    }
+    for impl_info in input_impls {
+        push_item(&mut output, &impl_info.path, &impl_info.definition);
+    }
+
+    output
+}
+
+/// Functions returning one of `type_names` without taking `self`: associated
+/// constructors returning `Self` from an inherent impl, and free functions
+/// whose return type names the type.
+fn constructors_of<'a>(
+    index: &'a ProjectIndex,
+    type_names: &[String],
+) -> Vec<(&'a String, &'a FunctionInfo)> {
+    let mut constructors: Vec<(&String, &FunctionInfo)> = index
+        .function_definitions
+        .iter()
+        .filter(|(_, info)| !info.is_test)
+        .filter(|(_, info)| {
+            let signature = signature(&info.definition);
+            if parameter_list(signature).contains("self") {
+                return false;
+            }
+            let Some(returned) = return_type(signature) else {
+                return false;
+            };
+            let returned_words: Vec<&str> = returned
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .collect();
+
+            if returned_words.contains(&"Self") {
+                return index.enclosing_impl(info).is_some_and(|impl_info| {
+                    impl_info.trait_name.is_none() && type_names.contains(&impl_info.self_type)
+                });
+            }
+            type_names
+                .iter()
+                .any(|name| returned_words.contains(&name.as_str()))
+        })
+        .collect();
+
+    constructors.sort_by(|a, b| a.0.cmp(b.0));
+    constructors
+}
+
+fn type_name(type_info: &TypeInfo) -> String {
+    let header = signature(&type_info.definition);
+    let declaration = header
+        .lines()
+        .find(|line| !line.trim_start().starts_with("#["))
+        .unwrap_or(header);
+    declaration
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .find(|word| !["pub", "crate", "struct", "enum", "type", "union", "trait"].contains(word))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Whether a type's derive attributes list `trait_name`.
+fn derives(type_info: &TypeInfo, trait_name: &str) -> bool {
+    type_info
+        .definition
+        .lines()
+        .take_while(|line| line.trim_start().starts_with("#["))
+        .filter(|line| line.contains("derive("))
+        .any(|line| {
+            line.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .any(|word| word == trait_name)
+        })
+}
+
+fn push_item(output: &mut String, path: &std::path::Path, definition: &str) {
+    output.push_str(&format!("\n=== {} ===\n", path.to_string_lossy()));
+    output.push_str(definition);
+    output.push_str("\n\n");
+}
@@ -0,0 +1,292 @@
+//! [`ContextGatherer`]: the core of a gather for one target function, for
+//! tools that embed the library (editor plugins, CI bots) instead of
+//! shelling out: its call tree, within a token budget or a depth if given,
+//! rendered plainly or under a prompt preset. The `rpc` server answers
+//! `gather` requests with it. The CLI's `gather` is a pipeline of its own
+//! over the same index, adding sections (types, aliases, stubs, ...) and
+//! flags (`--exclude`, `--seed`, `--lock`, ...) this leaves out.
+
+use crate::budget::{self, Strategy, Tokenizer};
+use crate::index::ProjectIndex;
+use crate::lock::content_hash;
+use crate::prompt::{self, PromptPreset};
+use crate::{render_output, FunctionInfo};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Gathers context from a built index.
+pub struct ContextGatherer<'a> {
+    index: &'a ProjectIndex,
+    tokenizer: &'a Tokenizer,
+}
+
+impl<'a> ContextGatherer<'a> {
+    /// `tokenizer` measures `fill_budget`s.
+    pub fn new(index: &'a ProjectIndex, tokenizer: &'a Tokenizer) -> ContextGatherer<'a> {
+        ContextGatherer { index, tokenizer }
+    }
+
+    /// The target function and what it calls, or `None` when no function
+    /// matches `params.function` (or encloses `params.at`).
+    pub fn gather(&self, params: &GatherParams) -> Option<GatherResult> {
+        let target = match &params.at {
+            Some((path, line)) => self.index.function_at(path, *line)?,
+            None => {
+                self.index
+                    .find_function(&params.function, params.module.as_ref())?
+                    .qualified_name
+            }
+        };
+
+        let roots = [target.clone()];
+        let names = match params.fill_budget {
+            Some(budget) => {
+                budget::fill(
+                    self.index,
+                    self.tokenizer,
+                    &roots,
+                    budget,
+                    params.depth,
+                    params.strategy,
+                )
+                .functions
+            }
+            None => budget::traverse(
+                self.index,
+                self.tokenizer,
+                &roots,
+                params.depth,
+                params.strategy,
+            ),
+        };
+        // What the caller already holds, unchanged, is named but not resent
+        let (already_sent, names): (Vec<String>, Vec<String>) =
+            names.into_iter().partition(|name| {
+                params.already_sent.get(name).is_some_and(|hash| {
+                    *hash == content_hash(&self.index.function_definitions[name].definition)
+                })
+            });
+        let gathered: Vec<&FunctionInfo> = names
+            .iter()
+            .map(|name| &self.index.function_definitions[name])
+            .collect();
+
+        let output = match params.prompt {
+            Some(preset) => {
+                prompt::render(preset, self.index, &names, &gathered, &[], &HashMap::new())
+            }
+            None => render_output(&gathered),
+        };
+
+        Some(GatherResult {
+            target,
+            functions: names
+                .iter()
+                .zip(&gathered)
+                .map(|(name, info)| GatheredFunction::new(name, info))
+                .collect(),
+            already_sent,
+            output,
+        })
+    }
+}
+
+/// What to gather: the target function and how far to expand from it.
+#[derive(Debug, Clone)]
+pub struct GatherParams {
+    /// Simple or fully qualified function name
+    pub function: String,
+    /// File (relative to the project root, or absolute) and 1-based line
+    /// whose enclosing function is the target instead of `function`, as an
+    /// editor knows the cursor position
+    pub at: Option<(String, usize)>,
+    /// Module to prefer when the name is ambiguous
+    pub module: Option<String>,
+    /// Prompt preset to frame the output with (`review`, `test-gen`, ...)
+    pub prompt: Option<PromptPreset>,
+    /// Expand in priority order up to this many tokens instead of taking
+    /// everything reachable
+    pub fill_budget: Option<usize>,
+    /// Follow at most this many calls from the target
+    pub depth: Option<usize>,
+    /// The order functions are gathered (and a `fill_budget` spent) in
+    pub strategy: Strategy,
+    /// Functions the caller already has, with the content hash of the
+    /// definition it has; they are left out of the result unless they
+    /// changed since
+    pub already_sent: HashMap<String, String>,
+}
+
+impl GatherParams {
+    /// Everything reachable from `function`, without a prompt preset.
+    pub fn function(function: &str) -> GatherParams {
+        GatherParams {
+            function: function.to_string(),
+            at: None,
+            module: None,
+            prompt: None,
+            fill_budget: None,
+            depth: None,
+            strategy: Strategy::Bfs,
+            already_sent: HashMap::new(),
+        }
+    }
+
+    pub fn from_json(params: &Value) -> Result<GatherParams, String> {
+        let at = match (&params["path"], &params["line"]) {
+            (Value::Null, Value::Null) => None,
+            (path, line) => Some((
+                path.as_str()
+                    .ok_or("'path' must be a file path")?
+                    .to_string(),
+                line.as_u64()
+                    .filter(|line| *line > 0)
+                    .ok_or("'line' must be a 1-based line number")? as usize,
+            )),
+        };
+        let function = match params["function"].as_str() {
+            Some(function) => function.to_string(),
+            None if at.is_some() => String::new(),
+            None => return Err("missing string parameter 'function' (or 'path' and 'line')".into()),
+        };
+        let module = params["module"].as_str().map(str::to_string);
+        let prompt = match params["prompt"].as_str() {
+            Some(name) => Some(
+                PromptPreset::parse(name)
+                    .ok_or_else(|| format!("unknown prompt preset '{}'", name))?,
+            ),
+            None => None,
+        };
+        let fill_budget = match &params["fill_budget"] {
+            Value::Null => None,
+            value => Some(
+                value
+                    .as_u64()
+                    .ok_or("'fill_budget' must be a token count")? as usize,
+            ),
+        };
+        let depth = match &params["depth"] {
+            Value::Null => None,
+            value => Some(value.as_u64().ok_or("'depth' must be a number of calls")? as usize),
+        };
+        let strategy = match params["strategy"].as_str() {
+            Some(name) => {
+                Strategy::parse(name).ok_or_else(|| format!("unknown strategy '{}'", name))?
+            }
+            None => Strategy::Bfs,
+        };
+        Ok(GatherParams {
+            function,
+            at,
+            module,
+            prompt,
+            fill_budget,
+            depth,
+            strategy,
+            already_sent: HashMap::new(),
+        })
+    }
+}
+
+/// One gathered function in a [`GatherResult`].
+#[derive(Debug, Clone)]
+pub struct GatheredFunction {
+    pub name: String,
+    pub path: String,
+    pub line: usize,
+    pub language: String,
+    /// Content hash of the definition, as recorded by `--lock`
+    pub hash: String,
+    pub definition: String,
+}
+
+impl GatheredFunction {
+    pub fn new(name: &str, info: &FunctionInfo) -> GatheredFunction {
+        GatheredFunction {
+            name: name.to_string(),
+            path: info.path.to_string_lossy().into_owned(),
+            line: info.line_number,
+            language: info.language.fence().to_string(),
+            hash: content_hash(&info.definition),
+            definition: info.definition.clone(),
+        }
+    }
+}
+
+/// A finished gather: the resolved target, the gathered functions in output
+/// order, and the rendered context.
+#[derive(Debug, Clone)]
+pub struct GatherResult {
+    pub target: String,
+    pub functions: Vec<GatheredFunction>,
+    /// Gathered functions left out because the caller already has them
+    /// (`GatherParams::already_sent`), in output order
+    pub already_sent: Vec<String>,
+    pub output: String,
+}
+
+impl GatherResult {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "target": self.target,
+            "functions": self
+                .functions
+                .iter()
+                .map(|function| {
+                    json!({
+                        "name": function.name,
+                        "path": function.path,
+                        "line": function.line,
+                        "language": function.language,
+                        "hash": function.hash,
+                        "definition": function.definition,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "already_sent": self.already_sent,
+            "output": self.output,
+        })
+    }
+
+    /// Reads a result back, e.g. a fragment saved with `--json`.
+    pub fn from_json(value: &Value) -> Result<GatherResult, String> {
+        let string = |value: &Value, key: &str| -> Result<String, String> {
+            value[key]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("missing string field '{}'", key))
+        };
+        let functions = value["functions"]
+            .as_array()
+            .ok_or("missing array field 'functions'")?
+            .iter()
+            .map(|function| {
+                let definition = string(function, "definition")?;
+                Ok(GatheredFunction {
+                    name: string(function, "name")?,
+                    path: string(function, "path")?,
+                    line: function["line"].as_u64().unwrap_or(0) as usize,
+                    language: string(function, "language")?,
+                    // Older fragments may lack the hash
+                    hash: string(function, "hash").unwrap_or_else(|_| content_hash(&definition)),
+                    definition,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let already_sent = value["already_sent"]
+            .as_array()
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| name.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(GatherResult {
+            target: string(value, "target")?,
+            functions,
+            already_sent,
+            output: string(value, "output").unwrap_or_default(),
+        })
+    }
+}
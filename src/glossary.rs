@@ -0,0 +1,87 @@
+//! `--glossary`: the project's vocabulary, placed ahead of the code so the
+//! model knows what `Ledger` or `settlement` mean here before it reads a
+//! single function.
+
+use crate::index::ProjectIndex;
+use crate::{lang, FunctionInfo, Language};
+use std::collections::HashSet;
+
+/// One line per module, type and function in the gathered context that has
+/// a doc comment, using the comment's first line as the definition.
+pub fn render(
+    index: &ProjectIndex,
+    gathered_names: &[String],
+    gathered: &[&FunctionInfo],
+) -> String {
+    let mut entries: Vec<(String, &str, String)> = Vec::new();
+
+    // Modules, described by their `//!` header
+    let mut seen_modules = HashSet::new();
+    for info in gathered {
+        if info.language != Language::Rust || !seen_modules.insert(&info.module_path) {
+            continue;
+        }
+        let module_doc = lang::read_source(&info.path)
+            .ok()
+            .and_then(|content| first_line(&module_doc(&content)));
+        if let Some(definition) = module_doc {
+            entries.push((info.module_path.clone(), "module", definition));
+        }
+    }
+
+    // Types named anywhere in the gathered functions
+    let mut seen_types = HashSet::new();
+    for info in gathered {
+        for word in info
+            .definition
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        {
+            let Some(definitions) = index.type_definitions.get(word) else {
+                continue;
+            };
+            if !seen_types.insert(word) {
+                continue;
+            }
+            let type_info = definitions
+                .iter()
+                .find(|t| t.language == info.language)
+                .unwrap_or(&definitions[0]);
+            if let Some(definition) = type_info.doc.as_deref().and_then(first_line) {
+                entries.push((word.to_string(), "type", definition));
+            }
+        }
+    }
+
+    for (name, info) in gathered_names.iter().zip(gathered) {
+        if let Some(definition) = info.doc.as_deref().and_then(first_line) {
+            let simple_name = name.rsplit("::").next().unwrap_or(name);
+            entries.push((simple_name.to_string(), "function", definition));
+        }
+    }
+
+    let mut output = String::new();
+    for (term, kind, definition) in entries {
+        output.push_str(&format!("{} ({}): {}\n", term, kind, definition));
+    }
+    output
+}
+
+/// The inner `//!` doc comment at the top of a Rust file.
+fn module_doc(content: &str) -> String {
+    content
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty())
+        .map_while(|line| line.strip_prefix("//!"))
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The first non-empty line of a doc comment.
+fn first_line(doc: &str) -> Option<String> {
+    doc.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
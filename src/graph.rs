@@ -0,0 +1,197 @@
+//! `--graph dot|mermaid|html`: the gathered call graph as a diagram instead
+//! of code. Nodes are colored by call distance from the target and sized by
+//! lines of code, so the core of a feature and its heavy helpers stand out.
+
+use crate::index::ProjectIndex;
+use crate::layers::depths_within;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+    Html,
+}
+
+impl GraphFormat {
+    pub const NAMES: &'static [&'static str] = &["dot", "mermaid", "html"];
+
+    pub fn parse(name: &str) -> Option<GraphFormat> {
+        match name {
+            "dot" => Some(GraphFormat::Dot),
+            "mermaid" => Some(GraphFormat::Mermaid),
+            "html" => Some(GraphFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// Fill colors by call distance; deeper functions share the last one.
+const DEPTH_COLORS: &[&str] = &["#d73027", "#fc8d59", "#fee090", "#e0f3f8", "#91bfdb"];
+/// Functions the first one doesn't reach, e.g. other seeds' trees
+const UNREACHED_COLOR: &str = "#d9d9d9";
+
+struct Node<'a> {
+    id: String,
+    name: &'a str,
+    lines: usize,
+    depth: Option<usize>,
+}
+
+impl Node<'_> {
+    fn color(&self) -> &'static str {
+        match self.depth {
+            Some(depth) => DEPTH_COLORS[depth.min(DEPTH_COLORS.len() - 1)],
+            None => UNREACHED_COLOR,
+        }
+    }
+
+    /// Font size in points, growing with the square root of the line count
+    /// so one huge function doesn't dwarf the rest.
+    fn font_size(&self) -> f64 {
+        (10.0 + (self.lines as f64).sqrt() * 1.5).min(32.0)
+    }
+}
+
+/// Renders the gathered functions and the calls between them.
+pub fn render(format: GraphFormat, index: &ProjectIndex, gathered_names: &[String]) -> String {
+    let depths = depths_within(index, gathered_names);
+    let nodes: Vec<Node> = gathered_names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| Node {
+            id: format!("n{}", i),
+            name,
+            lines: index.function_definitions[name].definition.lines().count(),
+            depth: depths.get(name).copied(),
+        })
+        .collect();
+
+    let ids: HashMap<&str, &str> = nodes.iter().map(|n| (n.name, n.id.as_str())).collect();
+    let mut seen = HashSet::new();
+    let edges: Vec<(&str, &str)> = nodes
+        .iter()
+        .flat_map(|node| {
+            index
+                .function_calls
+                .get(node.name)
+                .into_iter()
+                .flatten()
+                .filter_map(|called| ids.get(called.as_str()).copied())
+                .map(move |called| (node.id.as_str(), called))
+        })
+        .filter(|edge| seen.insert(*edge))
+        .collect();
+
+    match format {
+        GraphFormat::Dot => dot(&nodes, &edges),
+        GraphFormat::Mermaid => mermaid(&nodes, &edges),
+        GraphFormat::Html => html(&nodes, &edges),
+    }
+}
+
+fn dot(nodes: &[Node], edges: &[(&str, &str)]) -> String {
+    let mut output = String::from("digraph gathered {\n");
+    output.push_str("    rankdir=LR;\n");
+    output.push_str("    node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];\n");
+    for node in nodes {
+        output.push_str(&format!(
+            "    {} [label=\"{}\\n{} lines\", fillcolor=\"{}\", fontsize={:.0}];\n",
+            node.id,
+            node.name.replace('"', "\\\""),
+            node.lines,
+            node.color(),
+            node.font_size()
+        ));
+    }
+    for (caller, called) in edges {
+        output.push_str(&format!("    {} -> {};\n", caller, called));
+    }
+    output.push_str("}\n");
+    output
+}
+
+fn mermaid(nodes: &[Node], edges: &[(&str, &str)]) -> String {
+    let mut output = String::from("flowchart LR\n");
+    for node in nodes {
+        output.push_str(&format!(
+            "    {}[\"{}<br/>{} lines\"]\n",
+            node.id,
+            node.name.replace('"', "#quot;"),
+            node.lines
+        ));
+    }
+    for (caller, called) in edges {
+        output.push_str(&format!("    {} --> {}\n", caller, called));
+    }
+    for node in nodes {
+        output.push_str(&format!(
+            "    style {} fill:{},font-size:{:.0}px\n",
+            node.id,
+            node.color(),
+            node.font_size()
+        ));
+    }
+    output
+}
+
+/// A standalone page rendering the Mermaid diagram, with a legend.
+fn html(nodes: &[Node], edges: &[(&str, &str)]) -> String {
+    let mut legend = String::new();
+    for (depth, color) in DEPTH_COLORS.iter().enumerate() {
+        let label = match depth {
+            0 => "target".to_string(),
+            _ if depth == DEPTH_COLORS.len() - 1 => format!("{}+ calls away", depth),
+            1 => "1 call away".to_string(),
+            _ => format!("{} calls away", depth),
+        };
+        legend.push_str(&format!(
+            "<li><span style=\"background:{}\"></span>{}</li>\n",
+            color, label
+        ));
+    }
+    if nodes.iter().any(|node| node.depth.is_none()) {
+        legend.push_str(&format!(
+            "<li><span style=\"background:{}\"></span>not reached from the target</li>\n",
+            UNREACHED_COLOR
+        ));
+    }
+
+    let diagram = mermaid(nodes, edges)
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Gathered call graph</title>
+<style>
+body {{ font-family: Helvetica, sans-serif; margin: 2em; }}
+ul.legend {{ list-style: none; padding: 0; }}
+ul.legend li {{ display: inline-block; margin-right: 1.5em; }}
+ul.legend span {{ display: inline-block; width: 1em; height: 1em; margin-right: 0.4em; vertical-align: middle; border: 1px solid #999; }}
+</style>
+</head>
+<body>
+<h1>Gathered call graph</h1>
+<p>{} functions, {} call edges. Node size grows with lines of code.</p>
+<ul class="legend">
+{}</ul>
+<pre class="mermaid">
+{}</pre>
+<script type="module">
+import mermaid from "https://cdn.jsdelivr.net/npm/mermaid@10/dist/mermaid.esm.min.mjs";
+mermaid.initialize({{ startOnLoad: true }});
+</script>
+</body>
+</html>
+"#,
+        nodes.len(),
+        edges.len(),
+        legend,
+        diagram
+    )
+}
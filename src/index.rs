@@ -0,0 +1,1846 @@
+//! Project-wide function index and call graph.
+
+use crate::cache::FileCache;
+use crate::cargo;
+use crate::codeowners::{self, CodeOwners};
+use crate::expand;
+use crate::lang::{self, is_global_name, unraw, ExtractOptions, FileItems, Language};
+use crate::license::{self, Licenses};
+use crate::operators;
+use crate::overrides::{self, Overrides};
+use crate::{FunctionInfo, ImplInfo, TypeInfo};
+use ignore::{WalkBuilder, WalkState};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str;
+use std::sync::Mutex;
+
+/// Controls which files are indexed and how they are parsed.
+#[derive(Debug, Clone, Default)]
+pub struct IndexOptions {
+    pub extract: ExtractOptions,
+    /// Only index files owned by this CODEOWNERS owner (e.g. `@team/backend`)
+    pub owned_by: Option<String>,
+    /// Record each function's CODEOWNERS owners for annotation in the output
+    pub annotate_owners: bool,
+    /// Link functions to the operator impls (`Add`, `Index`, `Deref`, ...)
+    /// of project types they use operators on
+    pub operators: bool,
+    /// Also index files excluded by `.gitignore`/`.ignore` and build output
+    /// directories (`target/`, `vendor/`, `bin/`, `obj/`, `node_modules/`)
+    pub no_ignore: bool,
+    /// Reuse the parsed files stored in `.gather-context/index.bin`,
+    /// re-parsing only changed files, and store the result there
+    pub cache: bool,
+    /// Link every call to a function to all of its `#[cfg]` variants in the
+    /// same module, and label each with its condition
+    pub all_variants: bool,
+    /// Report every source file left out of the index, or only partly
+    /// indexed, and why
+    pub verbose: bool,
+    /// Only index files matching one of these gitignore-style globs,
+    /// relative to the project root (`src/**`); all files when empty
+    pub include: Vec<String>,
+    /// Don't index files matching any of these globs (`tests/**`)
+    pub exclude: Vec<String>,
+    /// Only link trait method declarations to the implementations for
+    /// these types; all implementations when empty
+    pub impls: Vec<String>,
+    /// Only index files in these languages; all languages when empty
+    pub languages: Vec<Language>,
+    /// Method names whose calls are never followed (`clone`, `log`)
+    pub skip_calls: Vec<String>,
+    /// Only index files under these SPDX licenses; files with no license
+    /// found are indexed but reported. No filter when empty.
+    pub license_allow: Vec<String>,
+    /// Also index the functions macros generate, read from `cargo expand`
+    /// output
+    pub expand: bool,
+    /// The Cargo profile to expand under, dev when `None`
+    pub profile: Option<String>,
+}
+
+/// Resolved call edges: each function's qualified name to the qualified
+/// names of the project functions it calls.
+pub type CallGraph = HashMap<String, HashSet<String>>;
+
+/// The rule that turned a call name into a call edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Pinned in `gather-overrides.toml`
+    Pinned,
+    /// The only function with the called name
+    Unique,
+    /// The caller calls it by module path (`crate::net::fetch()`), or by a
+    /// name a `use` declaration imports from there
+    Path,
+    /// Several methods share the name and one project trait declares it:
+    /// the caller calls the trait method (on a `dyn Trait` or a generic)
+    Trait,
+    /// An implementation of the trait method the caller declares
+    Implementation,
+    /// Several functions share the name; the one in the caller's module won
+    SameModule { candidates: usize },
+    /// Several functions share the name and none is in the caller's module,
+    /// or several are (methods of other types), so the first one indexed
+    /// was taken
+    FirstOfMany { candidates: usize },
+    /// The caller names a type deriving this proc macro
+    Derive,
+    /// The caller reads this lazily initialized global
+    Global,
+    /// Operator sugar on a project type (`--operators`)
+    Operator,
+    /// Another `#[cfg]` variant of the function the call resolved to
+    /// (`--all-variants`)
+    CfgVariant,
+}
+
+/// The function [`ProjectIndex::find_function`] settled on for a name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionMatch {
+    pub qualified_name: String,
+    /// Every function with the name, as (qualified name, module path), when
+    /// several have it; empty when the name was unambiguous
+    pub candidates: Vec<(String, String)>,
+    /// Whether the preferred module picked among the candidates; when it
+    /// didn't, the first candidate was taken
+    pub in_preferred_module: bool,
+}
+
+impl FunctionMatch {
+    fn unique(qualified_name: &str) -> FunctionMatch {
+        FunctionMatch {
+            qualified_name: qualified_name.to_string(),
+            candidates: Vec::new(),
+            in_preferred_module: false,
+        }
+    }
+}
+
+pub struct ProjectIndex {
+    /// Function definitions keyed by fully qualified name (module::function,
+    /// or module::Type::method for methods)
+    pub function_definitions: HashMap<String, FunctionInfo>,
+    /// Simple function name (and `Type::method` for methods) to (qualified
+    /// name, module path) variants
+    pub module_functions: HashMap<String, Vec<(String, String)>>,
+    /// Resolved call edges between qualified names
+    pub function_calls: CallGraph,
+    /// Type definitions keyed by simple type name
+    pub type_definitions: HashMap<String, Vec<TypeInfo>>,
+    /// Impl blocks keyed by the simple name of the implementing type
+    pub impls: HashMap<String, Vec<ImplInfo>>,
+    /// The later `#[cfg]` variants (`module::name#2`, ...) of a function,
+    /// keyed by its qualified name. Calls resolve to the first variant only.
+    pub cfg_variants: HashMap<String, Vec<String>>,
+    /// What building the index found worth telling the user, in order:
+    /// how many files were indexed, the languages detected, warnings such
+    /// as name collisions, and with `verbose` every file skipped. Nothing
+    /// is printed; the CLI writes these to stderr.
+    pub notes: Vec<String>,
+    // What the index was built from, kept for incremental updates
+    project_root: PathBuf,
+    options: IndexOptions,
+    codeowners: Option<CodeOwners>,
+    cache: Option<FileCache>,
+    overrides: Option<Overrides>,
+    proc_macro_crates: HashMap<PathBuf, Option<String>>,
+    /// The crates of a workspace by directory, each indexed under its name
+    workspace_crates: HashMap<PathBuf, String>,
+    derive_regex: Regex,
+    generated_files: usize,
+    /// Files left out or only partly indexed, with the reason, for --verbose
+    skipped: Vec<(PathBuf, String)>,
+    /// The `--include` and `--exclude` globs as (pattern, regex)
+    include: Vec<(String, Regex)>,
+    exclude: Vec<(String, Regex)>,
+}
+
+impl ProjectIndex {
+    pub fn build(project_root: &Path, options: &IndexOptions) -> io::Result<ProjectIndex> {
+        // Collect all supported source files in the project
+        let mut source_files = collect_source_files(project_root, !options.no_ignore, 0)?;
+        let mut notes = vec![format!(
+            "Found {} source files in project",
+            source_files.len()
+        )];
+
+        let codeowners = if options.owned_by.is_some() || options.annotate_owners {
+            let codeowners = CodeOwners::load(project_root);
+            if codeowners.is_none() {
+                notes.push("No CODEOWNERS file found; ownership is unknown".to_string());
+            }
+            codeowners
+        } else {
+            None
+        };
+
+        let mut index = ProjectIndex {
+            function_definitions: HashMap::new(),
+            module_functions: HashMap::new(),
+            function_calls: HashMap::new(),
+            type_definitions: HashMap::new(),
+            impls: HashMap::new(),
+            cfg_variants: HashMap::new(),
+            project_root: project_root.to_path_buf(),
+            options: options.clone(),
+            codeowners,
+            cache: options
+                .cache
+                .then(|| FileCache::load(project_root, &options.extract)),
+            overrides: None,
+            proc_macro_crates: HashMap::new(),
+            workspace_crates: workspace_crates(project_root),
+            derive_regex: Regex::new(r"#\[derive\(([^)]*)\)\]").unwrap(),
+            generated_files: 0,
+            skipped: Vec::new(),
+            notes,
+            include: compile_globs(&options.include),
+            exclude: compile_globs(&options.exclude),
+        };
+        if options.verbose {
+            index.skipped = skipped_files(project_root, !options.no_ignore, &source_files);
+        }
+
+        if !options.include.is_empty()
+            || !options.exclude.is_empty()
+            || !options.languages.is_empty()
+        {
+            let mut selected = Vec::new();
+            for path in source_files {
+                match index.glob_exclusion(&path) {
+                    Some(reason) if options.verbose => index.skipped.push((path, reason)),
+                    Some(_) => {}
+                    None => selected.push(path),
+                }
+            }
+            source_files = selected;
+            index.notes.push(format!(
+                "{} source files match --include/--exclude and the configured languages",
+                source_files.len()
+            ));
+        }
+
+        if let Some(team) = &options.owned_by {
+            let (owned, not_owned): (Vec<PathBuf>, Vec<PathBuf>) = source_files
+                .into_iter()
+                .partition(|path| index.is_owned(path));
+            source_files = owned;
+            if options.verbose {
+                index.skipped.extend(
+                    not_owned
+                        .into_iter()
+                        .map(|path| (path, format!("not owned by {} (--owned-by)", team))),
+                );
+            }
+            index.notes.push(format!(
+                "{} source files owned by {}",
+                source_files.len(),
+                team
+            ));
+        }
+
+        if !options.license_allow.is_empty() {
+            let mut licenses = Licenses::new(project_root);
+            let mut allowed = Vec::new();
+            let mut left_out: BTreeMap<String, usize> = BTreeMap::new();
+            let mut unlicensed = 0;
+            for path in source_files {
+                match licenses.license_of(&path) {
+                    Some(expression) if !license::allows(&expression, &options.license_allow) => {
+                        *left_out.entry(expression.clone()).or_default() += 1;
+                        if options.verbose {
+                            index.skipped.push((
+                                path,
+                                format!("licensed {}, not in --license-allow", expression),
+                            ));
+                        }
+                    }
+                    Some(_) => allowed.push(path),
+                    None => {
+                        unlicensed += 1;
+                        allowed.push(path);
+                    }
+                }
+            }
+            source_files = allowed;
+            let summary: Vec<String> = left_out
+                .iter()
+                .map(|(expression, count)| format!("{} ({})", expression, count))
+                .collect();
+            if !summary.is_empty() {
+                index
+                    .notes
+                    .push(format!("Left out files licensed {}", summary.join(", ")));
+            }
+            if unlicensed > 0 {
+                index.notes.push(format!(
+                    "No license found for {} source files; they are indexed, so check them \
+                     before sharing the output",
+                    unlicensed
+                ));
+            }
+            index.notes.push(format!(
+                "{} source files allowed by --license-allow",
+                source_files.len()
+            ));
+        }
+
+        // Each file goes to the backend for its extension as it is read;
+        // the counts only tell the user what was found
+        let languages = lang::detect_languages(&source_files);
+        if !languages.is_empty() {
+            let summary: Vec<String> = languages
+                .iter()
+                .map(|(language, count)| format!("{} ({})", language.name(), count))
+                .collect();
+            index
+                .notes
+                .push(format!("Detected languages: {}", summary.join(", ")));
+        }
+
+        // Build function definitions map with fully qualified names
+        let mut collisions: Vec<(String, String)> = Vec::new();
+        for path in &source_files {
+            collisions.extend(index.insert_file(path)?);
+        }
+
+        if let Some(cache) = &mut index.cache {
+            index.notes.push(format!(
+                "Reused {} of {} files from the stored index",
+                source_files.len() - cache.reparsed(),
+                source_files.len()
+            ));
+            if let Some(path) = cache.save(project_root)? {
+                index.notes.push(format!("Updated {}", path.display()));
+            }
+        }
+
+        if options.expand {
+            index.insert_expanded(&source_files);
+        }
+
+        if !index.workspace_crates.is_empty() {
+            let mut names: Vec<&str> = index
+                .workspace_crates
+                .values()
+                .map(|n| n.as_str())
+                .collect();
+            names.sort_unstable();
+            index
+                .notes
+                .push(format!("Indexed workspace crates: {}", names.join(", ")));
+        }
+
+        let names: Vec<&String> = index.proc_macro_crates.values().flatten().collect();
+        if !names.is_empty() {
+            let mut names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+            names.sort_unstable();
+            index
+                .notes
+                .push(format!("Indexed proc-macro crates: {}", names.join(", ")));
+        }
+
+        if index.generated_files > 0 {
+            index.notes.push(format!(
+                "Indexed {} generated protobuf/gRPC files signature-only",
+                index.generated_files
+            ));
+        }
+
+        index.report_collisions(&collisions);
+        if options.verbose {
+            index.report_skipped();
+        }
+
+        index.overrides = Overrides::load(project_root)?;
+        if let Some(overrides) = &index.overrides {
+            for target in overrides.targets() {
+                if !index.function_definitions.contains_key(target) {
+                    index.notes.push(format!(
+                        "Warning: {} pins a call to {}, which does not exist",
+                        overrides::FILE_NAME,
+                        target
+                    ));
+                }
+            }
+        }
+
+        // Build function call relationships
+        let names: Vec<String> = index.function_definitions.keys().cloned().collect();
+        let has_derives = index.has_derives();
+        let has_globals = index.has_globals();
+        for qualified_name in names {
+            let resolved_calls = index.resolve_calls(&qualified_name, has_derives, has_globals);
+            index.function_calls.insert(qualified_name, resolved_calls);
+        }
+
+        if options.operators {
+            let count = index.link_operators(None);
+            index.notes.push(format!("Linked {} operator calls", count));
+        }
+
+        Ok(index)
+    }
+
+    /// Re-indexes one file after it changed on disk or was deleted, for the
+    /// long-running `rpc` server and `--watch`. Only the file's own items
+    /// are replaced, and only the functions whose calls could now resolve
+    /// differently (callers of a removed or added name, users of a changed
+    /// type) are re-resolved, instead of rebuilding the whole resolution map.
+    pub fn update_file(&mut self, path: &Path) -> io::Result<()> {
+        // Names and types whose resolution may change
+        let mut affected_names: HashSet<String> = HashSet::new();
+        let mut affected_types: HashSet<String> = HashSet::new();
+
+        let removed: HashSet<String> = self
+            .function_definitions
+            .iter()
+            .filter(|(_, info)| info.path == path)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &removed {
+            self.function_definitions.remove(name);
+            self.function_calls.remove(name);
+        }
+        self.cfg_variants.retain(|name, variants| {
+            variants.retain(|variant| !removed.contains(variant));
+            !removed.contains(name) && !variants.is_empty()
+        });
+        self.module_functions.retain(|simple_name, variants| {
+            let before = variants.len();
+            variants.retain(|(qualified_name, _)| !removed.contains(qualified_name));
+            if variants.len() != before {
+                affected_names.insert(simple_name.clone());
+            }
+            !variants.is_empty()
+        });
+        self.type_definitions.retain(|type_name, definitions| {
+            let before = definitions.len();
+            definitions.retain(|t| t.path != path);
+            if definitions.len() != before {
+                affected_types.insert(type_name.clone());
+            }
+            !definitions.is_empty()
+        });
+        self.impls.retain(|type_name, impls| {
+            let before = impls.len();
+            // The declarations of an implemented trait link its methods
+            affected_types.extend(
+                impls
+                    .iter()
+                    .filter(|i| i.path == path)
+                    .filter_map(|i| i.trait_name.clone()),
+            );
+            impls.retain(|i| i.path != path);
+            if impls.len() != before {
+                affected_types.insert(type_name.clone());
+            }
+            !impls.is_empty()
+        });
+
+        let in_build_output = !self.options.no_ignore && path.ancestors().any(is_build_output);
+        if path.is_file()
+            && Language::from_path(path).is_some_and(Language::is_supported)
+            && self.is_owned(path)
+            && self.glob_exclusion(path).is_none()
+            && self.is_licensed(path)
+            && !in_build_output
+        {
+            let collisions = self.insert_file(path)?;
+            self.report_collisions(&collisions);
+        }
+
+        let added: HashSet<&String> = self
+            .function_definitions
+            .iter()
+            .filter(|(_, info)| info.path == path)
+            .map(|(name, _)| name)
+            .collect();
+        // Calls by path name the qualified name itself
+        affected_names.extend(
+            removed
+                .iter()
+                .chain(added.iter().copied())
+                .map(|name| format!("path({})", name)),
+        );
+        for (simple_name, variants) in &self.module_functions {
+            if variants
+                .iter()
+                .any(|(qualified_name, _)| added.contains(qualified_name))
+            {
+                affected_names.insert(simple_name.clone());
+            }
+        }
+        for (type_name, definitions) in &self.type_definitions {
+            if definitions.iter().any(|t| t.path == path) {
+                affected_types.insert(type_name.clone());
+            }
+        }
+        affected_types.extend(
+            self.impls
+                .values()
+                .flatten()
+                .filter(|i| i.path == path)
+                .filter_map(|i| i.trait_name.clone()),
+        );
+
+        // Globals are reached by the readers naming them, not by a call
+        let affected_globals: HashSet<&str> = affected_names
+            .iter()
+            .filter_map(|name| name.strip_prefix("static(")?.strip_suffix(')'))
+            .collect();
+        let to_resolve: HashSet<String> = self
+            .function_definitions
+            .iter()
+            .filter(|(_, info)| {
+                info.path == path
+                    || info
+                        .calls
+                        .iter()
+                        .any(|c| affected_names.contains(c.strip_prefix('.').unwrap_or(c)))
+                    || info
+                        .self_type
+                        .as_ref()
+                        .is_some_and(|t| affected_types.contains(t))
+                    || ((!affected_types.is_empty() || !affected_globals.is_empty())
+                        && info
+                            .definition
+                            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                            .any(|word| {
+                                affected_types.contains(word) || affected_globals.contains(word)
+                            }))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        let has_derives = self.has_derives();
+        let has_globals = self.has_globals();
+        for qualified_name in &to_resolve {
+            let resolved_calls = self.resolve_calls(qualified_name, has_derives, has_globals);
+            self.function_calls
+                .insert(qualified_name.clone(), resolved_calls);
+        }
+        if self.options.operators {
+            self.link_operators(Some(&to_resolve));
+        }
+
+        // Pinned edges into the removed functions have nothing left to point at
+        for calls in self.function_calls.values_mut() {
+            calls.retain(|callee| {
+                !removed.contains(callee) || self.function_definitions.contains_key(callee)
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Parses one file and adds its functions, types and impls, returning the
+    /// qualified names that collided with an existing definition as
+    /// (original, renamed) pairs.
+    fn insert_file(&mut self, path: &Path) -> io::Result<Vec<(String, String)>> {
+        let module_path = self.module_path_of(path);
+        let items = match &mut self.cache {
+            Some(cache) => cache.items(path, &module_path, &self.options.extract)?,
+            None => lang::process_file(path, &module_path, &self.options.extract)?,
+        };
+        if items.generated {
+            self.generated_files += 1;
+            if self.options.verbose {
+                self.skipped.push((
+                    path.to_path_buf(),
+                    "generated protobuf/gRPC code, indexed signature-only".to_string(),
+                ));
+            }
+        }
+        if self.options.verbose && fs::read(path).is_ok_and(|bytes| str::from_utf8(&bytes).is_err())
+        {
+            self.skipped.push((
+                path.to_path_buf(),
+                "not valid UTF-8, indexed with the invalid bytes replaced".to_string(),
+            ));
+        }
+        Ok(self.insert_items(path, &module_path, items))
+    }
+
+    /// Adds the items extracted from `path` to the index, and returns the
+    /// function names they collided with.
+    fn insert_items(
+        &mut self,
+        path: &Path,
+        module_path: &str,
+        items: FileItems,
+    ) -> Vec<(String, String)> {
+        let module_path = module_path.to_string();
+        let owners = self.owners_of(path);
+        let mut file_functions: HashMap<String, String> = HashMap::new();
+        let mut collisions = Vec::new();
+
+        for (name, info) in items.types {
+            self.type_definitions.entry(name).or_default().push(info);
+        }
+
+        for impl_info in items.impls.iter().cloned() {
+            self.impls
+                .entry(impl_info.self_type.clone())
+                .or_default()
+                .push(impl_info);
+        }
+
+        // In line order, so the first of several functions sharing a name
+        // is the same on every run
+        let mut functions: Vec<(String, FunctionInfo)> = items.functions.into_iter().collect();
+        functions.sort_by(|(a, a_info), (b, b_info)| {
+            a_info.line_number.cmp(&b_info.line_number).then(a.cmp(b))
+        });
+        for (name, mut info) in functions {
+            info.owners = owners.clone();
+            if !self.options.all_variants {
+                info.cfg = None;
+            }
+
+            // Methods answer to their bare name and to `Type::name`.
+            // Generated proto code repeats `new`/`encode`/`decode` thousands
+            // of times: keep signatures only, don't follow its calls, and make
+            // it reachable only through qualified calls (`UserRequest::new(...)`)
+            let simple_name = name.rsplit("::").next().unwrap_or(&name).to_string();
+            let call_names = if items.generated {
+                info.definition = format!("{} {{ ... }}", signature(&info.definition).trim_end());
+                info.calls.clear();
+                match info.self_type {
+                    Some(_) => vec![name.clone()],
+                    None => vec![format!(
+                        "{}::{}",
+                        module_path.rsplit("::").next().unwrap_or(&module_path),
+                        name
+                    )],
+                }
+            } else if name.contains('#') {
+                // Later cfg variants are only reached through the first
+                Vec::new()
+            } else if info.self_type.is_some() {
+                vec![simple_name, name.clone()]
+            } else {
+                vec![name.clone()]
+            };
+
+            // Store with fully qualified name (module::function). Two files
+            // can map to the same module path (`foo.rs` and `foo/mod.rs`),
+            // so never overwrite an existing definition
+            let mut qualified_name = format!("{}::{}", module_path, name);
+            if self.function_definitions.contains_key(&qualified_name) {
+                let original = qualified_name.clone();
+                let mut suffix = 2;
+                while self.function_definitions.contains_key(&qualified_name) {
+                    qualified_name = format!("{}#{}", original, suffix);
+                    suffix += 1;
+                }
+                collisions.push((original, qualified_name.clone()));
+            }
+            self.function_definitions
+                .insert(qualified_name.clone(), info);
+            file_functions.insert(name.clone(), qualified_name.clone());
+            if let Some((base, _)) = name.split_once('#') {
+                self.cfg_variants
+                    .entry(format!("{}::{}", module_path, base))
+                    .or_default()
+                    .push(qualified_name.clone());
+            }
+
+            // Store simple name to module mapping
+            for call_name in call_names {
+                self.module_functions
+                    .entry(call_name)
+                    .or_default()
+                    .push((qualified_name.clone(), module_path.clone()));
+            }
+        }
+
+        for (alias, name) in items.aliases {
+            if let Some(qualified_name) = file_functions.get(&name) {
+                self.module_functions
+                    .entry(alias)
+                    .or_default()
+                    .push((qualified_name.clone(), module_path.clone()));
+            }
+        }
+
+        collisions
+    }
+
+    /// Indexes the functions in `cargo expand` output that aren't in the
+    /// source, each at the file and module the expansion maps back to.
+    /// Only `source_files` are added to, so the file filters still apply.
+    fn insert_expanded(&mut self, source_files: &[PathBuf]) {
+        let modules = match expand::expand(
+            &self.project_root,
+            self.options.profile.as_deref(),
+            &mut self.notes,
+        ) {
+            Ok(modules) => modules,
+            Err(message) => {
+                self.notes.push(format!("Macros not expanded: {}", message));
+                return;
+            }
+        };
+        let mut generated = 0;
+        for module in modules {
+            if !source_files.contains(&module.path) {
+                continue;
+            }
+            let module_path = self.module_path_of(&module.path);
+            let content = fs::read_to_string(&module.path).unwrap_or_default();
+            let mut items = lang::extract(
+                &module.source,
+                &module.path,
+                &module_path,
+                &self.options.extract,
+            );
+            // Everything else was read from the source already
+            items.functions.retain(|name, _| {
+                !name.contains('#')
+                    && !name.ends_with('!')
+                    && !self
+                        .function_definitions
+                        .contains_key(&format!("{}::{}", module_path, name))
+            });
+            for (name, info) in &mut items.functions {
+                let simple_name = name.rsplit("::").next().unwrap_or(name);
+                info.line_number = expand::invocation_line(&content, simple_name);
+            }
+            items.types.clear();
+            items.impls.clear();
+            items.aliases.clear();
+            generated += items.functions.len();
+            self.insert_items(&module.path, &module_path, items);
+        }
+        self.notes.push(format!(
+            "Indexed {} macro-generated functions from cargo expand",
+            generated
+        ));
+    }
+
+    fn report_collisions(&mut self, collisions: &[(String, String)]) {
+        if collisions.is_empty() {
+            return;
+        }
+        self.notes.push(format!(
+            "Warning: {} functions share a qualified name with a definition in another file; all are kept:",
+            collisions.len()
+        ));
+        for (original, renamed) in collisions {
+            let describe = |name: &String| {
+                let info = &self.function_definitions[name];
+                format!(
+                    "{} ({}:{})",
+                    name,
+                    info.path.to_string_lossy(),
+                    info.line_number
+                )
+            };
+            let note = format!("  {} vs {}", describe(original), describe(renamed));
+            self.notes.push(note);
+        }
+    }
+
+    /// The module path a file's items are indexed under.
+    fn module_path_of(&mut self, path: &Path) -> String {
+        let namespace = crate_namespace_of(
+            path,
+            &self.project_root,
+            &self.workspace_crates,
+            &mut self.proc_macro_crates,
+        );
+        match namespace {
+            // Workspace crates and proc-macro crates get their own
+            // namespace, as other crates refer to them
+            Some((crate_name, crate_root)) => {
+                let src = crate_root.join("src");
+                let base = if path.starts_with(&src) {
+                    src
+                } else {
+                    crate_root
+                };
+                match extract_module_path(path, &base).as_str() {
+                    "lib" => crate_name,
+                    module => format!("{}::{}", crate_name, module),
+                }
+            }
+            None => extract_module_path(path, &self.project_root),
+        }
+    }
+
+    fn owners_of(&self, path: &Path) -> Vec<String> {
+        let relative = path.strip_prefix(&self.project_root).unwrap_or(path);
+        self.codeowners
+            .as_ref()
+            .map(|c| c.owners_of(relative).to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Whether a file passes the `--owned-by` filter.
+    fn is_owned(&self, path: &Path) -> bool {
+        match &self.options.owned_by {
+            Some(team) => self
+                .owners_of(path)
+                .iter()
+                .any(|owner| owner.eq_ignore_ascii_case(team)),
+            None => true,
+        }
+    }
+
+    /// Whether a file passes the `--license-allow` filter; files with no
+    /// license found do.
+    fn is_licensed(&self, path: &Path) -> bool {
+        self.options.license_allow.is_empty()
+            || Licenses::new(&self.project_root)
+                .license_of(path)
+                .is_none_or(|expression| license::allows(&expression, &self.options.license_allow))
+    }
+
+    /// Why `--include`/`--exclude` or the configured languages leave a file
+    /// out, or `None` when they keep it.
+    fn glob_exclusion(&self, path: &Path) -> Option<String> {
+        if let Some(language) = Language::from_path(path).filter(|language| {
+            !self.options.languages.is_empty() && !self.options.languages.contains(language)
+        }) {
+            return Some(format!("{} is not a configured language", language.name()));
+        }
+        let relative = path.strip_prefix(&self.project_root).unwrap_or(path);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if let Some((pattern, _)) = self
+            .exclude
+            .iter()
+            .find(|(_, regex)| regex.is_match(&relative))
+        {
+            return Some(format!("matches --exclude {}", pattern));
+        }
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|(_, regex)| regex.is_match(&relative));
+        (!included).then(|| "matches no --include glob".to_string())
+    }
+
+    /// Whether `called_fn` is to a method name the project configured as
+    /// never followed, however the call is qualified.
+    fn is_skipped_call(&self, called_fn: &str) -> bool {
+        let name = shown(called_fn);
+        let name = name.rsplit("::").next().unwrap_or(name);
+        self.options
+            .skip_calls
+            .iter()
+            .any(|skipped| skipped == name)
+    }
+
+    /// Whether any proc-macro derive is indexed, which is when derive edges
+    /// need computing at all.
+    fn has_derives(&self) -> bool {
+        self.module_functions
+            .keys()
+            .any(|name| name.starts_with("derive("))
+    }
+
+    /// Whether any lazily initialized global is indexed, which is when
+    /// functions need scanning for the globals they read.
+    fn has_globals(&self) -> bool {
+        self.module_functions
+            .keys()
+            .any(|name| name.starts_with("static("))
+    }
+
+    /// Resolves the calls a function makes to qualified names.
+    fn resolve_calls(
+        &self,
+        qualified_name: &str,
+        has_derives: bool,
+        has_globals: bool,
+    ) -> HashSet<String> {
+        let info = &self.function_definitions[qualified_name];
+
+        // A function touching a type reaches the derive macros that
+        // generated the type's impls
+        let derive_calls = if has_derives {
+            derives_used(info, &self.type_definitions, &self.derive_regex)
+        } else {
+            Vec::new()
+        };
+
+        let mut resolved: HashSet<String> = info
+            .calls
+            .iter()
+            .chain(&derive_calls)
+            .filter_map(|called_fn| self.resolve_call(qualified_name, called_fn))
+            .map(|(target, _)| target)
+            .collect();
+
+        // A function reading a lazily initialized global reaches its
+        // initializer; the global's own definition names it too
+        if has_globals {
+            resolved.extend(
+                globals_used(info)
+                    .iter()
+                    .filter_map(|global| self.resolve_call(qualified_name, global))
+                    .map(|(target, _)| target)
+                    .filter(|target| target != qualified_name),
+            );
+        }
+        // A trait method reaches every implementation a call through the
+        // trait may dispatch to
+        resolved.extend(self.implementations(qualified_name));
+        if self.options.all_variants {
+            let variants: Vec<String> = resolved
+                .iter()
+                .flat_map(|target| self.cfg_variants_of(target))
+                .collect();
+            resolved.extend(variants);
+        }
+        resolved
+    }
+
+    /// The trait declaring `qualified_name` when it is a trait method,
+    /// required or default.
+    fn trait_of(&self, qualified_name: &str) -> Option<&str> {
+        let info = &self.function_definitions[qualified_name];
+        let self_type = info.self_type.as_deref()?;
+        let is_trait = self.type_definitions.get(self_type)?.iter().any(|t| {
+            t.path == info.path
+                && without_preamble(&t.definition)
+                    .split_whitespace()
+                    .take_while(|word| *word != self_type)
+                    .any(|word| word == "trait")
+        });
+        is_trait.then_some(self_type)
+    }
+
+    /// The methods implementing the trait method `qualified_name` in the
+    /// project's `impl Trait for Type` blocks, only for the `--impls` types
+    /// when given. Empty for anything but a trait method.
+    pub fn implementations(&self, qualified_name: &str) -> Vec<String> {
+        let Some(trait_name) = self.trait_of(qualified_name) else {
+            return Vec::new();
+        };
+        let key = qualified_name.split('#').next().unwrap_or(qualified_name);
+        let method = key.rsplit("::").next().unwrap_or(key);
+        let mut implementations = Vec::new();
+        for impl_info in self.impls.values().flatten() {
+            if impl_info.trait_name.as_deref() != Some(trait_name)
+                || !(self.options.impls.is_empty()
+                    || self.options.impls.contains(&impl_info.self_type))
+            {
+                continue;
+            }
+            let Some(options) = self
+                .module_functions
+                .get(&format!("{}::{}", impl_info.self_type, method))
+            else {
+                continue;
+            };
+            // The method defined inside this impl block, not an inherent
+            // method of the same name
+            implementations.extend(
+                options
+                    .iter()
+                    .map(|(full_name, _)| full_name)
+                    .filter(|full_name| {
+                        let info = &self.function_definitions[*full_name];
+                        info.path == impl_info.path
+                            && impl_info.line_number <= info.line_number
+                            && info.line_number <= impl_info.end_line
+                    })
+                    .cloned(),
+            );
+        }
+        implementations.sort();
+        implementations
+    }
+
+    /// The other `#[cfg]` variants of a function, in definition order.
+    pub fn cfg_variants_of(&self, qualified_name: &str) -> Vec<String> {
+        let first = match self.cfg_variants.get(qualified_name) {
+            Some(_) => qualified_name,
+            None => qualified_name
+                .rsplit_once('#')
+                .map_or(qualified_name, |(first, _)| first),
+        };
+        let Some(variants) = self.cfg_variants.get(first) else {
+            return Vec::new();
+        };
+        let mut all = vec![first.to_string()];
+        all.extend(variants.iter().cloned());
+        all.retain(|name| name != qualified_name && self.function_definitions.contains_key(name));
+        all
+    }
+
+    /// Resolves one call name made by `qualified_name` to a qualified
+    /// function name, with the rule that picked it.
+    fn resolve_call(&self, qualified_name: &str, called_fn: &str) -> Option<(String, Resolution)> {
+        // Pinned resolutions take precedence over any heuristic
+        // (pins written for a bare method name cover `self.name()` too)
+        let pinned = self
+            .overrides
+            .as_ref()
+            .and_then(|o| {
+                o.target(qualified_name, shown(called_fn)).or_else(|| {
+                    let method = called_fn.strip_prefix("Self::")?;
+                    o.target(qualified_name, method)
+                })
+            })
+            .filter(|target| self.function_definitions.contains_key(*target));
+        if let Some(target) = pinned {
+            return Some((target.to_string(), Resolution::Pinned));
+        }
+        if self.is_skipped_call(called_fn) {
+            return None;
+        }
+
+        // A call by module path names its function outright; a path that
+        // names no definition (a re-export) resolves by its last segments
+        // like any other call
+        if let Some(path) = called_fn
+            .strip_prefix("path(")
+            .and_then(|path| path.strip_suffix(')'))
+        {
+            if self.function_definitions.contains_key(path) {
+                return Some((path.to_string(), Resolution::Path));
+            }
+            let mut segments = path.rsplit("::");
+            let name = segments.next()?;
+            let qualifier = segments.next()?;
+            return self
+                .resolve_call(qualified_name, &format!("{}::{}", qualifier, name))
+                .or_else(|| {
+                    let module = !qualifier.starts_with(|c: char| c.is_uppercase());
+                    module.then(|| self.resolve_call(qualified_name, name))?
+                });
+        }
+
+        let caller = &self.function_definitions[qualified_name];
+
+        // The Rust backend records `.name()` calls as `.name`: in Rust a
+        // method call only reaches a method, and a plain `name()` call only
+        // a free function
+        let (called_fn, method_call) = match called_fn.strip_prefix('.') {
+            Some(method) => (method, true),
+            None => (called_fn, false),
+        };
+        let by_kind = caller.language == Language::Rust
+            && called_fn.chars().all(|c| c.is_alphanumeric() || c == '_');
+
+        // `Self::bar` and `self.bar()` name a method of the caller's type
+        let called_fn = match (called_fn.strip_prefix("Self::"), &caller.self_type) {
+            (Some(method), Some(self_type)) => format!("{}::{}", self_type, method),
+            (Some(method), None) => method.to_string(),
+            (None, _) => called_fn.to_string(),
+        };
+
+        // Try to resolve the called function to its qualified name. A call
+        // through a project type with no such method of its own reaches the
+        // method by name (trait methods); through any other type (`Vec::new`)
+        // it reaches nothing.
+        let options = match self.module_functions.get(&called_fn) {
+            Some(options) => options,
+            None => {
+                let (qualifier, method) = called_fn.rsplit_once("::")?;
+                let through_type = self.type_definitions.contains_key(qualifier)
+                    || self.impls.contains_key(qualifier);
+                if !through_type {
+                    return None;
+                }
+                self.module_functions.get(method)?
+            }
+        };
+        let options: Vec<&(String, String)> = options
+            .iter()
+            .filter(|(full_name, _)| {
+                let info = &self.function_definitions[full_name];
+                // A method call needs a `self` receiver: an iterator's
+                // `.find()` never reaches an associated `Source::find(path)`
+                let receives_self = info.self_type.is_some()
+                    && (info.language != Language::Rust || takes_self(signature(&info.definition)));
+                match (by_kind, method_call) {
+                    (false, _) => true,
+                    (true, true) => receives_self,
+                    (true, false) => info.self_type.is_none(),
+                }
+            })
+            .collect();
+        if options.is_empty() {
+            return None;
+        }
+        if options.len() == 1 {
+            // Only one function with this name
+            return Some((options[0].0.clone(), Resolution::Unique));
+        }
+
+        // Methods of several types sharing a name one project trait declares
+        // are called through the trait: the call reaches the declaration,
+        // and through it the implementations
+        let methods = options
+            .iter()
+            .all(|(full_name, _)| self.function_definitions[full_name].self_type.is_some());
+        if methods && !called_fn.contains("::") {
+            let mut trait_methods = options
+                .iter()
+                .filter(|(full_name, _)| self.trait_of(full_name).is_some());
+            if let (Some((full_name, _)), None) = (trait_methods.next(), trait_methods.next()) {
+                return Some((full_name.clone(), Resolution::Trait));
+            }
+        }
+
+        // Multiple functions with this name - prefer same module, and
+        // there a method of the caller's own type
+        let candidates = options.len();
+        let same_module: Vec<&(String, String)> = options
+            .iter()
+            .copied()
+            .filter(|(_, m)| *m == caller.module_path)
+            .collect();
+        let same_type = same_module.iter().find(|(full_name, _)| {
+            caller.self_type.is_some()
+                && self.function_definitions[full_name].self_type == caller.self_type
+        });
+        match (same_type, same_module.as_slice()) {
+            (Some((full_name, _)), _) | (None, [(full_name, _)]) => {
+                Some((full_name.clone(), Resolution::SameModule { candidates }))
+            }
+            // Default to the first one indexed, in the caller's module if
+            // several are
+            (None, same_module) => {
+                let (full_name, _) = same_module.first().unwrap_or(&options[0]);
+                Some((full_name.clone(), Resolution::FirstOfMany { candidates }))
+            }
+        }
+    }
+
+    /// Every call name in `caller`'s body, sorted, with the function and
+    /// rule it resolves to, or `None` when it names no project function
+    /// (the standard library, a dependency, a closure).
+    pub fn call_resolutions(&self, caller: &str) -> Vec<(String, Option<(String, Resolution)>)> {
+        let Some(info) = self.function_definitions.get(caller) else {
+            return Vec::new();
+        };
+        let mut calls: Vec<&String> = info.calls.iter().collect();
+        calls.sort();
+        calls
+            .into_iter()
+            .filter(|called_fn| !self.is_skipped_call(called_fn))
+            .map(|called_fn| {
+                (
+                    shown(called_fn).to_string(),
+                    self.resolve_call(caller, called_fn),
+                )
+            })
+            .collect()
+    }
+
+    /// How the call edge from `caller` to `callee` came about: the call
+    /// name in the caller's body and the rule that resolved it.
+    pub fn resolution(&self, caller: &str, callee: &str) -> Option<(String, Resolution)> {
+        let info = self.function_definitions.get(caller)?;
+
+        let mut calls: Vec<&String> = info.calls.iter().collect();
+        calls.sort();
+        let direct = calls.into_iter().find_map(|called_fn| {
+            self.resolve_call(caller, called_fn)
+                .filter(|(target, _)| target == callee)
+                .map(|(_, resolution)| (shown(called_fn).to_string(), resolution))
+        });
+        if direct.is_some() {
+            return direct;
+        }
+
+        let derive = derives_used(info, &self.type_definitions, &self.derive_regex)
+            .into_iter()
+            .find(|derive| {
+                self.resolve_call(caller, derive)
+                    .is_some_and(|(target, _)| target == callee)
+            });
+        if let Some(derive) = derive {
+            return Some((derive, Resolution::Derive));
+        }
+
+        let global = globals_used(info).into_iter().find(|global| {
+            self.resolve_call(caller, global)
+                .is_some_and(|(target, _)| target == callee)
+        });
+        if let Some(global) = global {
+            let name = global["static(".len()..global.len() - 1].to_string();
+            return Some((name, Resolution::Global));
+        }
+
+        if self.implementations(caller).iter().any(|i| i == callee) {
+            let method = callee.rsplit("::").next().unwrap_or(callee);
+            return Some((method.to_string(), Resolution::Implementation));
+        }
+
+        let only = HashSet::from([caller.to_string()]);
+        let operator = operators::operator_calls(self, Some(&only))
+            .get(caller)
+            .is_some_and(|targets| targets.iter().any(|target| target == callee));
+        if operator {
+            return Some((
+                callee.rsplit("::").next().unwrap_or(callee).to_string(),
+                Resolution::Operator,
+            ));
+        }
+
+        // A variant is reached through the call that resolved to the first
+        if !self.options.all_variants || !callee.contains('#') {
+            return None;
+        }
+        let first = callee.rsplit_once('#')?.0;
+        let (call, _) = self.resolution(caller, first)?;
+        Some((call, Resolution::CfgVariant))
+    }
+
+    /// Adds operator impl edges for `only` the given functions, or all of
+    /// them, returning how many were linked.
+    fn link_operators(&mut self, only: Option<&HashSet<String>>) -> usize {
+        let edges = operators::operator_calls(self, only);
+        let count: usize = edges.values().map(Vec::len).sum();
+        for (caller, targets) in edges {
+            self.function_calls
+                .entry(caller)
+                .or_default()
+                .extend(targets);
+        }
+        count
+    }
+
+    /// The innermost function defined around `line` (1-based) of a file
+    /// whose path ends with `path`, or that `path` names absolutely.
+    pub fn function_at(&self, path: &str, line: usize) -> Option<String> {
+        let path = Path::new(path.trim_start_matches("./"));
+        let root = self
+            .project_root
+            .canonicalize()
+            .unwrap_or_else(|_| self.project_root.clone());
+        let path = path.strip_prefix(&root).unwrap_or(path);
+        self.function_definitions
+            .iter()
+            .filter(|(_, info)| info.path.ends_with(path))
+            .filter(|(_, info)| {
+                info.line_number <= line
+                    && line < info.line_number + info.definition.lines().count()
+            })
+            .max_by_key(|(_, info)| info.line_number)
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Lists the files `skipped` holds, in path order.
+    fn report_skipped(&mut self) {
+        self.skipped.sort();
+        for (path, reason) in &self.skipped {
+            self.notes
+                .push(format!("Skipped {}: {}", path.display(), reason));
+        }
+        self.notes.push(format!(
+            "{} files skipped or partly indexed",
+            self.skipped.len()
+        ));
+    }
+
+    /// The function `target_function` names: a qualified name as is, else
+    /// the function with that simple name (or `Type::method`). When several
+    /// have it, the one whose module path contains `preferred_module` is
+    /// taken, else the first, and the result lists them all.
+    pub fn find_function(
+        &self,
+        target_function: &str,
+        preferred_module: Option<&String>,
+    ) -> Option<FunctionMatch> {
+        // Names are indexed without raw prefixes (`r#type` as `type`)
+        let target_function = &target_function
+            .split("::")
+            .map(unraw)
+            .collect::<Vec<_>>()
+            .join("::");
+        let target_function = target_function.as_str();
+
+        // Fully qualified names can be used as-is
+        if self.function_definitions.contains_key(target_function) {
+            return Some(FunctionMatch::unique(target_function));
+        }
+
+        let variants = self.module_functions.get(target_function)?;
+        if let [(qualified_name, _)] = variants.as_slice() {
+            return Some(FunctionMatch::unique(qualified_name));
+        }
+
+        // Several functions share the name: the one in the preferred module,
+        // else the first
+        let preferred = preferred_module.and_then(|module| {
+            variants
+                .iter()
+                .find(|(_, mod_path)| mod_path.contains(module.as_str()))
+        });
+        Some(FunctionMatch {
+            qualified_name: preferred.unwrap_or(&variants[0]).0.clone(),
+            candidates: variants.clone(),
+            in_preferred_module: preferred.is_some(),
+        })
+    }
+
+    /// Qualified names whose simple name contains `fragment`, for suggestions.
+    pub fn partial_matches(&self, fragment: &str) -> Vec<(String, String)> {
+        let mut matches = Vec::new();
+        for (name, variants) in &self.module_functions {
+            if name.contains(fragment) {
+                for (qualified_name, module) in variants {
+                    matches.push((qualified_name.clone(), module.clone()));
+                }
+            }
+        }
+        matches
+    }
+
+    /// Type definitions whose names appear in a function's signature.
+    pub fn signature_types(&self, info: &FunctionInfo) -> Vec<&TypeInfo> {
+        self.types_in(signature(&info.definition), info.language)
+    }
+
+    /// Type definitions whose names appear in a function's parameter list.
+    pub fn parameter_types(&self, info: &FunctionInfo) -> Vec<&TypeInfo> {
+        self.types_in(parameter_list(signature(&info.definition)), info.language)
+    }
+
+    /// Type definitions for every project type named in `text`, in order of
+    /// first mention.
+    pub fn types_in(&self, text: &str, language: Language) -> Vec<&TypeInfo> {
+        let mut seen = HashSet::new();
+        let mut types = Vec::new();
+        for word in text.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+            if !seen.insert(word) {
+                continue;
+            }
+            if let Some(definitions) = self.type_definitions.get(word) {
+                // Prefer a definition in the same language as the function
+                let definition = definitions
+                    .iter()
+                    .find(|t| t.language == language)
+                    .unwrap_or(&definitions[0]);
+                types.push(definition);
+            }
+        }
+        types
+    }
+
+    /// The impl block a function is defined in, if any.
+    pub fn enclosing_impl(&self, info: &FunctionInfo) -> Option<&ImplInfo> {
+        self.impls.values().flatten().find(|impl_info| {
+            impl_info.path == info.path
+                && impl_info.line_number < info.line_number
+                && info.line_number <= impl_info.end_line
+        })
+    }
+
+    /// Test functions that directly call any of `functions`.
+    pub fn tests_calling(&self, functions: &[String]) -> Vec<String> {
+        let targets: HashSet<&String> = functions.iter().collect();
+        let mut tests: Vec<String> = self
+            .function_definitions
+            .iter()
+            .filter(|(name, info)| info.is_test && !targets.contains(name))
+            .filter(|(name, _)| {
+                self.function_calls
+                    .get(*name)
+                    .is_some_and(|calls| calls.iter().any(|c| targets.contains(c)))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        tests.sort();
+        tests
+    }
+
+    /// Breadth-first traversal of the call graph from `roots`, returning
+    /// every reachable function in visit order.
+    pub fn reachable(&self, roots: &[String]) -> Vec<String> {
+        self.reachable_within(roots, None)
+    }
+
+    /// Like [`reachable`](Self::reachable), following at most `max_depth`
+    /// calls from the roots when given.
+    pub fn reachable_within(&self, roots: &[String], max_depth: Option<usize>) -> Vec<String> {
+        self.traverse(&self.function_calls, roots, max_depth)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// The functions [`reachable_within`](Self::reachable_within) returns,
+    /// each with the fewest calls it takes to reach it from the roots.
+    pub fn depths_within(
+        &self,
+        roots: &[String],
+        max_depth: Option<usize>,
+    ) -> HashMap<String, usize> {
+        self.traverse(&self.function_calls, roots, max_depth)
+            .into_iter()
+            .collect()
+    }
+
+    /// Every function that calls `roots`, directly or transitively, in
+    /// breadth-first order from the roots (which come first), following at
+    /// most `max_depth` calls back when given.
+    pub fn callers_within(&self, roots: &[String], max_depth: Option<usize>) -> Vec<String> {
+        self.traverse(&self.callers_graph(), roots, max_depth)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Test functions whose call trees reach any of `roots`, each with the
+    /// number of calls between them, closest first.
+    pub fn tests_reaching(&self, roots: &[String]) -> Vec<(String, usize)> {
+        let mut tests: Vec<(String, usize)> = self
+            .traverse(&self.callers_graph(), roots, None)
+            .into_iter()
+            .filter(|(name, _)| self.function_definitions[name].is_test)
+            .collect();
+        tests.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        tests
+    }
+
+    /// The call graph reversed: each function to the functions calling it.
+    pub fn callers_graph(&self) -> CallGraph {
+        let mut callers: CallGraph = HashMap::new();
+        for (caller, callees) in &self.function_calls {
+            for callee in callees {
+                callers
+                    .entry(callee.clone())
+                    .or_default()
+                    .insert(caller.clone());
+            }
+        }
+        callers
+    }
+
+    /// Breadth-first traversal of `graph`, returning each function reached
+    /// with its depth, in visit order.
+    fn traverse(
+        &self,
+        graph: &CallGraph,
+        roots: &[String],
+        max_depth: Option<usize>,
+    ) -> Vec<(String, usize)> {
+        let mut queue: VecDeque<(String, usize)> =
+            roots.iter().map(|root| (root.clone(), 0)).collect();
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+
+        while let Some((current_function, depth)) = queue.pop_front() {
+            if visited.contains(&current_function) {
+                continue;
+            }
+
+            visited.insert(current_function.clone());
+
+            if self.function_definitions.contains_key(&current_function) {
+                order.push((current_function.clone(), depth));
+
+                // Add all neighbouring functions to the queue
+                if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                    continue;
+                }
+                // In name order, so the output is the same on every run
+                if let Some(neighbours) = graph.get(&current_function) {
+                    let mut neighbours: Vec<&String> = neighbours.iter().collect();
+                    neighbours.sort();
+                    for neighbour in neighbours {
+                        queue.push_back((neighbour.clone(), depth + 1));
+                    }
+                }
+            }
+        }
+
+        order
+    }
+}
+
+/// The part of a definition before its body, after its doc comment and
+/// attributes.
+pub fn signature(definition: &str) -> &str {
+    let code = without_preamble(definition);
+    code.split_once('{')
+        .map_or(code, |(signature, _)| signature)
+}
+
+/// A definition's signature on one line, however it was wrapped.
+pub fn flat_signature(definition: &str) -> String {
+    signature(definition)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(", )", ")")
+        .replace(" )", ")")
+}
+
+/// The body of a definition from its opening brace, or nothing when it has
+/// none.
+pub fn body(definition: &str) -> &str {
+    let code = without_preamble(definition);
+    code.find('{').map_or("", |start| &code[start..])
+}
+
+/// A definition without the doc comment and attributes it starts with.
+pub fn without_preamble(definition: &str) -> &str {
+    &definition[lang::preamble_len(definition)..]
+}
+
+/// The parameter list of a signature, without the surrounding parentheses.
+pub fn parameter_list(signature: &str) -> &str {
+    parameter_span(signature).map_or("", |(start, end)| &signature[start..end])
+}
+
+/// Whether a Rust signature's first parameter is `self`, in any of its
+/// forms (`self`, `&'a mut self`, `self: Box<Self>`).
+pub fn takes_self(signature: &str) -> bool {
+    let first = parameter_list(signature).split(',').next().unwrap_or("");
+    let mut first = first.trim_start();
+    if let Some(borrowed) = first.strip_prefix('&') {
+        first = borrowed.trim_start();
+        if first.starts_with('\'') {
+            first = first
+                .split_once(char::is_whitespace)
+                .map_or("", |(_, rest)| rest);
+        }
+    }
+    let first = first.trim_start();
+    let first = first.strip_prefix("mut ").unwrap_or(first).trim_start();
+    first
+        .strip_prefix("self")
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
+/// The declared return type of a signature, if any.
+pub fn return_type(signature: &str) -> Option<&str> {
+    let (_, end) = parameter_span(signature)?;
+    let (_, returned) = signature[end..].split_once("->")?;
+    Some(returned.split(" where").next().unwrap_or(returned).trim())
+}
+
+/// Byte range of the parameter list's contents. Parentheses inside generic
+/// bounds (`F: Fn(u8)`) are skipped.
+fn parameter_span(signature: &str) -> Option<(usize, usize)> {
+    let mut angle_depth = 0;
+    let mut open = None;
+    let mut depth = 0;
+
+    for (i, c) in signature.char_indices() {
+        match (c, open) {
+            ('<', None) => angle_depth += 1,
+            ('>', None) if angle_depth > 0 => angle_depth -= 1,
+            ('(', None) if angle_depth == 0 => {
+                open = Some(i + 1);
+                depth = 1;
+            }
+            ('(', Some(_)) => depth += 1,
+            (')', Some(start)) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, i));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    open.map(|start| (start, signature.len()))
+}
+
+/// Normalizes the user-supplied root. A directory holding `Cargo.toml` or
+/// `.git` is used as given; a file, or a directory nested inside a project
+/// (e.g. `src/`), resolves to the nearest ancestor holding one of those
+/// markers so module paths come out the same wherever the tool is run from.
+pub fn resolve_project_root(path: &Path) -> io::Result<PathBuf> {
+    if path.is_dir() && is_project_root(path) {
+        return Ok(path.to_path_buf());
+    }
+
+    let absolute = path.canonicalize()?;
+    let start = if absolute.is_file() {
+        absolute.parent().unwrap_or(&absolute)
+    } else {
+        &absolute
+    };
+
+    match start.ancestors().find(|dir| is_project_root(dir)) {
+        Some(root) => Ok(root.to_path_buf()),
+        // No markers anywhere: keep a directory as given, or a file's folder
+        None if path.is_dir() => Ok(path.to_path_buf()),
+        None => Ok(start.to_path_buf()),
+    }
+}
+
+fn is_project_root(dir: &Path) -> bool {
+    dir.join("Cargo.toml").is_file() || dir.join(".git").exists()
+}
+
+/// The files with a source extension that `collect_source_files` left out,
+/// and why; build output directories are reported once, not walked.
+fn skipped_files(
+    root: &Path,
+    respect_ignores: bool,
+    source_files: &[PathBuf],
+) -> Vec<(PathBuf, String)> {
+    let found: HashSet<&PathBuf> = source_files.iter().collect();
+    let mut skipped = Vec::new();
+    let mut build_output = Vec::new();
+    // Build output directories are listed but not entered
+    let walker = WalkBuilder::new(root)
+        .standard_filters(false)
+        .filter_entry(move |entry| {
+            entry.file_name() != ".git"
+                && !(respect_ignores && entry.path().parent().is_some_and(is_build_output))
+        })
+        .build();
+    for entry in walker.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_some_and(|t| t.is_dir()) {
+            if respect_ignores && is_build_output(path) {
+                build_output.push(path.to_path_buf());
+            }
+            continue;
+        }
+        let Some(language) = Language::from_path(path) else {
+            continue;
+        };
+        if !language.is_supported() {
+            skipped.push((
+                path.to_path_buf(),
+                format!("{} needs the regex-backend feature", language.name()),
+            ));
+        } else if !found.contains(&path.to_path_buf()) {
+            skipped.push((
+                path.to_path_buf(),
+                "excluded by .gitignore, .git/info/exclude, the global git excludes or .ignore"
+                    .to_string(),
+            ));
+        }
+    }
+    skipped.extend(build_output.into_iter().map(|dir| {
+        (
+            dir,
+            "build output or dependency directory, not searched".to_string(),
+        )
+    }));
+    skipped
+}
+
+/// The source files under `root` in a backend this build has, sorted,
+/// walked on `threads` threads (0 for one per core). Public for the walk
+/// benchmark.
+#[doc(hidden)]
+pub fn collect_source_files(
+    root: &Path,
+    respect_ignores: bool,
+    threads: usize,
+) -> io::Result<Vec<PathBuf>> {
+    let found = Mutex::new(Vec::new());
+
+    // Walk directories on all cores, which matters most on network file
+    // systems where every readdir is a round trip
+    WalkBuilder::new(root)
+        .threads(threads)
+        .standard_filters(false)
+        .git_ignore(respect_ignores)
+        .git_exclude(respect_ignores)
+        .git_global(respect_ignores)
+        .ignore(respect_ignores)
+        .parents(respect_ignores)
+        // A checkout without `.git` (a tarball, a vendored copy) still has
+        // its ignore files
+        .require_git(false)
+        .filter_entry(move |entry| !respect_ignores || !is_build_output(entry.path()))
+        .build_parallel()
+        .run(|| {
+            Box::new(|entry| {
+                if let Ok(entry) = entry {
+                    let is_file = entry.file_type().is_some_and(|t| !t.is_dir());
+                    if is_file
+                        && Language::from_path(entry.path()).is_some_and(Language::is_supported)
+                    {
+                        found.lock().unwrap().push(entry.into_path());
+                    }
+                }
+                WalkState::Continue
+            })
+        });
+    let mut source_files = found.into_inner().unwrap();
+
+    // A stable order keeps disambiguation suffixes the same between runs
+    source_files.sort();
+
+    Ok(source_files)
+}
+
+/// Directories of generated code and downloaded dependencies, skipped even
+/// when no ignore file lists them: `target/` next to a `Cargo.toml`,
+/// `vendor/` next to a `go.mod`, `bin/` and `obj/` next to a `.csproj`,
+/// `node_modules/` and `.git/`.
+fn is_build_output(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    match name {
+        "node_modules" | ".git" => path.is_dir(),
+        "target" => {
+            path.is_dir()
+                && path
+                    .parent()
+                    .is_some_and(|parent| parent.join("Cargo.toml").is_file())
+        }
+        "vendor" => {
+            path.is_dir()
+                && path
+                    .parent()
+                    .is_some_and(|parent| parent.join("go.mod").is_file())
+        }
+        "bin" | "obj" => {
+            path.is_dir()
+                && path
+                    .parent()
+                    .and_then(|parent| fs::read_dir(parent).ok())
+                    .is_some_and(|mut entries| {
+                        entries.any(|entry| {
+                            entry.is_ok_and(|entry| {
+                                entry.path().extension().is_some_and(|e| e == "csproj")
+                            })
+                        })
+                    })
+        }
+        _ => false,
+    }
+}
+
+/// `derive(Name)` for every derive on a project type the function names.
+fn derives_used(
+    info: &FunctionInfo,
+    type_definitions: &HashMap<String, Vec<TypeInfo>>,
+    derive_regex: &Regex,
+) -> Vec<String> {
+    let mut derives = Vec::new();
+    let words: HashSet<&str> = info
+        .definition
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .collect();
+    for word in words {
+        for type_info in type_definitions.get(word).into_iter().flatten() {
+            for captures in derive_regex.captures_iter(&type_info.definition) {
+                for derive in captures[1].split(',') {
+                    let derive = derive.trim();
+                    let derive = derive.rsplit("::").next().unwrap_or(derive);
+                    if !derive.is_empty() {
+                        derives.push(format!("derive({})", derive));
+                    }
+                }
+            }
+        }
+    }
+    derives
+}
+
+/// Compiles `--include`/`--exclude` globs, keeping each pattern to report.
+fn compile_globs(patterns: &[String]) -> Vec<(String, Regex)> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let regex = Regex::new(&codeowners::pattern_to_regex(pattern)).unwrap();
+            (pattern.clone(), regex)
+        })
+        .collect()
+}
+
+/// A call name for display: a call by module path, `path(src::net::fetch)`,
+/// by the qualified name it names, and a method call, `.fetch`, by name.
+fn shown(called_fn: &str) -> &str {
+    let called_fn = called_fn.strip_prefix('.').unwrap_or(called_fn);
+    called_fn
+        .strip_prefix("path(")
+        .and_then(|path| path.strip_suffix(')'))
+        .unwrap_or(called_fn)
+}
+
+/// The call names (`static(CONFIG)`) of the globals a function's definition
+/// names, which lazily initialized globals answer to.
+fn globals_used(info: &FunctionInfo) -> Vec<String> {
+    let words: HashSet<&str> = info
+        .definition
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| is_global_name(word))
+        .collect();
+    words
+        .into_iter()
+        .map(|word| format!("static({})", word))
+        .collect()
+}
+
+/// The workspace crates of the project at `project_root`, by directory,
+/// when its `Cargo.toml` makes it a workspace of more than one crate.
+fn workspace_crates(project_root: &Path) -> HashMap<PathBuf, String> {
+    let members = cargo::workspace_members(project_root);
+    if members.len() < 2 {
+        return HashMap::new();
+    }
+    members.into_iter().map(|(name, dir)| (dir, name)).collect()
+}
+
+/// The workspace or proc-macro crate containing `path`, as its name and
+/// root directory. Manifests are looked up once per directory and cached
+/// in `crates`.
+fn crate_namespace_of(
+    path: &Path,
+    project_root: &Path,
+    members: &HashMap<PathBuf, String>,
+    crates: &mut HashMap<PathBuf, Option<String>>,
+) -> Option<(String, PathBuf)> {
+    for dir in path.ancestors().skip(1) {
+        if !dir.starts_with(project_root) {
+            break;
+        }
+        let manifest = dir.join("Cargo.toml");
+        if !manifest.is_file() {
+            continue;
+        }
+        // The nearest manifest owns the file, proc-macro or not
+        let name = crates
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| cargo::proc_macro_crate_name(&manifest))
+            .clone()
+            .or_else(|| members.get(dir).cloned());
+        return name.map(|name| (name, dir.to_path_buf()));
+    }
+    None
+}
+
+fn extract_module_path(file_path: &Path, project_root: &Path) -> String {
+    let rel_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
+
+    // A Go package is a directory: all of its files share the package path
+    if file_path
+        .extension()
+        .is_some_and(|extension| extension == "go")
+    {
+        if let Some(dir) = rel_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            return dir.to_string_lossy().replace(['/', '\\'], "::");
+        }
+    }
+
+    let path_str = rel_path.with_extension("").to_string_lossy().into_owned();
+
+    // Convert path to Rust module path format
+    let mut module_path = path_str.replace(['/', '\\'], "::");
+
+    // Special case for lib.rs or mod.rs
+    if module_path.ends_with("::lib") || module_path.ends_with("::mod") {
+        module_path = module_path[..module_path.len() - 5].to_string();
+    }
+
+    module_path
+}
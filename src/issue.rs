@@ -0,0 +1,227 @@
+//! `from-issue <url|file>`: gathers the code an issue is about. Function
+//! names and `file:line` locations mentioned in the issue (in code spans,
+//! code blocks, backtraces or prose) are mapped to indexed functions, whose
+//! call trees are combined like `--seed`s.
+
+use crate::index::ProjectIndex;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A mention that identifies one indexed function.
+#[derive(Debug, Clone)]
+pub struct Mention {
+    /// As written in the issue
+    pub text: String,
+    pub function: String,
+}
+
+/// The issue text: title, body and comments of a GitHub issue or pull
+/// request URL (through the API, with `GITHUB_TOKEN` if set), any other
+/// URL's content, or a local file.
+pub fn fetch(source: &str) -> io::Result<String> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        return fs::read_to_string(source);
+    }
+
+    let github_regex =
+        Regex::new(r"^https?://github\.com/([^/]+)/([^/]+)/(?:issues|pull)/(\d+)").unwrap();
+    let Some(captures) = github_regex.captures(source) else {
+        return curl(source, None);
+    };
+    let api = format!(
+        "https://api.github.com/repos/{}/{}/issues/{}",
+        &captures[1], &captures[2], &captures[3]
+    );
+    let invalid = |err: serde_json::Error| io::Error::new(io::ErrorKind::InvalidData, err);
+
+    // The token only ever goes to the GitHub API
+    let token = env::var("GITHUB_TOKEN").ok();
+    let issue: Value = serde_json::from_str(&curl(&api, token.as_deref())?).map_err(invalid)?;
+    let comments: Value = serde_json::from_str(&curl(
+        &format!("{}/comments?per_page=100", api),
+        token.as_deref(),
+    )?)
+    .map_err(invalid)?;
+
+    let mut text = String::new();
+    for part in std::iter::once(&issue["title"])
+        .chain(std::iter::once(&issue["body"]))
+        .chain(
+            comments
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|c| &c["body"]),
+        )
+    {
+        if let Some(part) = part.as_str() {
+            text.push_str(part);
+            text.push_str("\n\n");
+        }
+    }
+    Ok(text)
+}
+
+/// The body at `url`, sending `token` as a bearer token. The header goes
+/// through curl's stdin, so the token doesn't show in the process list.
+fn curl(url: &str, token: Option<&str>) -> io::Result<String> {
+    let mut command = Command::new("curl");
+    command.args(["-sSfL", "-H", "Accept: application/vnd.github+json"]);
+    if token.is_some() {
+        command.args(["-H", "@-"]).stdin(Stdio::piped());
+    }
+    let mut child = command
+        .arg(url)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| io::Error::new(err.kind(), format!("could not run curl: {}", err)))?;
+    if let Some(token) = token {
+        let mut stdin = child.stdin.take().unwrap();
+        writeln!(stdin, "Authorization: Bearer {}", token)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "fetching {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The indexed functions `text` mentions, in order of first mention, and
+/// the names that matched several functions and were skipped.
+pub fn mentioned_functions(index: &ProjectIndex, text: &str) -> (Vec<Mention>, Vec<String>) {
+    let location_regex = Regex::new(r"([\w./\\-]+\.(?:rs|swift|m))(?::(\d+))?").unwrap();
+    let path_regex =
+        Regex::new(r"(?:[_\p{XID_Start}]\p{XID_Continue}*::)*[_\p{XID_Start}]\p{XID_Continue}*")
+            .unwrap();
+    let code_regex = Regex::new(r"(?s)```.*?```|`[^`\n]+`").unwrap();
+
+    let mut ambiguous = Vec::new();
+    let mut seen = HashSet::new();
+
+    // Files mentioned anywhere break ties between same-named functions
+    let files: Vec<&str> = location_regex
+        .captures_iter(text)
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .collect();
+
+    let in_code: Vec<(usize, usize)> = code_regex
+        .find_iter(text)
+        .map(|code| (code.start(), code.end()))
+        .collect();
+
+    let locations: Vec<(usize, usize)> = location_regex
+        .find_iter(text)
+        .map(|location| (location.start(), location.end()))
+        .collect();
+
+    let mut found: Vec<(usize, String, Option<String>)> = Vec::new();
+    for captures in location_regex.captures_iter(text) {
+        let Some(line) = captures.get(2).and_then(|line| line.as_str().parse().ok()) else {
+            continue;
+        };
+        let whole = captures.get(0).unwrap();
+        let function = index.function_at(&captures[1], line);
+        found.push((whole.start(), whole.as_str().to_string(), function));
+    }
+    for path in path_regex.find_iter(text) {
+        let written = path.as_str();
+        let quoted = in_code
+            .iter()
+            .any(|(start, end)| *start <= path.start() && path.end() <= *end);
+        // Prose is full of words that happen to name functions; only
+        // code-looking ones count there
+        let code_like =
+            written.contains("::") || written.contains('_') || text[path.end()..].starts_with('(');
+        if !quoted && !code_like {
+            continue;
+        }
+        if locations
+            .iter()
+            .any(|(start, end)| *start <= path.start() && path.end() <= *end)
+        {
+            continue;
+        }
+        match resolve(index, written, &files) {
+            Ok(function) => found.push((path.start(), written.to_string(), function)),
+            Err(candidates) => {
+                if seen.insert(written.to_string()) {
+                    ambiguous.push(format!("{} ({} functions)", written, candidates));
+                }
+            }
+        }
+    }
+
+    found.sort_by_key(|(position, _, _)| *position);
+    let mut mentions = Vec::new();
+    let mut included = HashSet::new();
+    for (_, text, function) in found {
+        if let Some(function) = function {
+            if included.insert(function.clone()) {
+                mentions.push(Mention { text, function });
+            }
+        }
+    }
+    (mentions, ambiguous)
+}
+
+/// The function a written name refers to, `Ok(None)` when it names none,
+/// or the number of candidates when it is ambiguous.
+fn resolve(index: &ProjectIndex, written: &str, files: &[&str]) -> Result<Option<String>, usize> {
+    if index.function_definitions.contains_key(written) {
+        return Ok(Some(written.to_string()));
+    }
+    let segments: Vec<&str> = written.split("::").collect();
+
+    // `Type::method`, then the bare name
+    let tail = segments[segments.len().saturating_sub(2)..].join("::");
+    let Some(variants) = index
+        .module_functions
+        .get(&tail)
+        .or_else(|| index.module_functions.get(*segments.last()?))
+    else {
+        return Ok(None);
+    };
+    if variants.len() == 1 {
+        return Ok(Some(variants[0].0.clone()));
+    }
+
+    // A written path (`my_crate::queue::worker::run` in a backtrace), as
+    // much of it as the index shares, or a mentioned file can single one out
+    for length in (2..=segments.len()).rev() {
+        let suffix = format!("::{}", segments[segments.len() - length..].join("::"));
+        let matching: Vec<&String> = variants
+            .iter()
+            .map(|(name, _)| name)
+            .filter(|name| name.ends_with(&suffix))
+            .collect();
+        if let [only] = matching.as_slice() {
+            return Ok(Some(only.to_string()));
+        }
+    }
+    let in_files: Vec<&String> = variants
+        .iter()
+        .map(|(name, _)| name)
+        .filter(|name| {
+            files.iter().any(|file| {
+                index.function_definitions[*name]
+                    .path
+                    .ends_with(Path::new(file.trim_start_matches("./")))
+            })
+        })
+        .collect();
+    match in_files.as_slice() {
+        [only] => Ok(Some(only.to_string())),
+        _ => Err(variants.len()),
+    }
+}
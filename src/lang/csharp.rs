@@ -0,0 +1,463 @@
+use super::{
+    definition_start, find_body_end, free_key, line_number_at, line_start, FileItems, Language,
+};
+use crate::{FunctionInfo, TypeInfo};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Keywords and operators that are followed by a parenthesis like a call.
+const KEYWORDS: &[&str] = &[
+    "if",
+    "else",
+    "for",
+    "foreach",
+    "while",
+    "do",
+    "switch",
+    "case",
+    "return",
+    "using",
+    "lock",
+    "catch",
+    "when",
+    "fixed",
+    "checked",
+    "unchecked",
+    "typeof",
+    "sizeof",
+    "nameof",
+    "default",
+    "new",
+    "base",
+    "this",
+    "await",
+    "throw",
+    "in",
+    "is",
+    "as",
+    "with",
+    "var",
+    "stackalloc",
+];
+
+/// Methods of the base class library and LINQ, which would otherwise
+/// resolve by name to any project method called the same.
+const SKIPPED_METHODS: &[&str] = &[
+    "Select",
+    "SelectMany",
+    "Where",
+    "OrderBy",
+    "OrderByDescending",
+    "ThenBy",
+    "GroupBy",
+    "ToList",
+    "ToArray",
+    "ToDictionary",
+    "ToHashSet",
+    "First",
+    "FirstOrDefault",
+    "Single",
+    "SingleOrDefault",
+    "Last",
+    "LastOrDefault",
+    "Any",
+    "All",
+    "Count",
+    "Sum",
+    "Min",
+    "Max",
+    "Average",
+    "Aggregate",
+    "Distinct",
+    "Skip",
+    "Take",
+    "Concat",
+    "Zip",
+    "Contains",
+    "Add",
+    "AddRange",
+    "Remove",
+    "RemoveAt",
+    "Clear",
+    "Insert",
+    "IndexOf",
+    "TryGetValue",
+    "ContainsKey",
+    "ToString",
+    "Equals",
+    "GetHashCode",
+    "GetType",
+    "Format",
+    "Join",
+    "Split",
+    "Trim",
+    "Replace",
+    "StartsWith",
+    "EndsWith",
+    "Substring",
+    "ToUpper",
+    "ToLower",
+    "ConfigureAwait",
+    "GetAwaiter",
+    "GetResult",
+    "Dispose",
+    "DisposeAsync",
+    "Append",
+    "AppendLine",
+];
+
+/// Static classes of the base class library, whose calls never reach a
+/// project method.
+const STANDARD_CLASSES: &[&str] = &[
+    "Console",
+    "Math",
+    "String",
+    "Task",
+    "File",
+    "Directory",
+    "Path",
+    "Convert",
+    "Enumerable",
+    "Guid",
+    "DateTime",
+    "TimeSpan",
+    "Environment",
+    "Debug",
+    "Trace",
+    "Assert",
+    "Activator",
+    "Array",
+    "Buffer",
+    "Encoding",
+    "JsonSerializer",
+    "Interlocked",
+    "Volatile",
+    "Marshal",
+    "Thread",
+    "GC",
+    "BitConverter",
+    "Regex",
+    "Uri",
+    "Nullable",
+    "Object",
+    "Int32",
+    "Int64",
+    "Double",
+    "Decimal",
+    "Boolean",
+    "Char",
+    "Byte",
+];
+
+/// Test framework attributes (xUnit, NUnit, MSTest).
+const TEST_ATTRIBUTES: &[&str] = &["Fact", "Theory", "Test", "TestCase", "TestMethod"];
+
+/// Extracts classes, structs, interfaces, records and enums, in block or
+/// file-scoped namespaces, and their methods and constructors keyed
+/// `Type::Name` (a constructor is `Type::Type`, which `new Type(...)` calls).
+/// Methods declared without a body (abstract, interface and `extern`
+/// P/Invoke declarations) are skipped, so calls into native code resolve
+/// to the functions that implement it.
+pub fn extract(content: &str, path: &Path, module_path: &str) -> FileItems {
+    let mut function_info: HashMap<String, FunctionInfo> = HashMap::new();
+    let mut types: HashMap<String, TypeInfo> = HashMap::new();
+
+    let type_regex = Regex::new(
+        r"(?m)^[ \t]*(?:\[[^\n]*\]\s*)*(?:(?:public|private|protected|internal|static|sealed|abstract|partial|readonly|ref|unsafe|file|new)\s+)*(class|struct|interface|enum|record(?:\s+class|\s+struct)?)\s+([A-Za-z_]\w*)",
+    )
+    .unwrap();
+    // Attributes and modifiers, an optional return type (`Task<IList<int>>`,
+    // `int[]`, `(int, string)`), then the name, type parameters and `(`
+    let member_regex = Regex::new(
+        r"(?m)^[ \t]*(?:\[[^\n]*\]\s*)*(?:(?:public|private|protected|internal|static|virtual|override|abstract|sealed|async|extern|unsafe|new|partial|readonly)\s+)*(?:[\w.]+(?:<[^;{}()]*>)?(?:\[[,\s]*\])*\??\s+|\([^;{}()]*\)\s+)?([A-Za-z_]\w*)\s*(?:<[^;{}()]*>)?\s*\(",
+    )
+    .unwrap();
+
+    // (start of the member, its body's `{` or `=>`, type, name)
+    let mut members: Vec<(usize, usize, String, String)> = Vec::new();
+    for captures in type_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let kind = captures.get(1).unwrap().as_str();
+        let type_name = captures.get(2).unwrap().as_str();
+        let def_start = preamble_start(content, definition_start(content, whole.start()));
+
+        // A positional record without a body ends at its `;`
+        let Some(open) = type_body_open(content, whole.end()) else {
+            let end = content[whole.end()..]
+                .find(';')
+                .map_or(content.len(), |i| whole.end() + i + 1);
+            types.insert(
+                type_name.to_string(),
+                type_info(content, path, def_start, end),
+            );
+            continue;
+        };
+        let type_end = find_body_end(content, open, Language::CSharp);
+
+        // Interfaces and enums are kept whole; classes, structs and records
+        // by their declaration, as their methods are indexed on their own
+        let def_end = match kind {
+            "interface" | "enum" => type_end,
+            _ => open,
+        };
+        types.insert(
+            type_name.to_string(),
+            type_info(content, path, def_start, def_end),
+        );
+        if kind == "enum" {
+            continue;
+        }
+
+        // Only members of the type itself, not code nested in them or the
+        // members of nested types, which are found on their own
+        let body = &content[open + 1..type_end.saturating_sub(1).max(open + 1)];
+        let mut depth = 0i32;
+        let mut scanned = 0;
+        for member in member_regex.captures_iter(body) {
+            let member_match = member.get(0).unwrap();
+            depth += brace_balance(&body[scanned..member_match.start()]);
+            scanned = member_match.start();
+            let name = member.get(1).unwrap().as_str();
+            if depth != 0 || KEYWORDS.contains(&name) {
+                continue;
+            }
+            let open_paren = open + 1 + member_match.end() - 1;
+            let Some(body_start) = body_open(content, open_paren) else {
+                continue;
+            };
+            members.push((
+                open + 1 + member_match.start(),
+                body_start,
+                type_name.to_string(),
+                name.to_string(),
+            ));
+        }
+    }
+
+    let call_regex = Regex::new(
+        r"(?:\b(this|base|[A-Za-z_]\w*)\s*\??\.\s*)?\b([A-Za-z_]\w*)\s*(?:<[^;{}()]*>)?\s*\(",
+    )
+    .unwrap();
+    let new_regex = Regex::new(r"\bnew\s+([A-Za-z_][\w.]*)\s*(?:<[^;{}()]*>)?\s*[({]").unwrap();
+
+    for (start, body_start, type_name, name) in members {
+        let def_start = preamble_start(content, definition_start(content, start));
+        let def_end = if content[body_start..].starts_with("=>") {
+            expression_body_end(content, body_start)
+        } else {
+            find_body_end(content, body_start, Language::CSharp)
+        };
+        let fn_body = content[def_start..def_end].trim().to_string();
+        let body = &content[body_start..def_end];
+
+        let mut calls = HashSet::new();
+        for call_captures in call_regex.captures_iter(body) {
+            let called = call_captures.get(2).unwrap().as_str();
+            // Constructions are handled below
+            if body[..call_captures.get(0).unwrap().start()]
+                .trim_end()
+                .ends_with("new")
+            {
+                continue;
+            }
+            match call_captures.get(1).map(|m| m.as_str()) {
+                Some("this") => {
+                    calls.insert(format!("Self::{}", called));
+                }
+                Some(qualifier)
+                    if STANDARD_CLASSES.contains(&qualifier)
+                        || SKIPPED_METHODS.contains(&called) => {}
+                Some(_) => {
+                    calls.insert(called.to_string());
+                }
+                None if !KEYWORDS.contains(&called) && !SKIPPED_METHODS.contains(&called) => {
+                    calls.insert(called.to_string());
+                }
+                None => {}
+            }
+        }
+        for new_captures in new_regex.captures_iter(body) {
+            let constructed = new_captures[1].rsplit('.').next().unwrap_or_default();
+            calls.insert(format!("{0}::{0}", constructed));
+        }
+
+        let attributes = attributes_of(&content[def_start..body_start]);
+        let is_test = attributes
+            .iter()
+            .any(|attribute| TEST_ATTRIBUTES.contains(&attribute.as_str()));
+
+        // Overloads are kept as `Type::Name#2`, ...
+        let base_key = format!("{}::{}", type_name, name);
+        function_info.insert(
+            free_key(&function_info, base_key),
+            FunctionInfo {
+                path: path.to_path_buf(),
+                language: Language::CSharp,
+                module_path: module_path.to_string(),
+                definition: fn_body,
+                line_number: line_number_at(content, def_start),
+                calls,
+                doc: doc_comment_above(content, start),
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: None,
+                is_test,
+                self_type: Some(type_name),
+            },
+        );
+    }
+
+    FileItems {
+        functions: function_info,
+        types,
+        impls: Vec::new(),
+        aliases: Vec::new(),
+        generated: is_generated(content),
+    }
+}
+
+fn type_info(content: &str, path: &Path, def_start: usize, def_end: usize) -> TypeInfo {
+    TypeInfo {
+        path: path.to_path_buf(),
+        language: Language::CSharp,
+        definition: content[def_start..def_end].trim().to_string(),
+        line_number: line_number_at(content, def_start),
+        doc: doc_comment_above(content, def_start),
+    }
+}
+
+/// The `{` opening a type's body, past its base list, primary constructor
+/// and constraints; `None` when the declaration ends with `;` first.
+fn type_body_open(content: &str, from: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, c) in content[from..].char_indices() {
+        match c {
+            '(' | '<' | '[' => depth += 1,
+            ')' | '>' | ']' => depth -= 1,
+            '{' if depth <= 0 => return Some(from + i),
+            ';' if depth <= 0 => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The `{` or `=>` starting the body of the member whose parameter list
+/// opens at `open_paren`, past constraints and a constructor initializer
+/// (`: base(name)`); `None` for declarations, which end with `;`, and for
+/// anything that isn't a member (a field initialized with a call).
+fn body_open(content: &str, open_paren: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut params_closed = false;
+    for (i, c) in content[open_paren..].char_indices() {
+        let at = open_paren + i;
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    params_closed = true;
+                }
+            }
+            '{' if depth == 0 && params_closed => return Some(at),
+            '=' if depth == 0 && params_closed && content[at..].starts_with("=>") => {
+                return Some(at)
+            }
+            ';' | '=' | '}' if depth == 0 => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The end of an expression-bodied member (`=> expression;`), after its `;`.
+fn expression_body_end(content: &str, arrow: usize) -> usize {
+    let mut depth = 0i32;
+    for (i, c) in super::code_chars(&content[arrow..], Language::CSharp) {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ';' if depth <= 0 => return arrow + i + 1,
+            _ => {}
+        }
+    }
+    content.len()
+}
+
+/// Net `{` minus `}` in `text`, outside strings and comments.
+fn brace_balance(text: &str) -> i32 {
+    super::code_chars(text, Language::CSharp)
+        .map(|(_, c)| match c {
+            '{' => 1,
+            '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The start of the `///` XML doc comment and `[Attribute]` lines directly
+/// above `def_start`, which belong to the definition.
+fn preamble_start(content: &str, def_start: usize) -> usize {
+    let mut start = def_start;
+    while start > 0 {
+        let previous = line_start(content, start - 1);
+        let line = content[previous..start].trim();
+        if !(line.starts_with("///") || (line.starts_with('[') && line.ends_with(']'))) {
+            break;
+        }
+        start = previous;
+    }
+    start
+}
+
+/// The names of the attributes in a definition's preamble (`[Fact]`,
+/// `[TestCase(1, 2)]` and `[Theory, InlineData(3)]` give `Fact`, `TestCase`,
+/// `Theory` and `InlineData`).
+fn attributes_of(preamble: &str) -> Vec<String> {
+    let attribute_regex =
+        Regex::new(r"(?:\[|,)\s*(?:[\w.]+\.)?([A-Za-z_]\w*?)(?:Attribute)?\s*[(\],]").unwrap();
+    preamble
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('['))
+        .flat_map(|line| {
+            attribute_regex
+                .captures_iter(line)
+                .map(|captures| captures[1].to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The text of the `///` XML doc comment above `def_start`, past any
+/// attribute lines, with the `<summary>` and `<remarks>` tags removed.
+fn doc_comment_above(content: &str, def_start: usize) -> Option<String> {
+    let tag_regex = Regex::new(r"</?(?:summary|remarks)>").unwrap();
+    let mut doc_lines = Vec::new();
+    let mut start = line_start(content, def_start);
+    while start > 0 {
+        let previous = line_start(content, start - 1);
+        let line = content[previous..start].trim();
+        if let Some(text) = line.strip_prefix("///") {
+            doc_lines.push(tag_regex.replace_all(text.trim(), "").trim().to_string());
+        } else if !(line.starts_with('[') && line.ends_with(']')) {
+            break;
+        }
+        start = previous;
+    }
+
+    doc_lines.reverse();
+    let doc = doc_lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Code `protoc` and the gRPC plugin generated, which says so in its
+/// header comment.
+fn is_generated(content: &str) -> bool {
+    content.lines().take(20).any(|line| {
+        line.trim_start().starts_with("//")
+            && line.contains("Generated by the protocol buffer compiler")
+    })
+}
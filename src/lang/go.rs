@@ -0,0 +1,254 @@
+use super::{
+    definition_start, find_body_end, free_key, line_number_at, line_start, FileItems, Language,
+};
+use crate::{FunctionInfo, TypeInfo};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Keywords, builtins and conversions to predeclared types, which look like
+/// calls but never reach a project function.
+const BUILTINS: &[&str] = &[
+    "if", "for", "switch", "select", "return", "func", "go", "defer", "range", "make", "new",
+    "len", "cap", "append", "copy", "delete", "panic", "recover", "print", "println", "close",
+    "complex", "real", "imag", "min", "max", "clear", "string", "byte", "rune", "int", "int8",
+    "int16", "int32", "int64", "uint", "uint8", "uint16", "uint32", "uint64", "uintptr", "float32",
+    "float64", "bool", "error", "any",
+];
+
+/// Extracts `func` declarations, methods keyed `Type::Name` by their
+/// receiver's type, and the types they use. Calls through a standard library
+/// package (`fmt.Println`, `strings.Split`) are left out, since they would
+/// otherwise resolve by name to any project function called the same.
+pub fn extract(content: &str, path: &Path, module_path: &str) -> FileItems {
+    let mut function_info: HashMap<String, FunctionInfo> = HashMap::new();
+    let mut types: HashMap<String, TypeInfo> = HashMap::new();
+
+    // `go test` runs Test, Benchmark, Fuzz and Example functions of
+    // `_test.go` files
+    let in_test_file = path
+        .file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().ends_with("_test"));
+
+    // The receiver `(s *Store)`, `(Store)` or `(s *Stack[T])`, then the
+    // name and optional type parameters
+    let fn_regex = Regex::new(
+        r"(?m)^func\s*(?:\(\s*(?:([A-Za-z_]\w*)\s+)?\*?\s*([A-Za-z_]\w*)(?:\[[^\]]*\])?\s*\)\s*)?([A-Za-z_]\w*)\s*(?:\[[^\]]*\])?\s*\(",
+    )
+    .unwrap();
+    // Package-level variables holding a function literal
+    // (`var handler = func(w Writer) { ... }`) are functions too
+    let var_regex =
+        Regex::new(r"(?m)^var\s+([A-Za-z_]\w*)(?:\s+[^=\n]+?)?\s*=\s*func\s*\(").unwrap();
+    let qualified_call_regex = Regex::new(r"\b([A-Za-z_]\w*)\.([A-Za-z_]\w*)\s*\(").unwrap();
+    let call_regex = Regex::new(r"(?:^|[^\w.])([A-Za-z_]\w*)\s*\(").unwrap();
+    let standard_packages = standard_imports(content);
+
+    // (start, the `(` of the parameters, name, receiver, receiver's type)
+    let mut declarations: Vec<_> = fn_regex
+        .captures_iter(content)
+        .map(|captures| {
+            let whole = captures.get(0).unwrap();
+            (
+                whole.start(),
+                whole.end() - 1,
+                captures.get(3).unwrap().as_str(),
+                captures.get(1).map(|m| m.as_str()),
+                captures.get(2).map(|m| m.as_str().to_string()),
+            )
+        })
+        .collect();
+    declarations.extend(var_regex.captures_iter(content).map(|captures| {
+        let whole = captures.get(0).unwrap();
+        let name = captures.get(1).unwrap().as_str();
+        (whole.start(), whole.end() - 1, name, None, None)
+    }));
+    declarations.sort_by_key(|declaration| declaration.0);
+
+    for (start, open_paren, function_name, receiver, self_type) in declarations {
+        // Functions implemented in assembly are declared without a body
+        let Some(body_start) = body_open(content, open_paren) else {
+            continue;
+        };
+
+        let def_start = definition_start(content, start);
+        let line_number = line_number_at(content, def_start);
+        let def_end = find_body_end(content, body_start, Language::Go);
+        let fn_body = content[def_start..def_end].trim().to_string();
+        let body = &content[body_start..def_end];
+
+        let mut calls = HashSet::new();
+        for call_captures in qualified_call_regex.captures_iter(body) {
+            let qualifier = call_captures.get(1).unwrap().as_str();
+            let name = call_captures.get(2).unwrap().as_str();
+            if receiver == Some(qualifier) {
+                calls.insert(format!("Self::{}", name));
+            } else if !standard_packages.contains(qualifier) {
+                calls.insert(name.to_string());
+            }
+        }
+        for call_captures in call_regex.captures_iter(body) {
+            let name = call_captures.get(1).unwrap().as_str();
+            if !BUILTINS.contains(&name) {
+                calls.insert(name.to_string());
+            }
+        }
+
+        // Methods are keyed by their receiver's type; a package may have
+        // several `init` functions, kept as `init#2`, ...
+        let base_key = match &self_type {
+            Some(self_type) => format!("{}::{}", self_type, function_name),
+            None => function_name.to_string(),
+        };
+        function_info.insert(
+            free_key(&function_info, base_key),
+            FunctionInfo {
+                path: path.to_path_buf(),
+                language: Language::Go,
+                module_path: module_path.to_string(),
+                definition: fn_body,
+                line_number,
+                calls,
+                doc: doc_comment_above(content, def_start),
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: None,
+                is_test: in_test_file
+                    && self_type.is_none()
+                    && ["Test", "Benchmark", "Fuzz", "Example"]
+                        .iter()
+                        .any(|prefix| function_name.starts_with(prefix)),
+                self_type,
+            },
+        );
+    }
+
+    let type_regex =
+        Regex::new(r"(?m)^type\s+([A-Za-z_]\w*)(?:\[[^\]]*\])?\s+(=\s*)?(struct|interface)?")
+            .unwrap();
+
+    for captures in type_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let def_start = definition_start(content, whole.start());
+        // Structs and interfaces are kept whole; other definitions
+        // (`type ID int64`, `type Handler = func(...)`) fit on their line
+        let def_end = match captures.get(3) {
+            Some(_) => find_body_end(content, whole.end(), Language::Go),
+            None => content[whole.end()..]
+                .find('\n')
+                .map_or(content.len(), |i| whole.end() + i),
+        };
+
+        types.insert(
+            captures.get(1).unwrap().as_str().to_string(),
+            TypeInfo {
+                path: path.to_path_buf(),
+                language: Language::Go,
+                definition: content[def_start..def_end].trim().to_string(),
+                line_number: line_number_at(content, def_start),
+                doc: doc_comment_above(content, def_start),
+            },
+        );
+    }
+
+    FileItems {
+        functions: function_info,
+        types,
+        impls: Vec::new(),
+        aliases: Vec::new(),
+        generated: false,
+    }
+}
+
+/// The names the file's standard library imports are used by: `strings`
+/// for `"strings"`, `filepath` for `"path/filepath"`, or the alias given.
+/// Standard library paths are the ones whose first element has no dot.
+fn standard_imports(content: &str) -> HashSet<String> {
+    let import_regex =
+        Regex::new(r#"(?m)^\s*(?:import\s+)?(?:([A-Za-z_]\w*|\.)\s+)?"([^"]+)"\s*$"#).unwrap();
+    let imports_end = content
+        .find("\nfunc ")
+        .or_else(|| content.find("\ntype "))
+        .unwrap_or(content.len());
+
+    import_regex
+        .captures_iter(&content[..imports_end])
+        .filter(|captures| {
+            let import_path = captures.get(2).unwrap().as_str();
+            !import_path
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .contains('.')
+        })
+        .map(|captures| match captures.get(1) {
+            Some(alias) => alias.as_str().to_string(),
+            None => {
+                let import_path = captures.get(2).unwrap().as_str();
+                import_path
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or(import_path)
+                    .to_string()
+            }
+        })
+        .collect()
+}
+
+/// The `{` opening the body of the function whose parameter list opens at
+/// `open_paren`, past its results; `None` when the declaration ends without
+/// one.
+fn body_open(content: &str, open_paren: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut in_params = true;
+    let mut chars = content[open_paren..].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    in_params = false;
+                }
+            }
+            '{' if depth == 0 && !in_params => {
+                // `interface{}` and `struct{...}` in the results aren't the body
+                let before = content[..open_paren + i].trim_end();
+                if !(before.ends_with("interface") || before.ends_with("struct")) {
+                    return Some(open_paren + i);
+                }
+                let end = find_body_end(content, open_paren + i, Language::Go) - open_paren;
+                while chars.peek().is_some_and(|(next, _)| *next < end) {
+                    chars.next();
+                }
+            }
+            '\n' if depth == 0 && !in_params => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The `//` comment lines directly above `def_start`, which is how Go
+/// documents declarations.
+fn doc_comment_above(content: &str, def_start: usize) -> Option<String> {
+    let mut doc_lines = Vec::new();
+    let mut start = def_start;
+    while start > 0 {
+        let previous = line_start(content, start - 1);
+        let Some(text) = content[previous..start].trim().strip_prefix("//") else {
+            break;
+        };
+        // Build constraints and compiler directives aren't documentation
+        if text.starts_with("go:") || text.starts_with("+build") {
+            break;
+        }
+        doc_lines.push(text.strip_prefix(' ').unwrap_or(text));
+        start = previous;
+    }
+
+    doc_lines.reverse();
+    let doc = doc_lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
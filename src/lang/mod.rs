@@ -0,0 +1,666 @@
+//! Per-language function extractors.
+//!
+//! Every backend turns the contents of one source file into the same
+//! `FunctionInfo` map, so call resolution can link definitions across
+//! languages by their simple names (e.g. a Swift call site invoking a Rust
+//! `extern "C"` function it binds to).
+
+mod csharp;
+mod go;
+mod objc;
+mod paths;
+mod rust;
+mod swift;
+mod typescript;
+
+pub use rust::{call_arguments, is_global_name, unraw};
+
+use crate::{FunctionInfo, ImplInfo, TypeInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Opt-in extraction behaviors shared by the backends.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Follow functions passed by name to combinators such as
+    /// `.and_then(validate)` or `.map(Self::parse)`
+    pub expand_combinators: bool,
+    /// How Rust definitions are read
+    pub rust_backend: RustBackend,
+}
+
+/// Which backend reads the functions of a Rust file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RustBackend {
+    /// The syntax tree when the file parses, the line regexes otherwise
+    #[default]
+    Auto,
+    /// The line regexes, even for files that parse (needs the
+    /// `regex-backend` feature)
+    Regex,
+}
+
+/// Everything a backend extracts from a single source file.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct FileItems {
+    pub functions: HashMap<String, FunctionInfo>,
+    pub types: HashMap<String, TypeInfo>,
+    pub impls: Vec<ImplInfo>,
+    /// Extra call names a function answers to, as (alias, function name):
+    /// proc-macro entry points are invoked as `name!`, `#[name]` or
+    /// `derive(Name)` rather than by their own name
+    pub aliases: Vec<(String, String)>,
+    /// Generated protobuf/gRPC code (prost, tonic), which is indexed
+    /// signature-only
+    pub generated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    Rust,
+    Swift,
+    ObjectiveC,
+    TypeScript,
+    JavaScript,
+    Go,
+    CSharp,
+}
+
+impl Language {
+    /// Human-readable name used in section headers and statistics.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "Rust",
+            Language::Swift => "Swift",
+            Language::ObjectiveC => "Objective-C",
+            Language::TypeScript => "TypeScript",
+            Language::JavaScript => "JavaScript",
+            Language::Go => "Go",
+            Language::CSharp => "C#",
+        }
+    }
+
+    /// Info string for fenced code blocks of this language.
+    pub fn fence(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Swift => "swift",
+            Language::ObjectiveC => "objc",
+            Language::TypeScript => "ts",
+            Language::JavaScript => "js",
+            Language::Go => "go",
+            Language::CSharp => "csharp",
+        }
+    }
+
+    /// The language called `name`, by its name or fence, ignoring case.
+    pub fn parse(name: &str) -> Option<Language> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Some(Language::Rust),
+            "swift" => Some(Language::Swift),
+            "objective-c" | "objc" => Some(Language::ObjectiveC),
+            "typescript" | "ts" => Some(Language::TypeScript),
+            "javascript" | "js" => Some(Language::JavaScript),
+            "go" => Some(Language::Go),
+            "c#" | "csharp" | "cs" => Some(Language::CSharp),
+            _ => None,
+        }
+    }
+
+    /// Whether this build has a backend for the language: every language
+    /// but Rust is extracted with line patterns only, so it needs the
+    /// `regex-backend` feature.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Language::Rust => true,
+            Language::Swift
+            | Language::ObjectiveC
+            | Language::TypeScript
+            | Language::JavaScript
+            | Language::Go
+            | Language::CSharp => cfg!(feature = "regex-backend"),
+        }
+    }
+
+    /// Picks the backend for a file based on its extension.
+    pub fn from_path(path: &Path) -> Option<Language> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "swift" => Some(Language::Swift),
+            "m" => Some(Language::ObjectiveC),
+            "ts" | "tsx" | "mts" | "cts" => Some(Language::TypeScript),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Language::JavaScript),
+            "go" => Some(Language::Go),
+            "cs" => Some(Language::CSharp),
+            _ => None,
+        }
+    }
+}
+
+pub fn process_file(
+    path: &Path,
+    module_path: &str,
+    options: &ExtractOptions,
+) -> io::Result<FileItems> {
+    let content = read_source(path)?;
+    Ok(extract(&content, path, module_path, options))
+}
+
+/// Runs the backend for `path`'s language over its already read `content`.
+pub fn extract(
+    content: &str,
+    path: &Path,
+    module_path: &str,
+    options: &ExtractOptions,
+) -> FileItems {
+    match Language::from_path(path) {
+        Some(Language::Rust) => rust::extract(content, path, module_path, options),
+        Some(Language::Swift) if cfg!(feature = "regex-backend") => {
+            swift::extract(content, path, module_path)
+        }
+        Some(Language::ObjectiveC) if cfg!(feature = "regex-backend") => {
+            objc::extract(content, path, module_path)
+        }
+        Some(language @ (Language::TypeScript | Language::JavaScript))
+            if cfg!(feature = "regex-backend") =>
+        {
+            typescript::extract(content, path, module_path, language)
+        }
+        Some(Language::Go) if cfg!(feature = "regex-backend") => {
+            go::extract(content, path, module_path)
+        }
+        Some(Language::CSharp) if cfg!(feature = "regex-backend") => {
+            csharp::extract(content, path, module_path)
+        }
+        _ => FileItems::default(),
+    }
+}
+
+/// A source file's text with the byte order mark removed and `\r\n` (or a
+/// lone `\r`) line endings turned into `\n`, so offsets and patterns work
+/// the same on every platform's files while each line keeps its original
+/// number. Invalid UTF-8 is replaced rather than cutting the file short.
+pub fn read_source(path: &Path) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+    let mut text = if text.contains('\r') {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        text.to_string()
+    };
+    // Like joining its lines: the final line ending is not part of the text
+    if text.ends_with('\n') {
+        text.pop();
+    }
+    Ok(text)
+}
+
+/// Counts the files of each language, in a stable order for reporting.
+pub fn detect_languages(files: &[PathBuf]) -> Vec<(Language, usize)> {
+    let mut counts: HashMap<Language, usize> = HashMap::new();
+    for path in files {
+        if let Some(language) = Language::from_path(path).filter(|l| l.is_supported()) {
+            *counts.entry(language).or_default() += 1;
+        }
+    }
+
+    let mut detected: Vec<(Language, usize)> = counts.into_iter().collect();
+    detected.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.name().cmp(b.0.name())));
+    detected
+}
+
+/// Returns the offset of the first character on the line containing `pos`.
+fn line_start(content: &str, pos: usize) -> usize {
+    content[..pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Returns the start of the line holding the first non-whitespace character
+/// at or after `pos`, since `^\s*` patterns can match from earlier blank lines.
+fn definition_start(content: &str, pos: usize) -> usize {
+    let skipped = content[pos..].len() - content[pos..].trim_start().len();
+    line_start(content, pos + skipped)
+}
+
+/// `base_key`, or when the file already has a function by that key (a
+/// `#[cfg]` variant, an overload) the first of `base_key#2`, `base_key#3`,
+/// ... that is free, so no definition overwrites another.
+fn free_key(functions: &HashMap<String, FunctionInfo>, base_key: String) -> String {
+    let mut key = base_key.clone();
+    let mut variant = 2;
+    while functions.contains_key(&key) {
+        key = format!("{}#{}", base_key, variant);
+        variant += 1;
+    }
+    key
+}
+
+/// 1-based line number of the byte offset `pos`.
+fn line_number_at(content: &str, pos: usize) -> usize {
+    content[..pos].matches('\n').count() + 1
+}
+
+/// Walks backwards from `def_start` over contiguous attribute lines
+/// (`#[derive(...)]`, `#[serde(...)]`) and returns where they begin.
+fn attributes_start(content: &str, def_start: usize) -> usize {
+    let mut start = def_start;
+    while start > 0 {
+        let previous = line_start(content, start - 1);
+        let line = without_line_comment(content[previous..start].trim());
+        if line.starts_with("#[") {
+            start = previous;
+        } else if line.ends_with(']') {
+            // The last line of an attribute spanning several lines
+            match attribute_start(content, start) {
+                Some(attribute_start) => start = attribute_start,
+                None => break,
+            }
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+/// The attributes at the start of `text`, in order, each on one line
+/// (`#[cfg(any(unix, windows))]`), past any comments among them.
+fn leading_attributes(text: &str) -> Vec<String> {
+    scan_preamble(text).0
+}
+
+/// Where the code of a definition starts, past the doc comments, other
+/// comments and attributes it begins with.
+pub fn preamble_len(definition: &str) -> usize {
+    scan_preamble(definition).1
+}
+
+/// The attributes among the comments and attributes `text` starts with,
+/// and the length of that preamble.
+fn scan_preamble(text: &str) -> (Vec<String>, usize) {
+    let mut attributes = Vec::new();
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start();
+        if rest.starts_with("//") {
+            rest = rest.split_once('\n').map_or("", |(_, after)| after);
+        } else if rest.starts_with("/*") {
+            rest = rest.split_once("*/").map_or("", |(_, after)| after);
+        } else if rest.starts_with("#[") {
+            let Some(end) = attribute_end(rest) else {
+                break;
+            };
+            let attribute = rest[..end].split_whitespace().collect::<Vec<_>>().join(" ");
+            attributes.push(attribute.replace("( ", "(").replace(" )", ")"));
+            rest = &rest[end..];
+        } else {
+            break;
+        }
+    }
+    (attributes, text.len() - rest.len())
+}
+
+/// The length of the attribute `text` starts with, through the `]` that
+/// closes its `#[`; `None` if it isn't closed.
+fn attribute_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Walks backwards from `def_start` over the contiguous `///` and `/** */`
+/// doc comments and attribute lines above it, in any order, and returns
+/// where they begin.
+fn doc_and_attributes_start(content: &str, def_start: usize) -> usize {
+    // Plain `//` comments count only when documentation or attributes sit
+    // above them
+    let mut committed = def_start;
+    let mut start = def_start;
+    while start > 0 {
+        let previous = line_start(content, start - 1);
+        let raw_line = content[previous..start].trim();
+        let line = without_line_comment(raw_line);
+        if raw_line.starts_with("///") || line.starts_with("#[") {
+            start = previous;
+        } else if raw_line.starts_with("//") {
+            start = previous;
+            continue;
+        } else if line.ends_with(']') {
+            // The last line of an attribute spanning several lines
+            match attribute_start(content, start) {
+                Some(attribute_start) => start = attribute_start,
+                None => break,
+            }
+        } else if line.ends_with("*/") {
+            // Only a doc comment's block, not a plain `/* */` comment
+            let Some(block_start) = content[..start].rfind("/*") else {
+                break;
+            };
+            let block_line = line_start(content, block_start);
+            if !content[block_start..].starts_with("/**")
+                || !content[block_line..block_start].trim().is_empty()
+            {
+                break;
+            }
+            start = block_line;
+        } else {
+            break;
+        }
+        committed = start;
+    }
+    committed
+}
+
+/// A line without its trailing `//` comment, if any (`//` inside a string
+/// literal doesn't start one).
+fn without_line_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '/' if !in_string && line[i..].starts_with("//") => return line[..i].trim_end(),
+            _ => {}
+        }
+    }
+    line
+}
+
+/// The start of the `#[` line opening the multi-line attribute that ends on
+/// the line before `end`, if the lines above balance its brackets.
+fn attribute_start(content: &str, end: usize) -> Option<usize> {
+    let mut start = end;
+    let mut depth = 0i32;
+    while start > 0 {
+        let previous = line_start(content, start - 1);
+        let line = without_line_comment(content[previous..start].trim());
+        depth += line.matches(']').count() as i32 - line.matches('[').count() as i32;
+        start = previous;
+        if depth <= 0 {
+            return (depth == 0 && line.starts_with("#[")).then_some(start);
+        }
+        if line.ends_with([';', '{', '}']) {
+            return None;
+        }
+    }
+    None
+}
+
+/// Collects the `///` (or `/** */`) doc comment directly above `def_start`,
+/// looking past any attribute lines in between.
+fn doc_comment_above(content: &str, def_start: usize) -> Option<String> {
+    let mut doc_lines = Vec::new();
+    let mut in_block = false;
+
+    let mut start = def_start;
+    while start > 0 {
+        let previous = line_start(content, start - 1);
+        let line = content[previous..start].trim();
+        if !in_block && line.ends_with(']') && !line.starts_with("#[") && !line.starts_with("//") {
+            // Past an attribute spanning several lines
+            match attribute_start(content, start) {
+                Some(attribute_start) => {
+                    start = attribute_start;
+                    continue;
+                }
+                None => break,
+            }
+        }
+        start = previous;
+        if in_block {
+            if let Some(first) = line.strip_prefix("/**") {
+                doc_lines.push(first.trim());
+                break;
+            }
+            // A plain `/* */` comment isn't documentation
+            if line.starts_with("/*") {
+                doc_lines.clear();
+                break;
+            }
+            doc_lines.push(line.trim_start_matches('*').trim());
+        } else if let Some(text) = line.strip_prefix("///") {
+            doc_lines.push(text.strip_prefix(' ').unwrap_or(text));
+        } else if line.ends_with("*/") && doc_lines.is_empty() {
+            let inner = line.trim_end_matches("*/").trim();
+            if let Some(single) = inner.strip_prefix("/**") {
+                doc_lines.push(single.trim());
+                break;
+            }
+            if inner.starts_with("/*") {
+                break;
+            }
+            doc_lines.push(inner.trim_start_matches('*').trim());
+            in_block = true;
+        } else if !line.starts_with("#[") && !line.starts_with('@') {
+            break;
+        }
+    }
+
+    doc_lines.reverse();
+    let doc = doc_lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Finds the end of a type definition: either a terminating `;` (unit and
+/// tuple structs, aliases) or the matching brace of its body.
+fn find_item_end(content: &str, start: usize, language: Language) -> usize {
+    let mut group_depth = 0i32;
+    for (i, c) in code_chars(&content[start..], language) {
+        match c {
+            '(' | '[' => group_depth += 1,
+            ')' | ']' => group_depth -= 1,
+            ';' if group_depth <= 0 => return start + i + 1,
+            '{' if group_depth <= 0 => return find_body_end(content, start + i, language),
+            _ => {}
+        }
+    }
+    content.len()
+}
+
+/// Finds the end of a brace-delimited body that opens at or after `start`.
+/// Braces in literals and comments don't count, and neither do braces
+/// inside parentheses or brackets before the body opens (a default
+/// argument `cb = () => {}`, a const generic `[u8; { N }]`), so `start` may
+/// be anywhere in a signature.
+pub(crate) fn find_body_end(content: &str, start: usize, language: Language) -> usize {
+    // Find the function's closing brace by properly tracking nested braces
+    let mut brace_count = 0;
+    let mut group_depth = 0i32;
+    let mut found_opening_brace = false;
+
+    for (i, c) in code_chars(&content[start..], language) {
+        match c {
+            '(' | '[' if !found_opening_brace => group_depth += 1,
+            ')' | ']' if !found_opening_brace => group_depth -= 1,
+            '{' if found_opening_brace || group_depth <= 0 => {
+                found_opening_brace = true;
+                brace_count += 1;
+            }
+            '}' if found_opening_brace => {
+                brace_count -= 1;
+                if brace_count == 0 {
+                    return start + i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // If we couldn't find the end properly, just use a large chunk
+    let mut end = std::cmp::min(start + 5000, content.len());
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
+
+/// The characters of `text` that are code, with their offsets: comments and
+/// string, character and template literals are skipped, so a `"{"`, a
+/// `'}'` or a commented-out brace is never matched. Rust lifetimes and
+/// labels (`'a`) are code.
+fn code_chars(text: &str, language: Language) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut skip_to = 0;
+    text.char_indices().filter(move |&(i, c)| {
+        if i < skip_to {
+            return false;
+        }
+        match literal_len(text, i, c, language) {
+            Some(len) => {
+                skip_to = i + len;
+                false
+            }
+            None => true,
+        }
+    })
+}
+
+/// The length of the comment or literal starting with `c` at `i`, or `None`
+/// when `c` is code.
+fn literal_len(text: &str, i: usize, c: char, language: Language) -> Option<usize> {
+    let rest = &text[i..];
+    match c {
+        '/' if rest.starts_with("//") => Some(rest.find('\n').unwrap_or(rest.len())),
+        '/' if rest.starts_with("/*") => Some(block_comment_len(rest, language == Language::Rust)),
+        // Swift multi-line strings
+        '"' if rest.starts_with("\"\"\"") => Some(
+            rest[3..]
+                .find("\"\"\"")
+                .map_or(rest.len(), |end| 3 + end + 3),
+        ),
+        // C# verbatim strings, where `""` is the only escape
+        '@' | '$' if language == Language::CSharp => verbatim_len(rest),
+        '"' => Some(quoted_len(rest, true)),
+        // Template literals, and Go raw strings without escapes
+        '`' if language != Language::Rust => Some(quoted_len(rest, language != Language::Go)),
+        '\'' if language == Language::Rust => char_literal_len(rest),
+        '\'' => Some(quoted_len(rest, true)),
+        'r' if language == Language::Rust => raw_string_len(text, i),
+        _ => None,
+    }
+}
+
+/// The length of the literal opening with the quote `rest` starts with, up
+/// to and including the matching quote. Single-quoted literals end at the
+/// line's end at the latest.
+fn quoted_len(rest: &str, escapes: bool) -> usize {
+    let quote = rest.chars().next().unwrap_or('"');
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if escapes => {
+                chars.next();
+            }
+            '\n' if quote == '\'' => return i,
+            c if c == quote => return i + 1,
+            _ => {}
+        }
+    }
+    rest.len()
+}
+
+/// The length of the C# verbatim string (`@"..."`, `$@"..."`, `@$"..."`)
+/// `rest` starts with, or `None` when it starts something else.
+fn verbatim_len(rest: &str) -> Option<usize> {
+    let prefix = ["@\"", "$@\"", "@$\""]
+        .iter()
+        .find(|prefix| rest.starts_with(**prefix))?;
+    let mut chars = rest.char_indices().skip(prefix.len()).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '"' {
+            if chars.peek().is_some_and(|(_, next)| *next == '"') {
+                chars.next();
+            } else {
+                return Some(i + 1);
+            }
+        }
+    }
+    Some(rest.len())
+}
+
+/// The length of the Rust character literal `rest` starts with (`'{'`,
+/// `'\''`, `'\u{7d}'`), or `None` for a lifetime or label.
+fn char_literal_len(rest: &str) -> Option<usize> {
+    let mut chars = rest.char_indices().skip(1);
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        chars.next();
+        return chars.find(|(_, c)| *c == '\'').map(|(i, _)| i + 1);
+    }
+    match chars.next()? {
+        (i, '\'') => Some(i + 1),
+        _ => None,
+    }
+}
+
+/// The length of the Rust raw string (`r"..."`, `r#"..."#`, `br"..."`)
+/// starting with the `r` at `i`, or `None` when the `r` is part of a name.
+fn raw_string_len(text: &str, i: usize) -> Option<usize> {
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut before = text[..i].chars().rev();
+    match before.next() {
+        Some('b') if !before.next().is_some_and(is_name_char) => {}
+        Some(c) if is_name_char(c) => return None,
+        _ => {}
+    }
+    let rest = &text[i + 1..];
+    let hashes = rest.len() - rest.trim_start_matches('#').len();
+    if !rest[hashes..].starts_with('"') {
+        return None;
+    }
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let body = &rest[hashes + 1..];
+    Some(
+        body.find(&closing)
+            .map_or(text.len() - i, |end| 1 + hashes + 1 + end + closing.len()),
+    )
+}
+
+/// The length of the block comment `rest` starts with; Rust's nest.
+fn block_comment_len(rest: &str, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < rest.len() {
+        if rest[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+            if !nested && depth > 1 {
+                depth = 1;
+            }
+        } else if rest[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += rest[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    rest.len()
+}
@@ -0,0 +1,205 @@
+use super::{
+    definition_start, doc_comment_above, find_body_end, free_key, line_number_at, FileItems,
+    Language,
+};
+use crate::{FunctionInfo, TypeInfo};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const C_KEYWORDS: &[&str] = &[
+    "if",
+    "else",
+    "for",
+    "while",
+    "do",
+    "switch",
+    "case",
+    "return",
+    "sizeof",
+    "typeof",
+    "defined",
+    "NSLog",
+    "assert",
+    "NSAssert",
+    "dispatch_async",
+    "dispatch_sync",
+    "selector",
+];
+
+pub fn extract(content: &str, path: &Path, module_path: &str) -> FileItems {
+    let mut function_info: HashMap<String, FunctionInfo> = HashMap::new();
+    let mut types: HashMap<String, TypeInfo> = HashMap::new();
+
+    // XCTest discovers `test...` methods in files of a test target
+    let in_test_file = path
+        .file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().ends_with("Tests"));
+
+    // Objective-C methods are indexed by the first part of their selector,
+    // which is what both message sends and Swift call sites use
+    let method_def_regex =
+        Regex::new(r"(?m)^[ \t]*[-+]\s*\([^)]*\)\s*([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+    // Plain C functions, which is how Objective-C usually calls into Rust
+    let c_fn_regex = Regex::new(
+        r"(?m)^[ \t]*(?:(?:static|inline|extern)\s+)*[A-Za-z_][A-Za-z0-9_]*[\s\*]+([A-Za-z_][A-Za-z0-9_]*)\s*\([^;{)]*\)\s*\{",
+    )
+    .unwrap();
+    let message_regex = Regex::new(r"\[\s*[^\[\]\s]+\s+([A-Za-z_][A-Za-z0-9_]*)\s*[:\]]").unwrap();
+    let call_regex = Regex::new(r"[^A-Za-z0-9_\.@]([A-Za-z_][A-Za-z0-9_]*)\s*\(").unwrap();
+
+    // Methods are keyed by the class they are implemented in, so `init` in
+    // two `@implementation` blocks of one file are both kept
+    let implementation_regex =
+        Regex::new(r"(?m)^[ \t]*@implementation\s+([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+    let implementations: Vec<(usize, usize, &str)> = implementation_regex
+        .captures_iter(content)
+        .map(|captures| {
+            let whole = captures.get(0).unwrap();
+            let end = content[whole.end()..]
+                .find("@end")
+                .map_or(content.len(), |i| whole.end() + i);
+            (whole.end(), end, captures.get(1).unwrap().as_str())
+        })
+        .collect();
+
+    let mut definitions = Vec::new();
+    for captures in method_def_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        // Declarations in class extensions end with `;` instead of a body
+        let rest = &content[whole.end()..];
+        match (rest.find('{'), rest.find(';')) {
+            (Some(brace), Some(semi)) if semi < brace => continue,
+            (None, _) => continue,
+            _ => {}
+        }
+        let class = implementations
+            .iter()
+            .find(|(start, end, _)| (*start..*end).contains(&whole.start()))
+            .map(|(_, _, class)| class.to_string());
+        definitions.push((
+            whole.start(),
+            whole.end(),
+            captures.get(1).unwrap().as_str(),
+            class,
+        ));
+    }
+    for captures in c_fn_regex.captures_iter(content) {
+        let name = captures.get(1).unwrap().as_str();
+        if C_KEYWORDS.contains(&name) {
+            continue;
+        }
+        let whole = captures.get(0).unwrap();
+        definitions.push((whole.start(), whole.start(), name, None));
+    }
+
+    for (start, body_search_start, function_name, self_type) in definitions {
+        let def_start = definition_start(content, start);
+        let line_number = line_number_at(content, def_start);
+        let def_end = find_body_end(content, body_search_start, Language::ObjectiveC);
+        let fn_body = content[def_start..def_end].trim().to_string();
+
+        let mut calls = HashSet::new();
+
+        // Look for message sends ([receiver selector:...])
+        for message_captures in message_regex.captures_iter(&fn_body) {
+            let selector = message_captures.get(1).unwrap().as_str();
+            if ![
+                "alloc",
+                "init",
+                "new",
+                "copy",
+                "release",
+                "retain",
+                "autorelease",
+                "count",
+                "length",
+                "objectAtIndex",
+                "objectForKey",
+                "setObject",
+                "addObject",
+            ]
+            .contains(&selector)
+            {
+                calls.insert(selector.to_string());
+            }
+        }
+
+        // Look for C function calls (function())
+        for call_captures in call_regex.captures_iter(&fn_body) {
+            let called_function = call_captures.get(1).unwrap().as_str();
+            if !C_KEYWORDS.contains(&called_function) && called_function != function_name {
+                calls.insert(called_function.to_string());
+            }
+        }
+
+        // Methods of one class sharing the first part of their selector
+        // (`initWithName:` and `initWithName:age:`) as `name#2`, ...
+        let base_key = match &self_type {
+            Some(self_type) => format!("{}::{}", self_type, function_name),
+            None => function_name.to_string(),
+        };
+        function_info.insert(
+            free_key(&function_info, base_key),
+            FunctionInfo {
+                path: path.to_path_buf(),
+                language: Language::ObjectiveC,
+                module_path: module_path.to_string(),
+                definition: fn_body,
+                line_number,
+                calls,
+                doc: doc_comment_above(content, def_start),
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: None,
+                is_test: in_test_file && function_name.starts_with("test"),
+                self_type,
+            },
+        );
+    }
+
+    let type_regex =
+        Regex::new(r"(?m)^\s*@(interface|implementation|protocol)\s+([A-Za-z_][A-Za-z0-9_]*)")
+            .unwrap();
+
+    for captures in type_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let def_start = definition_start(content, whole.start());
+        // Interfaces declare properties and methods up to `@end`; the
+        // implementation's methods are indexed as functions already
+        let def_end = match captures.get(1).unwrap().as_str() {
+            "interface" | "protocol" => content[whole.end()..]
+                .find("@end")
+                .map_or(content.len(), |i| whole.end() + i + 4),
+            _ => content[whole.end()..]
+                .find('\n')
+                .map_or(content.len(), |i| whole.end() + i),
+        };
+        let type_name = captures.get(2).unwrap().as_str().to_string();
+
+        // An interface is more informative than its implementation line
+        if types.contains_key(&type_name) && captures.get(1).unwrap().as_str() == "implementation" {
+            continue;
+        }
+
+        types.insert(
+            type_name,
+            TypeInfo {
+                path: path.to_path_buf(),
+                language: Language::ObjectiveC,
+                definition: content[def_start..def_end].trim().to_string(),
+                line_number: line_number_at(content, def_start),
+                doc: doc_comment_above(content, def_start),
+            },
+        );
+    }
+
+    FileItems {
+        functions: function_info,
+        types,
+        impls: Vec::new(),
+        aliases: Vec::new(),
+        generated: false,
+    }
+}
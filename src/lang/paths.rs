@@ -0,0 +1,363 @@
+//! What module paths in Rust calls name: `use` declarations, `crate::`,
+//! `self::` and `super::` prefixes and child modules, so a call written
+//! through a path (`crate::net::fetch()`, or `fetch()` after
+//! `use crate::net::fetch`) is recorded as the qualified name it reaches
+//! rather than a bare name to choose between.
+//!
+//! Module paths follow the index's file-based naming: items of a crate root
+//! (`src/main.rs`) are keyed under the root file's module, while the
+//! modules it declares are keyed under its directory.
+
+use super::rust::{is_value_name, unraw, SKIPPED_CALLS};
+use super::{find_body_end, Language};
+use crate::cargo;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Where the items and the child modules of one module are keyed. The two
+/// differ only for a crate root file: `src::main::run`, but `src::net`.
+#[derive(Debug, Clone, PartialEq)]
+struct Location {
+    items: Vec<String>,
+    children: Vec<String>,
+}
+
+/// The crate a file belongs to, by the module paths of its root file.
+#[derive(Debug, Clone)]
+struct CrateRoot {
+    items: Vec<String>,
+    children: Vec<String>,
+    /// The name the package's library is used by, and where it is keyed
+    library: Option<(String, Vec<String>)>,
+}
+
+impl CrateRoot {
+    /// The location of the module keyed `module`.
+    fn location(&self, module: Vec<String>) -> Location {
+        match module == self.children {
+            true => Location {
+                items: self.items.clone(),
+                children: module,
+            },
+            false => Location {
+                items: module.clone(),
+                children: module,
+            },
+        }
+    }
+
+    /// The module containing `location`, or `None` at the crate root.
+    fn parent(&self, location: &Location) -> Option<Location> {
+        if location.children == self.children {
+            return None;
+        }
+        let mut module = location.children.clone();
+        module.pop()?;
+        Some(self.location(module))
+    }
+}
+
+/// What paths mean in one module of a file: the file itself, or a
+/// `mod name { ... }` block inside it, whose items the index keys under
+/// the file's module.
+#[derive(Debug)]
+pub struct PathScope {
+    location: Location,
+    /// Where `super::` leads, `None` at the crate root
+    parent: Option<Location>,
+    root: Option<CrateRoot>,
+    /// Names brought in by `use`, to the path as written
+    imports: HashMap<String, Vec<String>>,
+    /// Modules declared with `mod name;`
+    modules: HashSet<String>,
+}
+
+impl PathScope {
+    /// The call name of a call through `segments`, `path(module::name)`,
+    /// when the path starts with `crate`, `self`, `super`, an imported name,
+    /// a declared module or another crate. Bare names that aren't imported
+    /// and calls through a type (`Vec::new()`) are left to name-based
+    /// resolution.
+    pub fn call_name(&self, segments: &[&str]) -> Option<String> {
+        let (name, _) = segments.split_last()?;
+        if !is_value_name(unraw(name)) {
+            return None;
+        }
+        let mut segments: Vec<String> = segments.iter().map(|s| unraw(s).to_string()).collect();
+        if let Some(import) = self.imports.get(&segments[0]) {
+            segments.splice(..1, import.iter().cloned());
+        }
+        let qualified = self.resolve(&segments)?;
+        Some(format!("path({})", qualified.join("::")))
+    }
+
+    /// The qualified name `segments` reaches from this scope.
+    fn resolve(&self, segments: &[String]) -> Option<Vec<String>> {
+        let (name, path) = segments.split_last()?;
+        // A bare name is an item of this module only when imported
+        let (first, rest) = path.split_first()?;
+        // The package's own library, as its binaries refer to it
+        let library = self
+            .root
+            .as_ref()
+            .and_then(|root| root.library.as_ref())
+            .filter(|(name, _)| name == first)
+            .map(|(_, module)| self.location_of(module.clone()));
+        let mut location = match first.as_str() {
+            _ if library.is_some() => library?,
+            "crate" => {
+                let root = self.root.as_ref()?;
+                root.location(root.children.clone())
+            }
+            "self" => self.location.clone(),
+            "super" => self.parent.clone()?,
+            module if self.modules.contains(module) => {
+                let mut child = self.location.children.clone();
+                child.push(module.to_string());
+                self.location_of(child)
+            }
+            // Another crate, keyed under its name when it is a workspace
+            // crate. Paths into dependencies name nothing in the index and
+            // fall back to the name, unless it is one never followed
+            // (`thread::spawn`).
+            other if is_value_name(other) && !SKIPPED_CALLS.contains(&name.as_str()) => Location {
+                items: vec![other.to_string()],
+                children: vec![other.to_string()],
+            },
+            _ => return None,
+        };
+        let mut in_type = false;
+        for segment in rest {
+            if segment == "super" && !in_type {
+                location = self.root.as_ref()?.parent(&location)?;
+            } else if in_type || !is_value_name(segment) {
+                // A type: its methods are keyed under it, and it has no
+                // modules
+                location.items.push(segment.clone());
+                in_type = true;
+            } else {
+                let mut child = location.children.clone();
+                child.push(segment.clone());
+                location = self.location_of(child);
+            }
+        }
+        let mut qualified = location.items;
+        qualified.push(name.clone());
+        Some(qualified)
+    }
+
+    /// The location of the module keyed `module`.
+    fn location_of(&self, module: Vec<String>) -> Location {
+        match &self.root {
+            Some(root) => root.location(module),
+            None => Location {
+                items: module.clone(),
+                children: module,
+            },
+        }
+    }
+}
+
+/// The path scopes of a file: the file itself, then its inline modules.
+pub struct PathScopes {
+    /// Each scope with the byte range it covers
+    scopes: Vec<(usize, usize, PathScope)>,
+}
+
+impl PathScopes {
+    pub fn new(content: &str, path: &Path, module_path: &str) -> PathScopes {
+        let module: Vec<String> = module_path.split("::").map(str::to_string).collect();
+        let root = crate_root(path, &module);
+        let location = match &root {
+            Some(root) if root.items == module => Location {
+                items: module.clone(),
+                children: root.children.clone(),
+            },
+            _ => Location {
+                items: module.clone(),
+                children: module,
+            },
+        };
+        let parent = root.as_ref().and_then(|root| root.parent(&location));
+        let mut scopes = vec![(
+            0,
+            content.len(),
+            PathScope {
+                location: location.clone(),
+                parent,
+                root: root.clone(),
+                imports: HashMap::new(),
+                modules: HashSet::new(),
+            },
+        )];
+
+        // Inline modules share the file's keys; `super::` leads back to it
+        let mod_regex =
+            Regex::new(r"(?m)^[ \t]*(?:pub(?:\([^)]*\))?\s+)?mod\s+((?:r#)?\w+)\s*([;{])").unwrap();
+        for captures in mod_regex.captures_iter(content) {
+            let name = unraw(&captures[1]).to_string();
+            let at = captures.get(0).unwrap().start();
+            // Paths into an inline module are left to name-based
+            // resolution, as its items aren't keyed under it
+            if &captures[2] == ";" {
+                let scope = innermost(&scopes, at);
+                scopes[scope].2.modules.insert(name);
+            } else {
+                let brace = captures.get(2).unwrap().start();
+                scopes.push((
+                    brace,
+                    find_body_end(content, brace, Language::Rust),
+                    PathScope {
+                        location: location.clone(),
+                        parent: Some(location.clone()),
+                        root: root.clone(),
+                        imports: HashMap::new(),
+                        modules: HashSet::new(),
+                    },
+                ));
+            }
+        }
+
+        let use_regex = Regex::new(r"(?m)^[ \t]*(?:pub(?:\([^)]*\))?\s+)?use\s+([^;]+);").unwrap();
+        let token_regex = Regex::new(r"(?:r#)?\w+|::|[{},*]").unwrap();
+        for captures in use_regex.captures_iter(content) {
+            let at = captures.get(0).unwrap().start();
+            let tokens: Vec<&str> = token_regex
+                .find_iter(&captures[1])
+                .map(|token| token.as_str())
+                .collect();
+            let mut imports = Vec::new();
+            use_tree(&tokens, &mut 0, &[], &mut imports);
+            let scope = innermost(&scopes, at);
+            scopes[scope].2.imports.extend(imports);
+        }
+        PathScopes { scopes }
+    }
+
+    /// The innermost scope around byte offset `at`.
+    pub fn at(&self, at: usize) -> &PathScope {
+        &self.scopes[innermost(&self.scopes, at)].2
+    }
+}
+
+/// Index of the smallest scope containing `at`; the file scope contains
+/// everything.
+fn innermost(scopes: &[(usize, usize, PathScope)], at: usize) -> usize {
+    scopes
+        .iter()
+        .enumerate()
+        .filter(|(_, (start, end, _))| *start <= at && at < *end)
+        .min_by_key(|(_, (start, end, _))| end - start)
+        .map_or(0, |(i, _)| i)
+}
+
+/// Adds the names one `use` tree imports, with the path each stands for.
+/// Glob imports (`use super::*`) name nothing in particular and are
+/// skipped, as are `_` imports.
+fn use_tree(
+    tokens: &[&str],
+    pos: &mut usize,
+    prefix: &[String],
+    imports: &mut Vec<(String, Vec<String>)>,
+) {
+    let mut path = prefix.to_vec();
+    let mut alias = None;
+    while let Some(&token) = tokens.get(*pos) {
+        match token {
+            "::" => *pos += 1,
+            "{" => {
+                *pos += 1;
+                while tokens.get(*pos).is_some_and(|token| *token != "}") {
+                    use_tree(tokens, pos, &path, imports);
+                    if tokens.get(*pos) == Some(&",") {
+                        *pos += 1;
+                    }
+                }
+                *pos += 1;
+                return;
+            }
+            "*" => {
+                *pos += 1;
+                return;
+            }
+            "," | "}" => break,
+            "as" => {
+                alias = tokens.get(*pos + 1).map(|alias| unraw(alias).to_string());
+                *pos += 2;
+            }
+            segment => {
+                path.push(unraw(segment).to_string());
+                *pos += 1;
+            }
+        }
+    }
+    if path.len() == prefix.len() {
+        return;
+    }
+    // `use crate::net::{self, fetch}` imports `net` itself
+    if path.last().is_some_and(|last| last == "self") {
+        path.pop();
+    }
+    let Some(name) = alias.or_else(|| path.last().cloned()) else {
+        return;
+    };
+    if name != "_" {
+        imports.push((name, path));
+    }
+}
+
+/// The crate root of a file under a `src/` directory: `src/lib.rs` when
+/// the crate has one, else `src/main.rs`, and each binary in `src/bin/` is
+/// a crate of its own. `module` is the file's module path, whose leading
+/// segments name the `src/` directory however the index spells it.
+fn crate_root(path: &Path, module: &[String]) -> Option<CrateRoot> {
+    let components: Vec<String> = path
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let src = components
+        .iter()
+        .rposition(|component| component == "src")?;
+    let below = &components[src + 1..];
+    let prefix_len = module.len().checked_sub(module_len(below))?;
+    let src_dir = path.ancestors().nth(below.len())?;
+
+    let prefix = &module[..prefix_len];
+    let library = src_dir
+        .join("lib.rs")
+        .is_file()
+        .then(|| cargo::library_name(&src_dir.parent()?.join("Cargo.toml")))
+        .flatten()
+        .map(|name| (name, prefix.to_vec()));
+
+    let root: Vec<&str> = match below {
+        [bin, name] if bin == "bin" => vec!["bin", name],
+        [bin, name, ..] if bin == "bin" => vec!["bin", name, "main"],
+        [main] if main == "main" => vec!["main"],
+        _ if src_dir.join("lib.rs").is_file() => vec!["lib"],
+        _ => vec!["main"],
+    };
+    let mut items = prefix.to_vec();
+    items.extend(root.iter().map(|s| s.to_string()));
+    if root.last() == Some(&"lib") {
+        items.pop();
+    }
+    let mut children = prefix.to_vec();
+    children.extend(root[..root.len() - 1].iter().map(|s| s.to_string()));
+    Some(CrateRoot {
+        items,
+        children,
+        library,
+    })
+}
+
+/// How many module path segments the path components below `src/` make:
+/// `lib.rs` and `mod.rs` are keyed by their directory.
+fn module_len(below: &[String]) -> usize {
+    match below.last().map(String::as_str) {
+        Some("lib" | "mod") => below.len() - 1,
+        _ => below.len(),
+    }
+}
@@ -0,0 +1,975 @@
+use super::paths::{PathScope, PathScopes};
+use super::{
+    attributes_start, definition_start, doc_and_attributes_start, doc_comment_above, find_body_end,
+    find_item_end, free_key, leading_attributes, line_number_at, ExtractOptions, FileItems,
+    Language,
+};
+#[cfg(feature = "syn-backend")]
+use crate::parser::{parse_functions, Call};
+use crate::{FunctionInfo, ImplInfo, TypeInfo};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Iterator/Option/Result adapters whose arguments are often plain function
+/// references rather than closures.
+const COMBINATORS: &[&str] = &[
+    "map",
+    "and_then",
+    "then",
+    "for_each",
+    "filter",
+    "filter_map",
+    "flat_map",
+    "map_err",
+    "or_else",
+    "unwrap_or_else",
+    "map_or_else",
+    "inspect",
+    "try_for_each",
+    "fold",
+    "any",
+    "all",
+    "find",
+    "retain",
+];
+
+/// Types computing their value on first use with the closure or function
+/// the static is defined with.
+const LAZY_TYPES: &[&str] = &["Lazy", "LazyLock", "LazyCell"];
+
+/// Types whose value is set by whichever code first calls `set` or
+/// `get_or_init` on them.
+const ONCE_TYPES: &[&str] = &["OnceCell", "OnceLock"];
+
+/// A Rust identifier: XID characters, optionally raw (`r#type`).
+const IDENT: &str = r"(?:r#)?[_\p{XID_Start}]\p{XID_Continue}*";
+
+/// An identifier not starting with an uppercase letter, as function and
+/// module names are written.
+const VALUE_IDENT: &str = r"(?:r#)?[[_\p{XID_Start}]--[\p{Lu}\p{Lt}]]\p{XID_Continue}*";
+
+/// A function definition found by either extraction path.
+struct FoundFunction {
+    name: String,
+    self_type: Option<String>,
+    def_start: usize,
+    def_end: usize,
+    calls: HashSet<String>,
+}
+
+pub fn extract(
+    content: &str,
+    path: &Path,
+    module_path: &str,
+    options: &ExtractOptions,
+) -> FileItems {
+    let mut function_info: HashMap<String, FunctionInfo> = HashMap::new();
+    let mut types: HashMap<String, TypeInfo> = HashMap::new();
+
+    // Extract function definitions with their body. Qualifiers such as
+    // `unsafe` and `extern "C"` matter for FFI entry points that other
+    // language backends call into.
+    let fn_regex = Regex::new(&format!(
+        r#"(?m)^\s*(?:#\[[^\]\n]*\][ \t]*)*(pub(\([^)]*\))?\s+)?(const\s+)?(async\s+)?(unsafe\s+)?(extern\s+"[^"]*"\s+)?fn\s+({})\s*(<.*?>)?\s*\("#,
+        IDENT
+    ))
+    .unwrap();
+    let call_patterns = CallPatterns::new();
+    let attribute_regex = Regex::new(&format!(r"^#\[(?:{0}::)*({0})", IDENT)).unwrap();
+    let setter_regex = Regex::new(&format!(
+        r"\b({})\s*\.\s*(?:set|get_or_init|get_or_try_init)\s*\(",
+        IDENT
+    ))
+    .unwrap();
+    let mut aliases = Vec::new();
+    let scopes = PathScopes::new(content, path, module_path);
+
+    // `fn` tokens inside macro_rules! bodies are templates, not functions
+    let macro_spans = macro_definitions(content);
+    let in_macro = |pos: usize| macro_spans.iter().any(|m| m.start <= pos && pos < m.end);
+
+    // Also extract struct/enum/type definitions
+    let type_regex = Regex::new(&format!(
+        r"(?m)^\s*(pub(\([^)]*\))?\s+)?(struct|enum|type|trait|union)\s+({})",
+        IDENT
+    ))
+    .unwrap();
+
+    // Trait definitions as (name, first line, last line), for qualifying
+    // default methods found by the regex fallback
+    let mut trait_spans = Vec::new();
+    for captures in type_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        if in_macro(whole.start()) {
+            continue;
+        }
+        let type_name = unraw(captures.get(4).unwrap().as_str());
+        let def_start = definition_start(content, whole.start());
+        let def_end = find_item_end(content, whole.end(), Language::Rust);
+        // Keep derives and other attributes, they define much of a type's behavior
+        let attrs_start = attributes_start(content, def_start);
+        if &captures[3] == "trait" {
+            trait_spans.push((
+                type_name.to_string(),
+                line_number_at(content, def_start),
+                line_number_at(content, def_end),
+            ));
+        }
+
+        types.insert(
+            type_name.to_string(),
+            TypeInfo {
+                path: path.to_path_buf(),
+                language: Language::Rust,
+                definition: content[attrs_start..def_end].trim().to_string(),
+                line_number: line_number_at(content, def_start),
+                doc: doc_comment_above(content, def_start),
+            },
+        );
+    }
+
+    // Record impl blocks so methods, constructors and trait impls can be
+    // related back to the type they belong to
+    let impl_regex = Regex::new(r"(?m)^\s*(unsafe\s+)?impl\b").unwrap();
+    let mut impls = Vec::new();
+
+    for impl_match in impl_regex.find_iter(content) {
+        if in_macro(impl_match.start()) {
+            continue;
+        }
+        let def_start = definition_start(content, impl_match.start());
+        let Some(brace) = content[impl_match.end()..].find(['{', ';']) else {
+            continue;
+        };
+        let header_end = impl_match.end() + brace;
+        if !content[header_end..].starts_with('{') {
+            continue;
+        }
+        let def_end = find_body_end(content, header_end, Language::Rust);
+        let header = content[impl_match.end()..header_end].trim();
+        let (trait_name, self_type) = split_impl_header(header);
+
+        impls.push(ImplInfo {
+            path: path.to_path_buf(),
+            self_type,
+            trait_name,
+            definition: content[def_start..def_end].trim().to_string(),
+            line_number: line_number_at(content, def_start),
+            end_line: line_number_at(content, def_end),
+        });
+    }
+
+    // Definitions come from the syntax tree when the file parses, and from
+    // line regexes otherwise
+    let found: Vec<FoundFunction> = match parsed_functions(content, options, &scopes) {
+        Some(found) => found,
+        None if !cfg!(feature = "regex-backend") => Vec::new(),
+        None => fn_regex
+            .captures_iter(content)
+            .filter(|captures| !in_macro(captures.get(0).unwrap().start()))
+            .map(|captures| {
+                // Required trait methods end at their `;`
+                let def_end =
+                    find_item_end(content, captures.get(0).unwrap().end() - 1, Language::Rust);
+                // Scanning starts after the name so the signature itself
+                // doesn't look like a call
+                let name_end = captures.get(7).unwrap().end();
+                let start = captures.get(0).unwrap().start();
+                let line_number = line_number_at(content, start);
+                FoundFunction {
+                    name: unraw(&captures[7]).to_string(),
+                    self_type: enclosing_type(&impls, &trait_spans, line_number),
+                    def_start: definition_start(content, start),
+                    def_end,
+                    calls: call_patterns.extract(
+                        &content[name_end..def_end],
+                        options,
+                        scopes.at(start),
+                    ),
+                }
+            })
+            .collect(),
+    };
+
+    for FoundFunction {
+        name: function_name,
+        self_type,
+        def_start,
+        def_end,
+        mut calls,
+    } in found
+    {
+        let function_name = function_name.as_str();
+        // Doc comments and attributes (`#[instrument]`, `#[test]`) are part
+        // of the definition, and it starts where they do
+        let preamble_start = doc_and_attributes_start(content, def_start);
+        let line_number = line_number_at(content, preamble_start);
+        let fn_body = content[preamble_start..def_end].trim().to_string();
+        let attributes = leading_attributes(&content[preamble_start..def_end]);
+        let is_test = has_test_attribute(&attributes);
+
+        // Attribute macros applied to this function are calls to their
+        // `#[proc_macro_attribute]` definitions
+        for attribute in &attributes {
+            if let Some(attribute_captures) = attribute_regex.captures(attribute) {
+                calls.insert(format!(
+                    "#[{}]",
+                    unraw(attribute_captures.get(1).unwrap().as_str())
+                ));
+            }
+        }
+        if let Some(alias) = proc_macro_alias(&attributes, function_name) {
+            aliases.push((alias, function_name.to_string()));
+        }
+
+        // Methods are keyed by their type, so `Foo::bar` and `Baz::bar` in
+        // one file don't overwrite each other
+        let base_key = match &self_type {
+            Some(self_type) => format!("{}::{}", self_type, function_name),
+            None => function_name.to_string(),
+        };
+        // `#[cfg]` variants of one function (unix and windows versions) are
+        // all kept, the later ones as `name#2`, `name#3`, ...
+        let key = free_key(&function_info, base_key);
+        // Functions filling a once-initialized global are its initializers
+        for setter_captures in setter_regex.captures_iter(&fn_body) {
+            let global = unraw(&setter_captures[1]);
+            if is_global_name(global) {
+                aliases.push((format!("init({})", global), key.clone()));
+            }
+        }
+        function_info.insert(
+            key,
+            FunctionInfo {
+                path: path.to_path_buf(),
+                language: Language::Rust,
+                module_path: module_path.to_string(),
+                definition: fn_body,
+                line_number,
+                calls,
+                doc: doc_comment_above(content, def_start),
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: cfg_condition(&attributes),
+                is_test,
+                self_type,
+            },
+        );
+    }
+
+    // Index macro definitions as templates named `name!`, which macro
+    // invocations resolve to
+    for macro_span in &macro_spans {
+        let definition = content[macro_span.start..macro_span.end].trim().to_string();
+        let calls = call_patterns.extract(&definition, options, scopes.at(macro_span.start));
+        // Like functions, `#[cfg]` variants of a macro are all kept
+        function_info.insert(
+            free_key(&function_info, format!("{}!", macro_span.name)),
+            FunctionInfo {
+                path: path.to_path_buf(),
+                language: Language::Rust,
+                module_path: module_path.to_string(),
+                definition,
+                line_number: line_number_at(content, macro_span.start),
+                calls,
+                doc: doc_comment_above(content, macro_span.start),
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: None,
+                is_test: false,
+                self_type: None,
+            },
+        );
+    }
+
+    // Lazily initialized globals are indexed by name with their initializer,
+    // which the functions reading them reach
+    for global in lazy_globals(content) {
+        if in_macro(global.start) {
+            continue;
+        }
+        let preamble_start = doc_and_attributes_start(content, global.start);
+        let initializer = &content[global.initializer..global.end];
+        let mut calls = call_patterns.extract(initializer, options, scopes.at(global.start));
+        // `LazyLock::new(load_config)` names its initializer without calling it
+        if let Some(new) = initializer.find("::new(") {
+            for argument in call_arguments(&initializer[new + "::new(".len()..]) {
+                if let Some(path_captures) = call_patterns.fn_path.captures(argument.trim()) {
+                    calls.insert(unraw(&path_captures[1]).to_string());
+                }
+            }
+        }
+        if global.set_later {
+            calls.insert(format!("init({})", global.name));
+        }
+        let key = free_key(&function_info, global.name.clone());
+        aliases.push((format!("static({})", global.name), key.clone()));
+        function_info.insert(
+            key,
+            FunctionInfo {
+                path: path.to_path_buf(),
+                language: Language::Rust,
+                module_path: module_path.to_string(),
+                definition: content[preamble_start..global.end].trim().to_string(),
+                line_number: line_number_at(content, preamble_start),
+                calls,
+                doc: doc_comment_above(content, global.start),
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: None,
+                is_test: false,
+                self_type: None,
+            },
+        );
+    }
+
+    FileItems {
+        functions: function_info,
+        types,
+        impls,
+        aliases,
+        generated: is_generated_proto(content),
+    }
+}
+
+/// The type a function starting on `line_number` is a method of: the
+/// innermost impl block or trait definition around it.
+fn enclosing_type(
+    impls: &[ImplInfo],
+    trait_spans: &[(String, usize, usize)],
+    line_number: usize,
+) -> Option<String> {
+    impls
+        .iter()
+        .map(|i| (&i.self_type, i.line_number, i.end_line))
+        .chain(
+            trait_spans
+                .iter()
+                .map(|(name, start, end)| (name, *start, *end)),
+        )
+        .filter(|(_, start, end)| *start < line_number && line_number <= *end)
+        .max_by_key(|(_, start, _)| *start)
+        .map(|(name, _, _)| name.clone())
+}
+
+/// Splits an impl header such as `<'a> Arbitrary<'a> for Invoice<T> where ...`
+/// into the trait's simple name (if any) and the implementing type's simple name.
+fn split_impl_header(header: &str) -> (Option<String>, String) {
+    // Drop the impl's own generic parameters
+    let mut rest = header;
+    if rest.starts_with('<') {
+        let mut depth = 0;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        rest = &rest[i + 1..];
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let rest = rest.split(" where").next().unwrap_or(rest).trim();
+
+    let simple_name = |path: &str| -> String {
+        let path = path
+            .trim()
+            .trim_start_matches('&')
+            .trim_start_matches("mut ");
+        let path = path.split('<').next().unwrap_or(path);
+        path.rsplit("::").next().unwrap_or(path).trim().to_string()
+    };
+
+    match rest.split_once(" for ") {
+        Some((trait_path, self_path)) => (
+            Some(simple_name(trait_path.trim_start_matches('!'))),
+            simple_name(self_path),
+        ),
+        None => (None, simple_name(rest)),
+    }
+}
+
+/// Common built-in methods, skipped when recording method calls.
+const SKIPPED_METHODS: &[&str] = &[
+    "is_empty",
+    "len",
+    "clone",
+    "unwrap",
+    "unwrap_or",
+    "unwrap_or_else",
+    "expect",
+    "map",
+    "map_err",
+    "and_then",
+    "or_else",
+    "filter",
+    "collect",
+    "to_string",
+    "to_str",
+    "parse",
+    "as_str",
+    "as_ref",
+    "display",
+    "send",
+    "await",
+    "lock",
+    "get",
+    "push",
+    "pop",
+    "clear",
+    "insert",
+    "contains_key",
+];
+
+/// Keywords, macros and builtins skipped when recording `name(...)` calls.
+pub(super) const SKIPPED_CALLS: &[&str] = &[
+    "if", "for", "while", "match", "return", "assert", "println", "panic", "format", "print",
+    "info", "error", "warn", "debug", "trace", "let", "break", "continue", "loop", "async",
+    "await", "move", "static", "const", "struct", "enum", "trait", "impl", "type", "pub", "self",
+    "map", "filter", "as", "is", "mut", "ref", "vec", "super", "use", "extern", "spawn", "process",
+    "eprintln", "unwrap",
+];
+
+/// Functions from the syntax tree, or `None` when the file doesn't parse.
+#[cfg(feature = "syn-backend")]
+fn parsed_functions(
+    content: &str,
+    options: &ExtractOptions,
+    scopes: &PathScopes,
+) -> Option<Vec<FoundFunction>> {
+    if options.rust_backend == super::RustBackend::Regex {
+        return None;
+    }
+    let parsed = parse_functions(content)?;
+    Some(
+        parsed
+            .into_iter()
+            .map(|function| FoundFunction {
+                calls: parsed_call_names(&function.calls, options, scopes.at(function.start)),
+                name: unraw(&function.name).to_string(),
+                self_type: function.self_type.as_deref().map(unraw).map(str::to_string),
+                def_start: function.start,
+                def_end: function.end,
+            })
+            .collect(),
+    )
+}
+
+/// Built without `syn`, every file goes through the line regexes.
+#[cfg(not(feature = "syn-backend"))]
+fn parsed_functions(
+    _content: &str,
+    _options: &ExtractOptions,
+    _scopes: &PathScopes,
+) -> Option<Vec<FoundFunction>> {
+    None
+}
+
+/// The `cfg(...)` conditions among a function's attributes, joined with
+/// `, `.
+fn cfg_condition(attributes: &[String]) -> Option<String> {
+    let conditions: Vec<&str> = attributes
+        .iter()
+        .filter_map(|attribute| attribute.strip_prefix("#[")?.strip_suffix(']'))
+        .filter(|attribute| attribute.starts_with("cfg("))
+        .collect();
+    (!conditions.is_empty()).then(|| conditions.join(", "))
+}
+
+/// Call names for the call sites the syntax-tree parser found, recorded
+/// the same way the regex scan records them.
+#[cfg(feature = "syn-backend")]
+fn parsed_call_names(
+    calls: &[Call],
+    options: &ExtractOptions,
+    scope: &PathScope,
+) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for call in calls {
+        match call {
+            Call::Method {
+                name,
+                path_arguments,
+                on_self,
+            } => {
+                // `self.bar()` resolves through the caller's own type
+                if !SKIPPED_METHODS.contains(&name.as_str()) {
+                    names.insert(if *on_self {
+                        format!("Self::{}", unraw(name))
+                    } else {
+                        format!(".{}", unraw(name))
+                    });
+                }
+                if options.expand_combinators && COMBINATORS.contains(&name.as_str()) {
+                    for argument in path_arguments {
+                        let path: Vec<&str> = argument.iter().map(String::as_str).collect();
+                        point_free_call_names(&path, scope, &mut names);
+                    }
+                }
+            }
+            Call::Path(segments) => {
+                let path: Vec<&str> = segments.iter().map(String::as_str).collect();
+                path_call_names(&path, scope, &mut names);
+            }
+            Call::Macro(name) => {
+                if !STD_MACROS.contains(&name.as_str()) && name != "macro_rules" {
+                    names.insert(format!("{}!", unraw(name)));
+                }
+            }
+        }
+    }
+    names
+}
+
+/// The call names for a call by `path` (`name(..)`, `path::to::name(..)`).
+fn path_call_names(path: &[&str], scope: &PathScope, names: &mut HashSet<String>) {
+    let Some(name) = path.last() else {
+        return;
+    };
+    // A path through `crate`, `self`, `super`, an import or a declared
+    // module names its function outright
+    if let Some(call_name) = scope.call_name(path) {
+        names.insert(call_name);
+        return;
+    }
+    // Otherwise keep the last qualifier, as the regex scan does. A call
+    // through a type (`Foo::new`, `Self::check`) resolves by the qualified
+    // name alone; a module path also by the bare name.
+    let mut bare = true;
+    if let [.., qualifier, name] = path {
+        if is_value_name(unraw(name)) {
+            names.insert(format!("{}::{}", unraw(qualifier), unraw(name)));
+            bare = is_value_name(unraw(qualifier));
+        }
+    }
+    // Skipped names are checked as written: keywords can only be called in
+    // raw form (`r#type`)
+    if bare && !SKIPPED_CALLS.contains(name) {
+        names.insert(unraw(name).to_string());
+    }
+}
+
+/// The call names for a function passed by `path` to a combinator
+/// (`.map(parse)`, `.and_then(Self::check)`), which are those of a call by
+/// that path.
+fn point_free_call_names(path: &[&str], scope: &PathScope, names: &mut HashSet<String>) {
+    match path {
+        [name] if !is_value_name(unraw(name)) || ["self", "drop", "identity"].contains(name) => {}
+        _ => path_call_names(path, scope, names),
+    }
+}
+
+/// Compiled patterns for finding the calls made in a body.
+struct CallPatterns {
+    method: Regex,
+    call: Regex,
+    builder: Regex,
+    combinator: Regex,
+    fn_path: Regex,
+    qualified: Regex,
+    macro_call: Regex,
+}
+
+impl CallPatterns {
+    fn new() -> CallPatterns {
+        CallPatterns {
+            method: Regex::new(&format!(r"\.({})\s*\(", IDENT)).unwrap(),
+            call: Regex::new(&format!(r"[^\p{{XID_Continue}}.:#]({})\s*\(", IDENT)).unwrap(),
+            builder: Regex::new(r"(?:^|[^\p{XID_Continue}:#])((?:r#)?\p{XID_Continue}+)\s*\(\s*\)")
+                .unwrap(),
+            combinator: Regex::new(&format!(r"\.({})\s*\(", COMBINATORS.join("|"))).unwrap(),
+            fn_path: Regex::new(&format!(r"^(?:{}::)*({})$", IDENT, VALUE_IDENT)).unwrap(),
+            qualified: Regex::new(&format!(
+                r"((?:{0}::)*{0})::({1})\s*(?:::<[^>]*>)?\s*\(",
+                IDENT, VALUE_IDENT
+            ))
+            .unwrap(),
+            macro_call: Regex::new(&format!(
+                r"(?:^|[^\p{{XID_Continue}}#])({})!\s*[({{\[]",
+                IDENT
+            ))
+            .unwrap(),
+        }
+    }
+
+    fn extract(&self, body: &str, options: &ExtractOptions, scope: &PathScope) -> HashSet<String> {
+        let mut calls = HashSet::new();
+
+        // Look for method calls (.method())
+        for method_captures in self.method.captures_iter(body) {
+            let method_name = method_captures.get(1).unwrap().as_str();
+            // Skip common built-ins and add the rest
+            if SKIPPED_METHODS.contains(&method_name) {
+                continue;
+            }
+            if on_self(&body[..method_captures.get(1).unwrap().start()]) {
+                calls.insert(format!("Self::{}", unraw(method_name)));
+            } else {
+                calls.insert(format!(".{}", unraw(method_name)));
+            }
+        }
+
+        // Look for function calls (function())
+        for call_captures in self.call.captures_iter(body) {
+            let called_function = call_captures.get(1).unwrap().as_str();
+
+            // Skip known keywords, macros, and builtins
+            if SKIPPED_CALLS.contains(&called_function) {
+                continue;
+            }
+
+            // An imported function is named by its path
+            match scope.call_name(&[called_function]) {
+                Some(call_name) => calls.insert(call_name),
+                None => calls.insert(unraw(called_function).to_string()),
+            };
+        }
+
+        // Look for AWS SDK builder pattern calls
+        for builder_captures in self.builder.captures_iter(body) {
+            let builder_fn = builder_captures.get(1).unwrap().as_str();
+            let before = &body[..builder_captures.get(1).unwrap().start()];
+            if on_self(before) {
+                continue;
+            }
+            if before.ends_with('.') && !before.ends_with("..") {
+                calls.insert(format!(".{}", unraw(builder_fn)));
+                continue;
+            }
+            if ![
+                "Ok", "Err", "Some", "None", "Arc", "Vec", "HashMap", "HashSet", "String",
+            ]
+            .contains(&builder_fn)
+            {
+                match scope.call_name(&[builder_fn]) {
+                    Some(call_name) => calls.insert(call_name),
+                    None => calls.insert(unraw(builder_fn).to_string()),
+                };
+            }
+        }
+
+        // Look for functions passed by name to combinators. Calls written
+        // inside closure arguments are already picked up by the scans above.
+        if options.expand_combinators {
+            for combinator_match in self.combinator.find_iter(body) {
+                for argument in call_arguments(&body[combinator_match.end()..]) {
+                    let argument = argument.trim();
+                    if self.fn_path.is_match(argument) {
+                        let path: Vec<&str> = argument.split("::").collect();
+                        point_free_call_names(&path, scope, &mut calls);
+                    }
+                }
+            }
+        }
+
+        // Paths through `crate`, `self`, `super`, an import or a declared
+        // module name their function outright. Of other path calls keep the
+        // last qualifier (`UserRequest::new(...)`), which is how calls
+        // through a type resolve; module paths (`rates::fetch(...)`) also
+        // record the bare name.
+        for qualified_captures in self.qualified.captures_iter(body) {
+            let called_function = &qualified_captures[2];
+            let mut path: Vec<&str> = qualified_captures[1].split("::").collect();
+            path.push(called_function);
+            if let Some(call_name) = scope.call_name(&path) {
+                calls.insert(call_name);
+                continue;
+            }
+            let qualifier = unraw(path[path.len() - 2]);
+            calls.insert(format!("{}::{}", qualifier, unraw(called_function)));
+            if is_value_name(qualifier) && !SKIPPED_CALLS.contains(&called_function) {
+                calls.insert(unraw(called_function).to_string());
+            }
+        }
+
+        // Look for invocations of project macros (name!(...)); std macros
+        // never resolve to a project definition
+        for macro_captures in self.macro_call.captures_iter(body) {
+            let macro_name = macro_captures.get(1).unwrap().as_str();
+            if !STD_MACROS.contains(&macro_name) {
+                calls.insert(format!("{}!", unraw(macro_name)));
+            }
+        }
+
+        calls
+    }
+}
+
+/// Standard library macros, skipped when recording macro invocations.
+const STD_MACROS: &[&str] = &[
+    "println",
+    "print",
+    "eprintln",
+    "eprint",
+    "format",
+    "format_args",
+    "write",
+    "writeln",
+    "vec",
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "panic",
+    "todo",
+    "unimplemented",
+    "unreachable",
+    "matches",
+    "dbg",
+    "concat",
+    "stringify",
+    "include",
+    "include_str",
+    "include_bytes",
+    "env",
+    "option_env",
+    "cfg",
+    "line",
+    "file",
+    "column",
+    "module_path",
+    "compile_error",
+    "thread_local",
+    "macro_rules",
+];
+
+/// The span of a `macro_rules!` definition.
+struct MacroSpan {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+/// Finds every `macro_rules!` definition and the extent of its body, which
+/// may be delimited by braces, parentheses or brackets.
+fn macro_definitions(content: &str) -> Vec<MacroSpan> {
+    let macro_regex =
+        Regex::new(&format!(r"(?m)^\s*macro_rules!\s*({})\s*([{{(\[])", IDENT)).unwrap();
+
+    let mut spans = Vec::new();
+    for captures in macro_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let open = captures.get(2).unwrap();
+        let close = match open.as_str() {
+            "{" => '}',
+            "(" => ')',
+            _ => ']',
+        };
+        let open_char = open.as_str().chars().next().unwrap();
+
+        let mut depth = 0;
+        let mut end = content.len();
+        for (i, c) in content[open.start()..].char_indices() {
+            if c == open_char {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    end = open.start() + i + 1;
+                    break;
+                }
+            }
+        }
+        // Parenthesized and bracketed definitions end with `;`
+        if content[end..].starts_with(';') {
+            end += 1;
+        }
+
+        spans.push(MacroSpan {
+            name: unraw(captures.get(1).unwrap().as_str()).to_string(),
+            start: definition_start(content, whole.start()),
+            end,
+        });
+    }
+
+    spans
+}
+
+/// A `static` initialized on first use: a `lazy_static!` entry, or a static
+/// of one of the `LAZY_TYPES` or `ONCE_TYPES`.
+struct LazyGlobal {
+    name: String,
+    start: usize,
+    /// Where the expression after `=` starts
+    initializer: usize,
+    end: usize,
+    /// Filled through `set` or `get_or_init` rather than by its initializer
+    set_later: bool,
+}
+
+/// Finds the lazily initialized globals defined in a file.
+fn lazy_globals(content: &str) -> Vec<LazyGlobal> {
+    let static_regex = Regex::new(&format!(
+        r"(?m)^\s*(pub(\([^)]*\))?\s+)?static\s+(ref\s+)?({})\s*:([^=;]*)=",
+        IDENT
+    ))
+    .unwrap();
+
+    let mut globals = Vec::new();
+    for captures in static_regex.captures_iter(content) {
+        let type_words: Vec<&str> = captures[5]
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .collect();
+        let set_later = type_words.iter().any(|word| ONCE_TYPES.contains(word));
+        // `static ref` is how `lazy_static!` entries are written
+        let lazy =
+            captures.get(3).is_some() || type_words.iter().any(|word| LAZY_TYPES.contains(word));
+        if !lazy && !set_later {
+            continue;
+        }
+        let whole = captures.get(0).unwrap();
+        globals.push(LazyGlobal {
+            name: unraw(&captures[4]).to_string(),
+            start: definition_start(content, whole.start()),
+            initializer: whole.end(),
+            end: statement_end(content, whole.end()),
+            set_later,
+        });
+    }
+    globals
+}
+
+/// The end of the statement starting at `start`: just past the first `;`
+/// outside brackets and string literals.
+fn statement_end(content: &str, start: usize) -> usize {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in content[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                depth -= 1;
+                // The end of an enclosing block without a `;`
+                if depth < 0 {
+                    return start + i;
+                }
+            }
+            ';' if depth == 0 => return start + i + 1,
+            _ => {}
+        }
+    }
+    content.len()
+}
+
+/// Whether a name is written like a global, in `SCREAMING_SNAKE_CASE`.
+pub fn is_global_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// The identifier a possibly raw name spells: `type` for `r#type`. Names
+/// are indexed and resolved in this form.
+pub fn unraw(name: &str) -> &str {
+    name.strip_prefix("r#").unwrap_or(name)
+}
+
+/// Whether a method name preceded by `before` is called on `self`.
+fn on_self(before: &str) -> bool {
+    before
+        .strip_suffix(".")
+        .and_then(|receiver| receiver.strip_suffix("self"))
+        .is_some_and(|rest| !rest.ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
+/// Whether an identifier is written like a function or module rather than
+/// a type: not starting with an uppercase letter, in any script.
+pub(super) fn is_value_name(name: &str) -> bool {
+    name.starts_with(|c: char| c == '_' || (c.is_alphabetic() && !c.is_uppercase()))
+}
+
+/// Splits the argument list starting right after an opening parenthesis into
+/// its top-level comma-separated arguments.
+pub fn call_arguments(after_paren: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut argument_start = 0;
+
+    for (i, c) in after_paren.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth > 0 => depth -= 1,
+            ')' => {
+                arguments.push(&after_paren[argument_start..i]);
+                break;
+            }
+            ',' if depth == 0 => {
+                arguments.push(&after_paren[argument_start..i]);
+                argument_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    arguments
+}
+
+/// Output of prost-build or tonic-build checked into the tree, known by the
+/// comments it starts with: prost-build's `// This file is @generated by
+/// prost-build.`, or another `@generated` marker on code using prost or
+/// tonic. Files that merely mention or derive `prost::Message` are written
+/// by hand.
+fn is_generated_proto(content: &str) -> bool {
+    let header: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .take_while(|line| {
+            line.is_empty()
+                || line.starts_with("//")
+                || line.starts_with("/*")
+                || line.starts_with('*')
+        })
+        .filter(|line| line.contains("@generated"))
+        .collect();
+    header.iter().any(|line| line.contains("prost-build"))
+        || (!header.is_empty() && (content.contains("prost") || content.contains("tonic")))
+}
+
+/// How a proc-macro entry point is invoked: `name!(...)` for `#[proc_macro]`,
+/// `#[name]` for `#[proc_macro_attribute]`, and `derive(Name)` for
+/// `#[proc_macro_derive(Name)]`.
+fn proc_macro_alias(attributes: &[String], function_name: &str) -> Option<String> {
+    for attribute in attributes {
+        if let Some(rest) = attribute.strip_prefix("#[proc_macro_derive(") {
+            let derive_name = rest.split([',', ')']).next()?.trim();
+            return Some(format!("derive({})", derive_name));
+        }
+        if attribute == "#[proc_macro_attribute]" {
+            return Some(format!("#[{}]", function_name));
+        }
+        if attribute == "#[proc_macro]" {
+            return Some(format!("{}!", function_name));
+        }
+    }
+    None
+}
+
+/// Checks a definition's attributes for `#[test]` or an async runtime's
+/// equivalent (`#[tokio::test]`, ...).
+fn has_test_attribute(attributes: &[String]) -> bool {
+    attributes.iter().any(|attribute| {
+        attribute == "#[test]" || attribute.starts_with("#[test(") || attribute.contains("::test")
+    })
+}
@@ -0,0 +1,232 @@
+use super::{
+    definition_start, doc_comment_above, find_body_end, free_key, line_number_at, FileItems,
+    Language,
+};
+use crate::{FunctionInfo, TypeInfo};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+pub fn extract(content: &str, path: &Path, module_path: &str) -> FileItems {
+    let mut function_info: HashMap<String, FunctionInfo> = HashMap::new();
+    let mut types: HashMap<String, TypeInfo> = HashMap::new();
+
+    // XCTest discovers `test...` methods in files of a test target
+    let in_test_file = path
+        .file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().ends_with("Tests"));
+
+    // Attributes (@objc, @MainActor, ...) and modifiers may precede `func`
+    let fn_regex = Regex::new(
+        r"(?m)^[ \t]*(?:@[A-Za-z_]+(?:\([^)]*\))?\s+)*(?:(?:public|private|fileprivate|internal|open|static|class|final|override|mutating|nonmutating|dynamic|required|convenience|nonisolated)\s+)*func\s+([A-Za-z_][A-Za-z0-9_]*)\s*(?:<[^>]*>)?\s*\(",
+    )
+    .unwrap();
+    let method_regex = Regex::new(r"\.([A-Za-z_][A-Za-z0-9_]*)\s*\(").unwrap();
+    let call_regex = Regex::new(r"[^A-Za-z0-9_\.]([A-Za-z_][A-Za-z0-9_]*)\s*\(").unwrap();
+
+    let type_regex = Regex::new(
+        r"(?m)^[ \t]*(?:(?:public|private|fileprivate|internal|open|final)\s+)*(class|struct|enum|protocol|extension|typealias|actor)\s+([A-Za-z_][A-Za-z0-9_]*)",
+    )
+    .unwrap();
+
+    // The bodies of types and extensions as (open brace, end, name), so
+    // methods are keyed by their type like Rust ones, and `Foo.update` and
+    // `Bar.update` in one file don't overwrite each other
+    let mut type_bodies = Vec::new();
+    for captures in type_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        if &captures[1] == "typealias" {
+            continue;
+        }
+        let Some(open) = content[whole.end()..].find('{').map(|i| whole.end() + i) else {
+            continue;
+        };
+        let end = find_body_end(content, whole.end(), Language::Swift);
+        type_bodies.push((open, end, captures[2].to_string()));
+    }
+
+    for captures in fn_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let function_name = captures.get(1).unwrap().as_str();
+
+        // Protocol requirements have no body to gather
+        if !has_body(content, whole.end() - 1) {
+            continue;
+        }
+
+        let def_start = definition_start(content, whole.start());
+        let line_number = line_number_at(content, def_start);
+        let def_end = find_body_end(content, whole.end() - 1, Language::Swift);
+        let fn_body = content[def_start..def_end].trim().to_string();
+
+        let mut calls = HashSet::new();
+
+        // Look for method calls (.method())
+        for method_captures in method_regex.captures_iter(&fn_body) {
+            let method_name = method_captures.get(1).unwrap().as_str();
+            if ![
+                "map",
+                "compactMap",
+                "flatMap",
+                "filter",
+                "reduce",
+                "forEach",
+                "sorted",
+                "joined",
+                "append",
+                "contains",
+                "first",
+                "last",
+                "removeAll",
+                "insert",
+                "dropFirst",
+                "prefix",
+                "suffix",
+                "async",
+                "sync",
+            ]
+            .contains(&method_name)
+            {
+                calls.insert(method_name.to_string());
+            }
+        }
+
+        // Look for free function calls, which is how Swift reaches C/Rust FFI
+        for call_captures in call_regex.captures_iter(&fn_body) {
+            let called_function = call_captures.get(1).unwrap().as_str();
+
+            if [
+                "if",
+                "guard",
+                "while",
+                "for",
+                "switch",
+                "return",
+                "func",
+                "init",
+                "super",
+                "self",
+                "print",
+                "debugPrint",
+                "let",
+                "var",
+                "case",
+                "catch",
+                "throw",
+                "try",
+                "await",
+                "defer",
+                "repeat",
+                "in",
+                "where",
+                "as",
+                "is",
+                "assert",
+                "precondition",
+                "fatalError",
+                "min",
+                "max",
+            ]
+            .contains(&called_function)
+            {
+                continue;
+            }
+
+            calls.insert(called_function.to_string());
+        }
+
+        // The innermost type around the function
+        let self_type = type_bodies
+            .iter()
+            .filter(|(open, end, _)| *open < whole.start() && whole.start() < *end)
+            .max_by_key(|(open, _, _)| *open)
+            .map(|(_, _, name)| name.clone());
+        // Overloads are kept as `name#2`, ...
+        let base_key = match &self_type {
+            Some(self_type) => format!("{}::{}", self_type, function_name),
+            None => function_name.to_string(),
+        };
+        function_info.insert(
+            free_key(&function_info, base_key),
+            FunctionInfo {
+                path: path.to_path_buf(),
+                language: Language::Swift,
+                module_path: module_path.to_string(),
+                definition: fn_body,
+                line_number,
+                calls,
+                doc: doc_comment_above(content, def_start),
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: None,
+                is_test: in_test_file && function_name.starts_with("test"),
+                self_type,
+            },
+        );
+    }
+
+    for captures in type_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let def_start = definition_start(content, whole.start());
+        // Value types and protocols describe the data model and are kept
+        // whole; classes would drag every method along, so only their
+        // declaration line is kept
+        let def_end = match captures.get(1).unwrap().as_str() {
+            "struct" | "enum" | "protocol" => find_body_end(content, whole.end(), Language::Swift),
+            "typealias" => content[whole.end()..]
+                .find('\n')
+                .map_or(content.len(), |i| whole.end() + i),
+            _ => content[whole.end()..]
+                .find(['{', '\n'])
+                .map_or(content.len(), |i| whole.end() + i),
+        };
+
+        types.insert(
+            captures.get(2).unwrap().as_str().to_string(),
+            TypeInfo {
+                path: path.to_path_buf(),
+                language: Language::Swift,
+                definition: content[def_start..def_end].trim().to_string(),
+                line_number: line_number_at(content, def_start),
+                doc: doc_comment_above(content, def_start),
+            },
+        );
+    }
+
+    FileItems {
+        functions: function_info,
+        types,
+        impls: Vec::new(),
+        aliases: Vec::new(),
+        generated: false,
+    }
+}
+
+/// Checks whether the declaration whose parameter list opens at `open_paren`
+/// is followed by a body, rather than being a protocol requirement.
+fn has_body(content: &str, open_paren: usize) -> bool {
+    let mut depth = 0;
+    for (i, c) in content[open_paren..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '{' if depth == 0 => return true,
+            '}' | ';' if depth == 0 => return false,
+            '\n' if depth == 0 => {
+                // Signatures may continue on the next line with effects,
+                // a return type, a where clause, or the opening brace itself
+                let next_line = content[open_paren + i + 1..].trim_start();
+                if !["->", "throws", "rethrows", "async", "where", "{"]
+                    .iter()
+                    .any(|prefix| next_line.starts_with(prefix))
+                {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
@@ -0,0 +1,475 @@
+use super::{
+    definition_start, doc_comment_above, find_body_end, free_key, line_number_at, FileItems,
+    Language,
+};
+use crate::{FunctionInfo, TypeInfo};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const KEYWORDS: &[&str] = &[
+    "if",
+    "else",
+    "for",
+    "while",
+    "do",
+    "switch",
+    "case",
+    "return",
+    "function",
+    "typeof",
+    "instanceof",
+    "catch",
+    "await",
+    "yield",
+    "void",
+    "delete",
+    "in",
+    "of",
+    "new",
+    "super",
+    "import",
+    "require",
+    "constructor",
+];
+
+/// Globals and test-framework helpers, which are never project functions.
+const SKIPPED_FUNCTIONS: &[&str] = &[
+    "setTimeout",
+    "setInterval",
+    "clearTimeout",
+    "clearInterval",
+    "parseInt",
+    "parseFloat",
+    "isNaN",
+    "String",
+    "Number",
+    "Boolean",
+    "Array",
+    "Object",
+    "Symbol",
+    "BigInt",
+    "Error",
+    "describe",
+    "it",
+    "test",
+    "expect",
+    "beforeEach",
+    "afterEach",
+];
+
+const SKIPPED_METHODS: &[&str] = &[
+    "map",
+    "flatMap",
+    "filter",
+    "reduce",
+    "forEach",
+    "find",
+    "findIndex",
+    "some",
+    "every",
+    "includes",
+    "indexOf",
+    "push",
+    "pop",
+    "shift",
+    "unshift",
+    "slice",
+    "splice",
+    "concat",
+    "join",
+    "split",
+    "sort",
+    "keys",
+    "values",
+    "entries",
+    "then",
+    "catch",
+    "finally",
+    "toString",
+    "trim",
+    "replace",
+    "startsWith",
+    "endsWith",
+    "log",
+    "warn",
+    "error",
+    "stringify",
+    "parse",
+    "resolve",
+    "reject",
+    "all",
+    "call",
+    "apply",
+    "bind",
+    "preventDefault",
+    "stopPropagation",
+];
+
+/// Extracts function declarations, functions and arrow functions assigned
+/// to variables, and class methods (including arrow function properties).
+/// Methods are keyed `Class::method`, like Rust methods are by their type.
+pub fn extract(content: &str, path: &Path, module_path: &str, language: Language) -> FileItems {
+    let mut function_info: HashMap<String, FunctionInfo> = HashMap::new();
+    let mut types: HashMap<String, TypeInfo> = HashMap::new();
+
+    // Jest, Vitest and Mocha pick up `*.test.ts`, `*.spec.js` and files
+    // under `__tests__`
+    let in_test_file = path.file_stem().is_some_and(|stem| {
+        let stem = stem.to_string_lossy();
+        stem.ends_with(".test") || stem.ends_with(".spec")
+    }) || path.components().any(|c| c.as_os_str() == "__tests__");
+    // Components rendered as `<Component ... />` are calls too
+    let has_jsx = path
+        .extension()
+        .is_some_and(|extension| extension == "tsx" || extension == "jsx");
+
+    let fn_regex = Regex::new(
+        r"(?m)^[ \t]*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:async\s+)?function\b\s*\*?\s*([A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\s*\(",
+    )
+    .unwrap();
+    let variable_regex = Regex::new(
+        r"(?m)^[ \t]*(?:export\s+)?(?:const|let|var)\s+([A-Za-z_$][\w$]*)\s*(?::(?:[^=\n]|=>)*)?=[^=>]",
+    )
+    .unwrap();
+    let class_regex = Regex::new(
+        r"(?m)^[ \t]*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?class\s+([A-Za-z_$][\w$]*)",
+    )
+    .unwrap();
+    // Decorators (@Input(), @HostListener(...)) and modifiers may precede
+    // a method or property
+    let member_regex = Regex::new(
+        r"(?m)^[ \t]*(?:@[\w$.]+(?:\([^)]*\))?\s+)*(?:(?:public|private|protected|static|readonly|override|abstract|async|get|set)\s+)*\*?\s*(#?[A-Za-z_$][\w$]*)\s*[?!]?\s*(?:<[^>]*>)?\s*(\(|(?::(?:[^=;\n]|=>)*)?=[^=>])",
+    )
+    .unwrap();
+
+    // (start of the match, the `{` of its body or the start of the value
+    // assigned, whether it is a value, name, class)
+    let mut definitions: Vec<(usize, usize, bool, String, Option<String>)> = Vec::new();
+    for captures in fn_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        // Overloads and `declare function` have no body
+        let Some(body_start) = body_open(content, whole.end() - 1) else {
+            continue;
+        };
+        let name = captures.get(1).unwrap().as_str().to_string();
+        definitions.push((whole.start(), body_start, false, name, None));
+    }
+    for captures in variable_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let name = captures.get(1).unwrap().as_str().to_string();
+        definitions.push((whole.start(), whole.end() - 1, true, name, None));
+    }
+
+    for captures in class_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let class_name = captures.get(1).unwrap().as_str();
+        let Some(open) = content[whole.end()..].find('{').map(|i| whole.end() + i) else {
+            continue;
+        };
+        let class_end = find_body_end(content, open, Language::TypeScript);
+        let body = &content[open + 1..class_end.saturating_sub(1).max(open + 1)];
+
+        // Only members of the class itself, not code nested in them
+        let mut depth = 0i32;
+        let mut scanned = 0;
+        for member in member_regex.captures_iter(body) {
+            let whole = member.get(0).unwrap();
+            depth += brace_balance(&body[scanned..whole.start()]);
+            scanned = whole.start();
+            let name = member.get(1).unwrap().as_str();
+            if depth != 0 || (KEYWORDS.contains(&name) && name != "constructor") {
+                continue;
+            }
+            let start = open + 1 + whole.start();
+            let value_start = open + 1 + whole.end() - 1;
+            let is_value = member.get(2).unwrap().as_str() != "(";
+            let body_start = if is_value {
+                value_start
+            } else {
+                match body_open(content, value_start) {
+                    Some(body_start) => body_start,
+                    None => continue,
+                }
+            };
+            definitions.push((
+                start,
+                body_start,
+                is_value,
+                format!("{}::{}", class_name, name),
+                Some(class_name.to_string()),
+            ));
+        }
+
+        let def_start = definition_start(content, whole.start());
+        types.insert(
+            class_name.to_string(),
+            TypeInfo {
+                path: path.to_path_buf(),
+                language,
+                definition: content[def_start..open].trim().to_string(),
+                line_number: line_number_at(content, def_start),
+                doc: doc_comment_above(content, def_start),
+            },
+        );
+    }
+
+    let method_regex = Regex::new(r"(this\.)?\.?([A-Za-z_$][\w$]*)\s*(?:<[^>()]*>)?\s*\(").unwrap();
+    let jsx_regex = Regex::new(r"<([A-Z][\w$]*)[\s/>]").unwrap();
+
+    for (start, body_start, is_value, key, self_type) in definitions {
+        // Variables and properties count only when they hold a function
+        let def_end = if is_value {
+            match function_value_end(content, body_start) {
+                Some(end) => end,
+                None => continue,
+            }
+        } else {
+            find_body_end(content, body_start, Language::TypeScript)
+        };
+
+        let def_start = definition_start(content, start);
+        let line_number = line_number_at(content, def_start);
+        let fn_body = content[def_start..def_end].trim().to_string();
+        let simple_name = key.rsplit("::").next().unwrap_or(&key);
+
+        let mut calls = HashSet::new();
+        for call_captures in method_regex.captures_iter(&fn_body) {
+            let whole = call_captures.get(0).unwrap();
+            let name = call_captures.get(2).unwrap().as_str();
+            let before = fn_body[..whole.start()].trim_end();
+            if call_captures.get(1).is_some() {
+                calls.insert(format!("Self::{}", name));
+            } else if whole.as_str().starts_with('.') {
+                if !SKIPPED_METHODS.contains(&name) {
+                    calls.insert(name.to_string());
+                }
+            } else if !KEYWORDS.contains(&name)
+                && !SKIPPED_FUNCTIONS.contains(&name)
+                && name != simple_name
+                // Constructions, nested declarations and decorators
+                && !before.ends_with("new")
+                && !before.ends_with("function")
+                && !before.ends_with('@')
+            {
+                calls.insert(name.to_string());
+            }
+        }
+        if has_jsx {
+            for jsx_captures in jsx_regex.captures_iter(&fn_body) {
+                calls.insert(jsx_captures.get(1).unwrap().as_str().to_string());
+            }
+        }
+
+        // Getter and setter pairs, and nested functions sharing a name, are
+        // all kept, the later ones as `name#2`, ...
+        function_info.insert(
+            free_key(&function_info, key.clone()),
+            FunctionInfo {
+                path: path.to_path_buf(),
+                language,
+                module_path: module_path.to_string(),
+                definition: fn_body,
+                line_number,
+                calls,
+                doc: doc_comment_above(content, def_start),
+                owners: Vec::new(),
+                churn: None,
+                depth: None,
+                cfg: None,
+                is_test: in_test_file,
+                self_type,
+            },
+        );
+    }
+
+    let type_regex = Regex::new(
+        r"(?m)^[ \t]*(?:export\s+)?(?:declare\s+)?(interface|type|enum|const\s+enum)\s+([A-Za-z_$][\w$]*)",
+    )
+    .unwrap();
+
+    for captures in type_regex.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        let def_start = definition_start(content, whole.start());
+        let def_end = match captures.get(1).unwrap().as_str() {
+            "type" => match content[whole.end()..].find('=') {
+                Some(i) => expression_end(content, whole.end() + i + 1),
+                None => continue,
+            },
+            _ => find_body_end(content, whole.end(), Language::TypeScript),
+        };
+
+        types.insert(
+            captures.get(2).unwrap().as_str().to_string(),
+            TypeInfo {
+                path: path.to_path_buf(),
+                language,
+                definition: content[def_start..def_end].trim().to_string(),
+                line_number: line_number_at(content, def_start),
+                doc: doc_comment_above(content, def_start),
+            },
+        );
+    }
+
+    FileItems {
+        functions: function_info,
+        types,
+        impls: Vec::new(),
+        aliases: Vec::new(),
+        generated: false,
+    }
+}
+
+/// The `{` opening the body of the declaration whose parameter list opens
+/// at `open_paren`, past any return type annotation; `None` for overloads
+/// and declarations, which end without one.
+fn body_open(content: &str, open_paren: usize) -> Option<usize> {
+    let close = matching_close(content, open_paren)?;
+    let mut depth = 0i32;
+    for (i, c) in content[close + 1..].char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            '{' if depth <= 0 => return Some(close + 1 + i),
+            ';' | '}' if depth <= 0 => return None,
+            '\n' if depth <= 0 => {
+                // The return type or the brace may continue on the next line
+                let line = content[close + 1..close + 1 + i].trim_end();
+                let next_line = content[close + 2 + i..].trim_start();
+                let continues = line.is_empty() && next_line.starts_with(['{', ':'])
+                    || line.ends_with([':', '|', '&', ','])
+                    || next_line.starts_with(['{', '|', '&']);
+                if !continues {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The end of the function value starting at `value_start` (just after an
+/// `=`): an arrow function or a `function` expression. `None` when the
+/// value is anything else.
+fn function_value_end(content: &str, value_start: usize) -> Option<usize> {
+    let mut pos =
+        value_start + (content[value_start..].len() - content[value_start..].trim_start().len());
+    if let Some(rest) = content[pos..].strip_prefix("async") {
+        if rest.starts_with(char::is_whitespace) {
+            pos += "async".len();
+            pos += content[pos..].len() - content[pos..].trim_start().len();
+        }
+    }
+    let rest = &content[pos..];
+
+    if rest.starts_with("function") {
+        let open_paren = pos + rest.find('(')?;
+        return Some(find_body_end(
+            content,
+            body_open(content, open_paren)?,
+            Language::TypeScript,
+        ));
+    }
+
+    // A generic arrow function's type parameters
+    if rest.starts_with('<') {
+        pos += rest.find('(')?;
+    }
+    let arrow_search = if content[pos..].starts_with('(') {
+        matching_close(content, pos)? + 1
+    } else {
+        let parameter = content[pos..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .map_or(content.len(), |i| pos + i);
+        if parameter == pos {
+            return None;
+        }
+        parameter
+    };
+
+    // An optional return type annotation, then the arrow
+    let after = &content[arrow_search..];
+    let trimmed = after.trim_start();
+    let arrow = if trimmed.starts_with("=>") {
+        arrow_search + (after.len() - trimmed.len())
+    } else if trimmed.starts_with(':') {
+        let arrow = after.find("=>")?;
+        if after[..arrow].contains([';', '{', '=']) {
+            return None;
+        }
+        arrow_search + arrow
+    } else {
+        return None;
+    };
+
+    let body = arrow + 2;
+    let body = body + (content[body..].len() - content[body..].trim_start().len());
+    if content[body..].starts_with('{') {
+        Some(find_body_end(content, body, Language::TypeScript))
+    } else {
+        Some(expression_end(content, body))
+    }
+}
+
+/// The offset of the `)` matching the `(` at `open_paren`.
+fn matching_close(content: &str, open_paren: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in content[open_paren..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open_paren + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The end of an expression starting at `start`: the `;`, `,` or closing
+/// bracket ending it, or the end of its last line when the next line
+/// doesn't continue it (code without semicolons). Brackets and separators
+/// inside strings, template literals and comments don't count.
+fn expression_end(content: &str, start: usize) -> usize {
+    let mut depth = 0i32;
+    for (i, c) in super::code_chars(&content[start..], Language::TypeScript) {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                if depth == 0 {
+                    return start + i;
+                }
+                depth -= 1;
+            }
+            ';' | ',' if depth == 0 => return start + i,
+            '\n' if depth == 0 => {
+                let line = content[start..start + i].trim_end();
+                let next_line = content[start + i + 1..].trim_start();
+                let continues = line.is_empty()
+                    || line.ends_with(['=', '>', '(', '?', ':', '&', '|', '+', '-', '*', '/'])
+                    || next_line.starts_with(['.', '?', ':', '&', '|', '+', '-', '*', '/', '=']);
+                if !continues {
+                    return start + i;
+                }
+            }
+            _ => {}
+        }
+    }
+    content.len()
+}
+
+/// Net `{` minus `}` in `text`, outside strings, template literals and
+/// comments.
+fn brace_balance(text: &str) -> i32 {
+    super::code_chars(text, Language::TypeScript)
+        .map(|(_, c)| match c {
+            '{' => 1,
+            '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
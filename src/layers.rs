@@ -0,0 +1,80 @@
+//! `--layered-output dir/`: the gathered context split by call distance, so
+//! a model can be given the target first and deeper layers only on request.
+
+use crate::atomic;
+use crate::index::ProjectIndex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const LAYER_TITLES: [&str; 3] = ["target", "direct callees", "transitive callees"];
+
+/// Writes `layer0.md` (the first gathered function), `layer1.md` (what it
+/// calls directly) and `layer2.md` (everything else gathered), rendering each
+/// layer's functions with `render(layer, names)`. Returns each file with its
+/// function count.
+pub fn write<F>(
+    dir: &Path,
+    index: &ProjectIndex,
+    gathered_names: &[String],
+    render: F,
+) -> io::Result<Vec<(PathBuf, usize)>>
+where
+    F: Fn(usize, &[String]) -> String,
+{
+    fs::create_dir_all(dir)?;
+
+    let depths = depths_within(index, gathered_names);
+    let mut layers: [Vec<String>; 3] = Default::default();
+    for name in gathered_names {
+        let depth = depths.get(name).copied().unwrap_or(2);
+        layers[depth.min(2)].push(name.clone());
+    }
+
+    let mut written = Vec::new();
+    for (layer, names) in layers.iter().enumerate() {
+        let path = dir.join(format!("layer{}.md", layer));
+        let mut content = format!(
+            "# Layer {}: {} ({} functions)\n",
+            layer,
+            LAYER_TITLES[layer],
+            names.len()
+        );
+        if names.is_empty() {
+            content.push_str("\n(none)\n");
+        } else {
+            let rendered = render(layer, names);
+            if !rendered.starts_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(&rendered);
+        }
+        atomic::write(&path, content)?;
+        written.push((path, names.len()));
+    }
+    Ok(written)
+}
+
+/// Call distance of each gathered function from the first one, following
+/// only edges between gathered functions.
+pub fn depths_within(index: &ProjectIndex, gathered_names: &[String]) -> HashMap<String, usize> {
+    let gathered: HashSet<&String> = gathered_names.iter().collect();
+    let mut depths = HashMap::new();
+    let mut queue = VecDeque::new();
+    if let Some(target) = gathered_names.first() {
+        depths.insert(target.clone(), 0);
+        queue.push_back(target.clone());
+    }
+
+    while let Some(current) = queue.pop_front() {
+        let depth = depths[&current];
+        for called in index.function_calls.get(&current).into_iter().flatten() {
+            if gathered.contains(called) && !depths.contains_key(called) {
+                depths.insert(called.clone(), depth + 1);
+                queue.push_back(called.clone());
+            }
+        }
+    }
+    depths
+}
@@ -0,0 +1,75 @@
+//! Learned excludes: the functions a project's runs keep leaving out, with
+//! `--ban` or `--interactive-prune`, counted in
+//! `.gather-context/learned-excludes.json`. A name left out in
+//! [`THRESHOLD`] runs is suggested for `[calls] skip` in
+//! `.gather-context.toml`, and `--learned-excludes` skips it until then.
+//!
+//! Names are counted as `[calls] skip` matches them, by the last segment of
+//! the qualified name (`trace` for `src::log::Logger::trace`).
+
+use crate::{atomic, cache};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "learned-excludes.json";
+
+/// Runs that have to leave a function out before it counts as learned.
+pub const THRESHOLD: usize = 3;
+
+/// Where the counts of `project_root` are stored.
+pub fn path_for(project_root: &Path) -> PathBuf {
+    project_root.join(cache::DIR).join(FILE_NAME)
+}
+
+/// The number of runs that left out each name; empty when nothing was
+/// recorded or the file can't be read.
+pub fn load(project_root: &Path) -> BTreeMap<String, usize> {
+    fs::read_to_string(path_for(project_root))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// The names left out in at least [`THRESHOLD`] runs.
+pub fn learned(project_root: &Path) -> Vec<String> {
+    load(project_root)
+        .into_iter()
+        .filter(|(_, runs)| *runs >= THRESHOLD)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Counts one more run leaving out each of `names` and stores the counts.
+/// Returns those of `names` now left out in at least [`THRESHOLD`] runs,
+/// with their counts.
+pub fn record(project_root: &Path, names: &[String]) -> io::Result<Vec<(String, usize)>> {
+    let mut names: Vec<&str> = names.iter().map(|name| simple_name(name)).collect();
+    names.sort_unstable();
+    names.dedup();
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut counts = load(project_root);
+    let mut learned = Vec::new();
+    for name in names {
+        let runs = counts.entry(name.to_string()).or_default();
+        *runs += 1;
+        if *runs >= THRESHOLD {
+            learned.push((name.to_string(), *runs));
+        }
+    }
+
+    let path = path_for(project_root);
+    fs::create_dir_all(path.parent().unwrap())?;
+    let json = serde_json::to_string_pretty(&counts).map_err(io::Error::other)?;
+    atomic::write(&path, json + "\n")?;
+    Ok(learned)
+}
+
+/// The name `[calls] skip` matches a function by.
+pub fn simple_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
@@ -0,0 +1,303 @@
+//! Extracts function call trees from Rust (and Swift, Objective-C, TypeScript,
+//! JavaScript, Go and C#) projects as context for reading, reviewing or
+//! prompting.
+//!
+//! The `gather-context` binary is built on this library, which gives tools
+//! embedding it the index and the core of a gather: a target's call tree,
+//! within a token budget or a depth if given, rendered plainly or under a
+//! prompt preset. The sections the CLI adds after the code (types, aliases,
+//! stubs, dependency signatures, questions, ...) and its other flags are
+//! not part of it.
+//!
+//!
+//! ```no_run
+//! use gather_context::{ContextGatherer, GatherParams, IndexOptions, ProjectIndex, Tokenizer};
+//! use std::path::Path;
+//!
+//! let index = ProjectIndex::build(Path::new("./my-project"), &IndexOptions::default())?;
+//! let tokenizer = Tokenizer::Chars;
+//! let gatherer = ContextGatherer::new(&index, &tokenizer);
+//! if let Some(result) = gatherer.gather(&GatherParams::function("process_queue")) {
+//!     print!("{}", result.output);
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+#[cfg(not(any(feature = "syn-backend", feature = "regex-backend")))]
+compile_error!("enable at least one of the `syn-backend` and `regex-backend` features");
+
+// The modules the binary, the benchmarks and the tests build on; they
+// aren't a stable API
+#[doc(hidden)]
+pub mod aliases;
+#[doc(hidden)]
+pub mod api;
+#[doc(hidden)]
+pub mod atomic;
+#[doc(hidden)]
+pub mod budget;
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod cargo;
+#[doc(hidden)]
+pub mod churn;
+#[doc(hidden)]
+pub mod compose;
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod conversions;
+#[doc(hidden)]
+pub mod dataset;
+#[doc(hidden)]
+pub mod deps;
+#[doc(hidden)]
+pub mod explain;
+#[doc(hidden)]
+pub mod fuzz;
+#[doc(hidden)]
+pub mod gatherer;
+#[doc(hidden)]
+pub mod glossary;
+#[doc(hidden)]
+pub mod graph;
+#[doc(hidden)]
+pub mod index;
+#[doc(hidden)]
+pub mod issue;
+#[doc(hidden)]
+pub mod lang;
+#[doc(hidden)]
+pub mod layers;
+#[doc(hidden)]
+pub mod learned;
+#[doc(hidden)]
+pub mod lock;
+#[doc(hidden)]
+pub mod markdown;
+#[doc(hidden)]
+pub mod module_docs;
+#[doc(hidden)]
+pub mod outline;
+#[doc(hidden)]
+pub mod panics;
+#[cfg(feature = "syn-backend")]
+#[doc(hidden)]
+pub mod parser;
+#[doc(hidden)]
+pub mod prompt;
+#[doc(hidden)]
+pub mod prune;
+#[doc(hidden)]
+pub mod questions;
+#[doc(hidden)]
+pub mod receiver;
+#[doc(hidden)]
+pub mod rpc;
+#[doc(hidden)]
+pub mod search;
+#[doc(hidden)]
+pub mod seed;
+#[doc(hidden)]
+pub mod serialization;
+#[doc(hidden)]
+pub mod sidecar;
+#[doc(hidden)]
+pub mod stats;
+#[doc(hidden)]
+pub mod stubs;
+#[doc(hidden)]
+pub mod summary;
+#[doc(hidden)]
+pub mod tags;
+#[doc(hidden)]
+pub mod trim;
+#[doc(hidden)]
+pub mod types;
+#[doc(hidden)]
+pub mod usage;
+#[doc(hidden)]
+pub mod validate;
+#[doc(hidden)]
+pub mod wrappers;
+
+mod codeowners;
+mod expand;
+mod license;
+mod operators;
+mod overrides;
+
+pub use budget::{Strategy, Tokenizer};
+pub use gatherer::{ContextGatherer, GatherParams, GatherResult, GatheredFunction};
+pub use index::{CallGraph, FunctionMatch, IndexOptions, ProjectIndex, Resolution};
+pub use lang::{ExtractOptions, Language};
+pub use prompt::PromptPreset;
+
+use module_docs::ModuleDoc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// A function (or macro template) definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionInfo {
+    pub path: PathBuf,
+    pub language: Language,
+    pub module_path: String,
+    /// Full source text, from the signature to the closing brace
+    pub definition: String,
+    pub line_number: usize,
+    /// Unresolved call names found in the body
+    pub calls: HashSet<String>,
+    pub doc: Option<String>,
+    /// CODEOWNERS owners, when ownership annotation is enabled
+    pub owners: Vec<String>,
+    /// Commits that touched the function within the `--churn` window, when
+    /// churn annotation is enabled
+    pub churn: Option<usize>,
+    /// Calls from the gather's target, recorded with `--outline` to label
+    /// each function; never stored with the index
+    #[serde(skip)]
+    pub depth: Option<usize>,
+    /// The `cfg(...)` condition the function is compiled under, recorded
+    /// with `--all-variants` to label each variant
+    pub cfg: Option<String>,
+    pub is_test: bool,
+    /// Simple name of the type whose `impl` block (or of the trait whose
+    /// definition) contains the function, for methods
+    pub self_type: Option<String>,
+}
+
+/// An `impl` block, trait or inherent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImplInfo {
+    pub path: PathBuf,
+    /// Simple name of the implementing type
+    pub self_type: String,
+    /// Simple name of the implemented trait, if any
+    pub trait_name: Option<String>,
+    pub definition: String,
+    pub line_number: usize,
+    pub end_line: usize,
+}
+
+/// A struct, enum, union, trait or type alias definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeInfo {
+    pub path: PathBuf,
+    pub language: Language,
+    /// Source text including attributes such as derives
+    pub definition: String,
+    pub line_number: usize,
+    pub doc: Option<String>,
+}
+
+/// Renders functions in the standard output format.
+pub fn render_output(gathered: &[&FunctionInfo]) -> String {
+    render_with_module_docs(gathered, &HashMap::new())
+}
+
+/// Renders the gathered functions, introducing each module that has
+/// documentation with that document.
+#[doc(hidden)]
+pub fn render_with_module_docs(
+    gathered: &[&FunctionInfo],
+    module_docs: &HashMap<String, ModuleDoc>,
+) -> String {
+    let mut documented = HashSet::new();
+    let mut module_doc = |function_info: &FunctionInfo| -> String {
+        match module_docs.get(&function_info.module_path) {
+            Some(doc) if documented.insert(function_info.module_path.clone()) => format!(
+                "\n=== {} (module {}) ===\n{}\n",
+                doc.path.to_string_lossy(),
+                function_info.module_path,
+                doc.text
+            ),
+            _ => String::new(),
+        }
+    };
+
+    let mut languages: Vec<Language> = Vec::new();
+    for function_info in gathered {
+        if !languages.contains(&function_info.language) {
+            languages.push(function_info.language);
+        }
+    }
+
+    let mut output = String::new();
+
+    // Single-language output keeps the plain format
+    if languages.len() <= 1 {
+        for function_info in gathered {
+            output.push_str(&module_doc(function_info));
+            output.push_str(&section_header(function_info));
+            output.push_str(&function_info.definition);
+            output.push_str("\n\n");
+        }
+        return output;
+    }
+
+    // Mixed-language output is grouped into one fenced section per language,
+    // in order of first appearance during traversal
+    for language in languages {
+        let functions: Vec<&&FunctionInfo> =
+            gathered.iter().filter(|f| f.language == language).collect();
+        let line_count: usize = functions.iter().map(|f| f.definition.lines().count()).sum();
+
+        output.push_str(&format!(
+            "\n##### {} ({} functions, {} lines) #####\n",
+            language.name(),
+            functions.len(),
+            line_count
+        ));
+
+        for function_info in functions {
+            let fence = code_fence(&function_info.definition);
+            output.push_str(&module_doc(function_info));
+            output.push_str(&section_header(function_info));
+            output.push_str(&format!("{}{}\n", fence, language.fence()));
+            output.push_str(&function_info.definition);
+            output.push_str(&format!("\n{}\n", fence));
+        }
+    }
+
+    output
+}
+
+/// The `=== path ===` line introducing a function, with its cfg condition,
+/// owners and churn when those annotations are enabled.
+fn section_header(function_info: &FunctionInfo) -> String {
+    format!(
+        "\n=== {}{} ===\n",
+        function_info.path.to_string_lossy(),
+        annotations(function_info)
+    )
+}
+
+/// A function's depth, cfg condition, owners and churn, each with a leading
+/// space, when those annotations are enabled.
+pub(crate) fn annotations(function_info: &FunctionInfo) -> String {
+    let mut text = String::new();
+    if let Some(depth) = function_info.depth {
+        text.push_str(&format!(" (depth {})", depth));
+    }
+    if let Some(condition) = &function_info.cfg {
+        text.push_str(&format!(" #[{}]", condition));
+    }
+    if !function_info.owners.is_empty() {
+        text.push_str(&format!(" [{}]", function_info.owners.join(" ")));
+    }
+    if let Some(commits) = function_info.churn {
+        let plural = if commits == 1 { "" } else { "s" };
+        text.push_str(&format!(" ({} commit{})", commits, plural));
+    }
+    text
+}
+
+/// A Markdown code fence longer than any run of backticks in `text` (doc
+/// comments carry fenced examples of their own).
+pub fn code_fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
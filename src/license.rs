@@ -0,0 +1,223 @@
+//! `--license-allow`: the license each source file is under, for keeping
+//! vendored or mixed-license code whose license forbids sharing out of
+//! what gets sent to a model.
+//!
+//! A file's license is its `SPDX-License-Identifier` header, or else that of
+//! the nearest directory declaring one, up to the project root: the
+//! `license` of its `Cargo.toml` package or `package.json`, or its
+//! `LICENSE`/`COPYING` files, recognized by their text. Several license
+//! files in one directory (`LICENSE-MIT`, `LICENSE-APACHE`) are a choice
+//! between them.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The identifier given to license text that isn't recognized, which is
+/// only allowed when listed like any other.
+pub const UNRECOGNIZED: &str = "LicenseRef-Unrecognized";
+
+/// Phrases identifying common license texts, most specific first.
+const TEXTS: &[(&str, &[&str])] = &[
+    ("AGPL-3.0", &["GNU AFFERO GENERAL PUBLIC LICENSE"]),
+    (
+        "LGPL-2.1",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 2.1"],
+    ),
+    ("LGPL-3.0", &["GNU LESSER GENERAL PUBLIC LICENSE"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2,"]),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE"]),
+    ("MPL-2.0", &["Mozilla Public License", "2.0"]),
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    ("BSL-1.0", &["Boost Software License"]),
+    (
+        "Unlicense",
+        &["free and unencumbered software released into the public domain"],
+    ),
+    (
+        "ISC",
+        &["Permission to use, copy, modify, and/or distribute this software"],
+    ),
+    ("MIT", &["Permission is hereby granted, free of charge"]),
+    (
+        "BSD-3-Clause",
+        &[
+            "Redistribution and use in source and binary forms",
+            "Neither the name",
+        ],
+    ),
+    (
+        "BSD-2-Clause",
+        &["Redistribution and use in source and binary forms"],
+    ),
+    ("Zlib", &["This software is provided 'as-is'"]),
+];
+
+/// Finds the licenses of files in one project, remembering each
+/// directory's.
+pub struct Licenses {
+    project_root: PathBuf,
+    directories: HashMap<PathBuf, Option<String>>,
+}
+
+impl Licenses {
+    pub fn new(project_root: &Path) -> Licenses {
+        Licenses {
+            project_root: project_root.to_path_buf(),
+            directories: HashMap::new(),
+        }
+    }
+
+    /// The SPDX license expression `path` is under, or `None` when neither
+    /// the file nor any directory up to the project root declares one.
+    pub fn license_of(&mut self, path: &Path) -> Option<String> {
+        if let Some(header) = spdx_header(path) {
+            return Some(header);
+        }
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            if let Some(license) = self.directory_license(current) {
+                return Some(license);
+            }
+            if current == self.project_root || !current.starts_with(&self.project_root) {
+                break;
+            }
+            dir = current.parent();
+        }
+        None
+    }
+
+    fn directory_license(&mut self, dir: &Path) -> Option<String> {
+        if let Some(license) = self.directories.get(dir) {
+            return license.clone();
+        }
+        let license = declared_license(dir).or_else(|| license_files(dir));
+        self.directories.insert(dir.to_path_buf(), license.clone());
+        license
+    }
+}
+
+/// The `SPDX-License-Identifier` in the first lines of a source file.
+fn spdx_header(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    content.lines().take(20).find_map(|line| {
+        let (_, expression) = line.split_once("SPDX-License-Identifier:")?;
+        let expression = expression.trim().trim_end_matches("*/").trim();
+        (!expression.is_empty()).then(|| expression.to_string())
+    })
+}
+
+/// The `license` field of the package manifest in `dir`.
+fn declared_license(dir: &Path) -> Option<String> {
+    if let Ok(content) = fs::read_to_string(dir.join("Cargo.toml")) {
+        let manifest: toml::Table = content.parse().ok()?;
+        let package_license = manifest
+            .get("package")
+            .and_then(|package| package.get("license"));
+        let workspace_license = manifest
+            .get("workspace")
+            .and_then(|workspace| workspace.get("package"))
+            .and_then(|package| package.get("license"));
+        // `license.workspace = true` is a table, not a license
+        return package_license
+            .and_then(toml::Value::as_str)
+            .or_else(|| workspace_license.and_then(toml::Value::as_str))
+            .map(str::to_string);
+    }
+    let content = fs::read_to_string(dir.join("package.json")).ok()?;
+    let manifest: serde_json::Value = serde_json::from_str(&content).ok()?;
+    manifest["license"].as_str().map(str::to_string)
+}
+
+/// The licenses of the `LICENSE*`, `LICENCE*` and `COPYING*` files in `dir`,
+/// as a choice between them.
+fn license_files(dir: &Path) -> Option<String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_uppercase())
+                .unwrap_or_default();
+            path.is_file()
+                && ["LICENSE", "LICENCE", "COPYING"]
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+        })
+        .collect();
+    paths.sort();
+
+    let mut licenses: Vec<&str> = Vec::new();
+    for path in paths {
+        let text = fs::read_to_string(&path).unwrap_or_default();
+        let license = TEXTS
+            .iter()
+            .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+            .map_or(UNRECOGNIZED, |(license, _)| license);
+        if !licenses.contains(&license) {
+            licenses.push(license);
+        }
+    }
+    (!licenses.is_empty()).then(|| licenses.join(" OR "))
+}
+
+/// Whether the SPDX `expression` (`MIT OR Apache-2.0`, `(MIT AND Zlib)`,
+/// Cargo's older `MIT/Apache-2.0`) is satisfied by the `allowed` licenses:
+/// one side of each `OR` and both sides of each `AND`. Exceptions after
+/// `WITH` only ever add permissions, so they are ignored.
+pub fn allows(expression: &str, allowed: &[String]) -> bool {
+    let spaced = expression
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace('/', " OR ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let mut position = 0;
+    let permitted = any_of(&tokens, &mut position, allowed);
+    permitted && position == tokens.len()
+}
+
+/// `all_of (OR all_of)*`
+fn any_of(tokens: &[&str], position: &mut usize, allowed: &[String]) -> bool {
+    let mut permitted = all_of(tokens, position, allowed);
+    while tokens.get(*position) == Some(&"OR") {
+        *position += 1;
+        permitted |= all_of(tokens, position, allowed);
+    }
+    permitted
+}
+
+/// `license (AND license)*`
+fn all_of(tokens: &[&str], position: &mut usize, allowed: &[String]) -> bool {
+    let mut permitted = license(tokens, position, allowed);
+    while tokens.get(*position) == Some(&"AND") {
+        *position += 1;
+        permitted &= license(tokens, position, allowed);
+    }
+    permitted
+}
+
+/// A license identifier, with an optional `WITH` exception, or a
+/// parenthesized expression.
+fn license(tokens: &[&str], position: &mut usize, allowed: &[String]) -> bool {
+    let Some(token) = tokens.get(*position) else {
+        return false;
+    };
+    *position += 1;
+    if *token == "(" {
+        let permitted = any_of(tokens, position, allowed);
+        if tokens.get(*position) != Some(&")") {
+            return false;
+        }
+        *position += 1;
+        return permitted;
+    }
+    if tokens.get(*position) == Some(&"WITH") {
+        *position += 2;
+    }
+    let token = token.trim_end_matches('+');
+    allowed
+        .iter()
+        .any(|license| license.eq_ignore_ascii_case(token))
+}
@@ -0,0 +1,174 @@
+//! `--lock gather.lock` and `replay gather.lock`: record exactly what a run
+//! gathered so the same context can be regenerated later, or the replay
+//! fails loudly because the code moved on.
+
+use crate::atomic;
+use crate::index::ProjectIndex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedFunction {
+    pub name: String,
+    pub path: String,
+    pub line: usize,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lock {
+    /// Arguments of the recorded run, without the program name and `--lock`
+    pub args: Vec<String>,
+    /// Absolute project root, so a replay works from any directory
+    pub project_root: String,
+    pub functions: Vec<LockedFunction>,
+}
+
+impl Lock {
+    /// Captures the gathered functions in output order with their hashes.
+    pub fn record(
+        args: Vec<String>,
+        project_root: &Path,
+        index: &ProjectIndex,
+        gathered_names: &[String],
+    ) -> Lock {
+        let functions = gathered_names
+            .iter()
+            .map(|name| {
+                let info = &index.function_definitions[name];
+                LockedFunction {
+                    name: name.clone(),
+                    path: info.path.to_string_lossy().into_owned(),
+                    line: info.line_number,
+                    hash: content_hash(&info.definition),
+                }
+            })
+            .collect();
+
+        Lock {
+            args,
+            project_root: project_root
+                .canonicalize()
+                .unwrap_or_else(|_| project_root.to_path_buf())
+                .to_string_lossy()
+                .into_owned(),
+            functions,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let record = json!({
+            "version": 1,
+            "project_root": self.project_root,
+            "args": self.args,
+            "functions": self
+                .functions
+                .iter()
+                .map(|function| {
+                    json!({
+                        "name": function.name,
+                        "path": function.path,
+                        "line": function.line,
+                        "hash": function.hash,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        });
+        let mut text = serde_json::to_string_pretty(&record)?;
+        text.push('\n');
+        atomic::write(path, text)
+    }
+
+    pub fn load(path: &Path) -> Result<Lock, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let record: Value = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+
+        let string = |value: &Value, key: &str| -> Result<String, String> {
+            value[key]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("missing string field '{}'", key))
+        };
+
+        let args = record["args"]
+            .as_array()
+            .ok_or("missing array field 'args'")?
+            .iter()
+            .map(|arg| {
+                arg.as_str()
+                    .map(str::to_string)
+                    .ok_or("non-string argument")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let functions = record["functions"]
+            .as_array()
+            .ok_or("missing array field 'functions'")?
+            .iter()
+            .map(|function| {
+                Ok(LockedFunction {
+                    name: string(function, "name")?,
+                    path: string(function, "path")?,
+                    line: function["line"].as_u64().unwrap_or(0) as usize,
+                    hash: string(function, "hash")?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Lock {
+            args,
+            project_root: string(&record, "project_root")?,
+            functions,
+        })
+    }
+
+    /// Describes every way `current` differs from this recording; empty when
+    /// the replay reproduces the same context.
+    pub fn changes(&self, current: &Lock) -> Vec<String> {
+        let current_by_name: HashMap<&str, &LockedFunction> = current
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function))
+            .collect();
+        let recorded_by_name: HashMap<&str, &LockedFunction> = self
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function))
+            .collect();
+
+        let mut changes = Vec::new();
+        for function in &self.functions {
+            match current_by_name.get(function.name.as_str()) {
+                None => changes.push(format!("removed: {}", function.name)),
+                Some(now) if now.hash != function.hash => changes.push(format!(
+                    "changed: {} ({}:{})",
+                    function.name, now.path, now.line
+                )),
+                Some(_) => {}
+            }
+        }
+        for function in &current.functions {
+            if !recorded_by_name.contains_key(function.name.as_str()) {
+                changes.push(format!(
+                    "added: {} ({}:{})",
+                    function.name, function.path, function.line
+                ));
+            }
+        }
+        changes
+    }
+}
+
+/// FNV-1a over the definition text, stable across platforms and Rust
+/// versions unlike `DefaultHasher`.
+pub fn content_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
@@ -1,436 +1,1656 @@
+mod cli;
+
+use clap::Parser;
+use cli::{Cli, Command, IndexFlags, OutputArgs, OutputFormat, TraversalArgs};
+use gather_context::budget::{self, Strategy, Tokenizer};
+use gather_context::config::{self, Config};
+use gather_context::graph;
+use gather_context::index::{self, IndexOptions, ProjectIndex};
+use gather_context::lang::{ExtractOptions, Language, RustBackend};
+use gather_context::lock::Lock;
+use gather_context::module_docs;
+use gather_context::prompt::{self, PromptPreset};
+use gather_context::seed::{self, Seed};
+use gather_context::{
+    aliases, api, atomic, cache, cargo, churn, compose, conversions, dataset, deps, explain, fuzz,
+    glossary, issue, layers, learned, markdown, outline, panics, prune, questions, receiver, rpc,
+    search, serialization, sidecar, stats, stubs, summary, tags, trim, types, usage, validate,
+    wrappers,
+};
+use gather_context::{
+    render_with_module_docs, FunctionInfo, GatherResult, GatheredFunction, TypeInfo,
+};
+use notify::{EventKind, RecursiveMode, Watcher};
 use regex::Regex;
-use std::collections::{HashMap, HashSet, VecDeque};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
 use std::process;
-use walkdir::WalkDir;
+use std::sync::mpsc;
+use std::time::Duration;
+
+fn main() -> io::Result<()> {
+    let mut args: Vec<String> = env::args().collect();
+
+    // As `cargo gather-context`, the project root is the current crate
+    if env::var_os("GATHER_CONTEXT_FROM_CARGO").is_some() {
+        insert_cargo_project_root(&mut args);
+    }
+    let args = cli::upgrade_legacy_args(args);
+
+    // What a `--lock` records, so a replay parses the same command
+    let mut invocation = args[1..].to_vec();
+    take_flag_value(&mut invocation, "--lock");
 
-#[allow(dead_code)]
-#[derive(Debug, Clone)]
-struct FunctionInfo {
-    path: PathBuf,
-    module_path: String,
-    definition: String,
-    line_number: usize,
-    calls: HashSet<String>,
+    let cli = Cli::parse_from(&args);
+    let interactive = !cli.non_interactive && io::stdin().is_terminal();
+    match cli.command {
+        Command::Gather(args) => run_gather(Gather::new(args), invocation, None, interactive),
+        Command::Callers(args) => run_gather(
+            Gather {
+                callers: true,
+                ..Gather::new(args)
+            },
+            invocation,
+            None,
+            interactive,
+        ),
+        Command::Explain(args) => run_gather(
+            Gather {
+                explained: Some(args.why),
+                ..Gather::new(args.gather)
+            },
+            invocation,
+            None,
+            interactive,
+        ),
+        Command::FromIssue(args) => run_gather(
+            Gather {
+                project_root: args.project_root,
+                functions: Vec::new(),
+                locations: Vec::new(),
+                module: None,
+                callers: false,
+                explained: None,
+                issue_source: Some(args.source),
+                traversal: args.traversal,
+                output: args.output,
+                index: args.index,
+            },
+            invocation,
+            None,
+            interactive,
+        ),
+        Command::Replay(args) => run_replay(&args.lock_file, interactive),
+        Command::Index(args) => run_index(args),
+        Command::List(args) => run_list(args),
+        Command::Stats(args) => run_stats(args),
+        Command::Search(args) => run_search(args),
+        Command::Compare(args) => run_compare(args, interactive),
+        Command::Impact(args) => run_impact(args, interactive),
+        Command::FuzzPrep(args) => run_fuzz_prep(args, interactive),
+        Command::Dataset(args) => run_dataset(args),
+        Command::Api(args) => run_api(args),
+        Command::Validate(args) => run_validate(args),
+        Command::Compose(args) => run_compose(args),
+        Command::ChangedSince(args) => run_changed_since(args),
+        Command::Rpc(args) => {
+            // Editor plugins keep the indexes alive and send requests over stdio
+            let mut projects: Vec<(String, PathBuf)> = Vec::new();
+            for spec in &args.project_roots {
+                // `NAME=PATH`, unless the `=` is part of the path
+                let (name, path) = match spec.split_once('=') {
+                    Some((name, path)) if !name.is_empty() && !name.contains(['/', '\\']) => {
+                        (Some(name.to_string()), path)
+                    }
+                    _ => (None, spec.as_str()),
+                };
+                let project_root = resolve_root_or_exit(Path::new(path));
+                let project_root = project_root.canonicalize().unwrap_or(project_root);
+                let name = name.unwrap_or_else(|| rpc::project_name(&project_root));
+                if projects.iter().any(|(other, _)| *other == name) {
+                    eprintln!("Two projects are named '{}'; name one with NAME=PATH", name);
+                    process::exit(1);
+                }
+                projects.push((name, project_root));
+            }
+            let tokenizer = load_tokenizer(&args.tokenizer, args.tokenizer_file.as_deref());
+            let flags = args.index;
+            let build: rpc::IndexBuilder = Box::new(move |project_root: &Path| {
+                build_index(project_root, &index_options(&flags, project_root))
+            });
+            rpc::serve(projects, build, tokenizer)
+        }
+    }
 }
 
-fn main() -> io::Result<()> {
-    let args: Vec<String> = env::args().collect();
+/// A gather to run, from whichever subcommand asked for it.
+#[derive(Clone)]
+struct Gather {
+    project_root: PathBuf,
+    /// Functions to start from, merged into one tree
+    functions: Vec<String>,
+    /// Files and lines whose enclosing functions are targets too
+    locations: Vec<(String, usize)>,
+    module: Option<String>,
+    /// Walk the graph towards callers instead of callees
+    callers: bool,
+    /// Report on this function instead of writing the output
+    explained: Option<String>,
+    /// Seed the gather with the functions this issue mentions
+    issue_source: Option<String>,
+    traversal: TraversalArgs,
+    output: OutputArgs,
+    index: IndexFlags,
+}
 
-    if args.len() > 1 && (args[1] == "-h" || args[1] == "--help") {
-        print_help();
-        process::exit(0);
+impl Gather {
+    fn new(args: cli::GatherArgs) -> Gather {
+        Gather {
+            project_root: args.project_root,
+            functions: args.function.into_iter().chain(args.functions).collect(),
+            locations: args.at,
+            module: args.module,
+            callers: false,
+            explained: None,
+            issue_source: None,
+            traversal: args.traversal,
+            output: args.output,
+            index: args.index,
+        }
     }
+}
 
-    if args.len() < 3 {
-        eprintln!(
-            "Usage: {} <project_root> <function_name> [preferred_module] [output_file]",
-            args[0]
-        );
+/// `replay <lock_file>`: reruns the recorded arguments and checks the result
+/// against them.
+fn run_replay(lock_file: &Path, interactive: bool) -> io::Result<()> {
+    let lock = Lock::load(lock_file).unwrap_or_else(|err| {
+        eprintln!("Could not read {}: {}", lock_file.display(), err);
         process::exit(1);
-    }
+    });
+    let args = cli::upgrade_legacy_args(
+        std::iter::once("gather-context".to_string())
+            .chain(lock.args.iter().cloned())
+            .collect(),
+    );
+    let invocation = args[1..].to_vec();
 
-    let project_root = Path::new(&args[1]);
-    let target_function = &args[2];
-    let preferred_module = if args.len() > 3 { Some(&args[3]) } else { None };
-    let output_file = if args.len() > 4 {
-        Some(PathBuf::from(&args[4]))
-    } else if args.len() > 3 && !args[3].contains('/') && !args[3].contains('\\') {
-        Some(PathBuf::from(&args[3]))
-    } else {
-        None
+    let mut gather = match Cli::parse_from(&args).command {
+        Command::Gather(args) => Gather::new(args),
+        Command::Callers(args) => Gather {
+            callers: true,
+            ..Gather::new(args)
+        },
+        Command::FromIssue(args) => Gather {
+            project_root: args.project_root,
+            functions: Vec::new(),
+            locations: Vec::new(),
+            module: None,
+            callers: false,
+            explained: None,
+            issue_source: Some(args.source),
+            traversal: args.traversal,
+            output: args.output,
+            index: args.index,
+        },
+        _ => {
+            eprintln!("{} does not record a gather", lock_file.display());
+            process::exit(1);
+        }
     };
+    // Recorded as an absolute path, so the replay works from any directory
+    gather.project_root = PathBuf::from(&lock.project_root);
+    gather.output.lock = None;
+    run_gather(gather, invocation, Some(lock), interactive)
+}
 
-    // Collect all Rust files in the project
-    let rust_files = collect_rust_files(project_root)?;
-    eprintln!("Found {} Rust files in project", rust_files.len());
-
-    // Build function definitions map with fully qualified names
-    let mut function_definitions: HashMap<String, FunctionInfo> = HashMap::new();
-    let mut module_functions: HashMap<String, Vec<(String, String)>> = HashMap::new();
-
-    for path in &rust_files {
-        let module_path = extract_module_path(path, project_root);
-        let (functions, _) = process_file(path, &module_path)?;
-
-        for (name, info) in functions {
-            // Store with fully qualified name (module::function)
-            let qualified_name = format!("{}::{}", module_path, name);
-            function_definitions.insert(qualified_name.clone(), info);
-
-            // Store simple name to module mapping
-            module_functions
-                .entry(name.clone())
-                .or_insert_with(Vec::new)
-                .push((qualified_name, module_path.clone()));
-        }
-    }
-
-    // Build function call relationships
-    let mut function_calls: HashMap<String, HashSet<String>> = HashMap::new();
-    for (qualified_name, info) in &function_definitions {
-        let mut resolved_calls = HashSet::new();
-
-        for called_fn in &info.calls {
-            // Try to resolve the called function to its qualified name
-            if let Some(options) = module_functions.get(called_fn) {
-                if options.len() == 1 {
-                    // Only one function with this name
-                    resolved_calls.insert(options[0].0.clone());
-                } else {
-                    // Multiple functions with this name - prefer same module
-                    let caller_module = qualified_name.rsplit_once("::").map(|(m, _)| m);
-                    let same_module = options.iter().find(|(_, m)| caller_module == Some(m));
-
-                    if let Some((full_name, _)) = same_module {
-                        resolved_calls.insert(full_name.clone());
-                    } else {
-                        // Default to first one
-                        resolved_calls.insert(options[0].0.clone());
+fn run_gather(
+    mut gather: Gather,
+    invocation: Vec<String>,
+    replaying: Option<Lock>,
+    interactive: bool,
+) -> io::Result<()> {
+    let options = &gather.output;
+    if options.watch {
+        if options.file.is_none() && options.layered_output.is_none() {
+            eprintln!("--watch rewrites an output file and needs -o or --layered-output");
+            process::exit(1);
+        }
+        if gather.explained.is_some() || options.dry_run {
+            eprintln!("--watch rewrites the output and cannot be used with explain or --dry-run");
+            process::exit(1);
+        }
+        if options.lock.is_some() {
+            eprintln!("--watch keeps regenerating the output and cannot be used with --lock");
+            process::exit(1);
+        }
+    }
+    if options.interactive_prune {
+        if !interactive {
+            eprintln!(
+                "--interactive-prune needs a terminal and cannot be used with --non-interactive"
+            );
+            process::exit(1);
+        }
+        if options.watch || options.dry_run || gather.explained.is_some() {
+            eprintln!("--interactive-prune picks what is written and cannot be used with --watch, --dry-run or explain");
+            process::exit(1);
+        }
+    }
+
+    gather.project_root = resolve_root_or_exit(&gather.project_root);
+    apply_config(&mut gather);
+    let mut index = build_index(
+        &gather.project_root,
+        &index_options(&gather.index, &gather.project_root),
+    )?;
+    // Counted once the gather succeeds, so a failed run doesn't count
+    let banned = replaying
+        .is_none()
+        .then(|| (gather.project_root.clone(), gather.index.ban.clone()));
+    if !gather.output.watch {
+        gather_once(gather, &mut index, invocation, replaying, interactive)?;
+        if let Some((project_root, ban)) = banned {
+            record_left_out(&project_root, &ban);
+        }
+        return Ok(());
+    }
+
+    let gathered_names = gather_once(
+        gather.clone(),
+        &mut index,
+        invocation.clone(),
+        replaying,
+        interactive,
+    )?;
+    if let Some((project_root, ban)) = banned {
+        record_left_out(&project_root, &ban);
+    }
+    watch(gather, index, gathered_names, invocation)
+}
+
+/// `--watch`: keeps the index in memory, re-indexes the source files that
+/// change, and regenerates the output when one of them holds a gathered
+/// function. Runs until interrupted.
+fn watch(
+    gather: Gather,
+    mut index: ProjectIndex,
+    mut gathered_names: Vec<String>,
+    invocation: Vec<String>,
+) -> io::Result<()> {
+    let project_root = gather.project_root.clone();
+    let canonical_root = project_root.canonicalize()?;
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+    watcher
+        .watch(&canonical_root, RecursiveMode::Recursive)
+        .map_err(io::Error::other)?;
+    eprintln!("Watching {} for changes", project_root.display());
+
+    loop {
+        // A save usually arrives as several events; take them all first
+        let Ok(mut event) = receiver.recv() else {
+            return Ok(());
+        };
+        let mut changed: Vec<PathBuf> = Vec::new();
+        loop {
+            if let Ok(event) = event {
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) {
+                    for path in event.paths {
+                        // Paths are reported under the canonical root, while
+                        // the index keeps them under the root as given
+                        let Ok(relative) = path.strip_prefix(&canonical_root) else {
+                            continue;
+                        };
+                        let path = project_root.join(relative);
+                        if !relative.starts_with(cache::DIR)
+                            && Language::from_path(&path).is_some()
+                            && !changed.contains(&path)
+                        {
+                            changed.push(path);
+                        }
                     }
                 }
             }
+            match receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok(next) => event = next,
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+            }
         }
 
-        function_calls.insert(qualified_name.clone(), resolved_calls);
+        let gathered_paths: HashSet<&Path> = gathered_names
+            .iter()
+            .filter_map(|name| index.function_definitions.get(name))
+            .map(|info| info.path.as_path())
+            .collect();
+        let regenerate: Vec<String> = changed
+            .iter()
+            .filter(|path| gathered_paths.contains(path.as_path()))
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        for path in &changed {
+            index.update_file(path)?;
+        }
+        if !regenerate.is_empty() {
+            eprintln!("{} changed; regenerating", regenerate.join(", "));
+            gathered_names =
+                gather_once(gather.clone(), &mut index, invocation.clone(), None, false)?;
+        }
     }
+}
 
-    // Find our target function with module preference
-    let selected_function =
-        match find_function(target_function, preferred_module, &module_functions) {
-            Some(func) => func,
+/// Runs one gather over `index` and writes its output, returning the names
+/// of the gathered functions.
+fn gather_once(
+    gather: Gather,
+    index: &mut ProjectIndex,
+    invocation: Vec<String>,
+    replaying: Option<Lock>,
+    interactive: bool,
+) -> io::Result<Vec<String>> {
+    let Gather {
+        project_root,
+        mut functions,
+        locations,
+        module,
+        callers,
+        explained,
+        issue_source,
+        traversal,
+        output: options,
+        index: index_flags,
+    } = gather;
+    let mut seeds = traversal.seeds;
+    seeds.extend(traversal.doc_matches.into_iter().map(Seed::Doc));
+    let max_depth = traversal.depth;
+    let tokenizer = load_tokenizer(&traversal.tokenizer, traversal.tokenizer_file.as_deref());
+    // Fields of fields rarely matter past a few levels
+    let type_closure = match (options.type_closure, options.type_depth) {
+        (_, Some(depth)) => Some(depth),
+        (true, None) => Some(3),
+        (false, None) => None,
+    };
+
+    if options.format == Some(OutputFormat::Markdown) && options.prompt.is_some() {
+        eprintln!("--prompt lays out the code itself and cannot be used with --format markdown");
+        process::exit(1);
+    }
+
+    // Positions resolve to qualified names, which need no disambiguation
+    for (file, line) in &locations {
+        match index.function_at(file, *line) {
+            Some(function) => {
+                eprintln!("{}:{} is in {}", file, line, function);
+                functions.push(function);
+            }
             None => {
-                // Try to find a partial match
-                let mut matches = Vec::new();
-                for (name, variants) in &module_functions {
-                    if name.contains(target_function) {
-                        for (qualified_name, module) in variants {
-                            matches.push((qualified_name.clone(), module.clone()));
-                        }
-                    }
-                }
+                eprintln!("No indexed function encloses {}:{}", file, line);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(source) = &issue_source {
+        let text = issue::fetch(source).unwrap_or_else(|err| {
+            eprintln!("Could not read issue {}: {}", source, err);
+            process::exit(1);
+        });
+        let (mentions, ambiguous) = issue::mentioned_functions(index, &text);
+        for mention in &mentions {
+            eprintln!("Mentioned: {} -> {}", mention.text, mention.function);
+        }
+        if !ambiguous.is_empty() {
+            eprintln!("Skipped ambiguous names: {}", ambiguous.join(", "));
+        }
+        if mentions.is_empty() {
+            eprintln!("No indexed function is mentioned in {}", source);
+            process::exit(1);
+        }
+        seeds.extend(
+            mentions
+                .into_iter()
+                .map(|mention| Seed::Function(mention.function)),
+        );
+    }
+
+    let mut orphan_usage = None;
+    let mut single_target = None;
+    // The functions the gather starts from, where --outline depths count
+    // from
+    let outline_roots: Vec<String>;
+    let gathered_names = if seeds.is_empty() {
+        // Seeds replace the function arguments, so one of them is present
+        // Find our target functions with module preference
+        let mut selected_functions: Vec<String> = Vec::new();
+        for target_function in &functions {
+            let selected_function =
+                select_function(index, target_function, module.as_ref(), interactive);
+            eprintln!("Selected function: {}", selected_function);
+            if !selected_functions.contains(&selected_function) {
+                selected_functions.push(selected_function);
+            }
+        }
+        let target_names = selected_functions.join(", ");
+        let mut usages = String::new();
+        for selected_function in &selected_functions {
+            if let Some(usage) = usage::orphan_usage(index, selected_function) {
+                eprintln!("{} has no callers; adding usage context", selected_function);
+                usages.push_str(&usage);
+            }
+        }
+        if !usages.is_empty() {
+            orphan_usage = Some(usages);
+        }
+        if let [selected_function] = selected_functions.as_slice() {
+            single_target = Some(selected_function.clone());
+        }
+
+        if callers && options.layered_output.is_some() {
+            eprintln!("--layered-output layers callees and cannot be used with callers");
+            process::exit(1);
+        }
+        if selected_functions.len() > 1 && options.layered_output.is_some() {
+            eprintln!(
+                "--layered-output layers by distance from a single function and cannot be used with several targets"
+            );
+            process::exit(1);
+        }
 
-                if !matches.is_empty() {
+        // With --all-variants, every cfg variant of a target is a root too;
+        // the roots' frontiers are walked together so shared callees appear once
+        let mut roots = selected_functions.clone();
+        if index_flags.all_variants {
+            for selected_function in &selected_functions {
+                roots.extend(index.cfg_variants_of(selected_function));
+            }
+        }
+        if options.inline_thin_wrappers {
+            wrappers::inline(index, &roots);
+        }
+        outline_roots = roots.clone();
+
+        // Recursively gather all context starting with the target function
+        let gathered_names = match traversal.fill_budget {
+            Some(_) if callers => {
+                eprintln!("--fill-budget expands callees and cannot be used with callers");
+                process::exit(1);
+            }
+            _ if callers && traversal.strategy != Strategy::Bfs => {
+                eprintln!("--strategy orders callees and cannot be used with callers");
+                process::exit(1);
+            }
+            None if callers => {
+                let callers = index.callers_within(&roots, max_depth);
+                eprintln!(
+                    "{} {} called by {} functions",
+                    target_names,
+                    if selected_functions.len() > 1 {
+                        "are"
+                    } else {
+                        "is"
+                    },
+                    callers.len() - roots.len()
+                );
+                callers
+            }
+            Some(budget) => {
+                let filled = budget::fill(
+                    index,
+                    &tokenizer,
+                    &roots,
+                    budget,
+                    max_depth,
+                    traversal.strategy,
+                );
+                if filled.used_tokens > budget {
                     eprintln!(
-                        "Function '{}' not found. Did you mean one of these?",
-                        target_function
+                        "Warning: the target alone needs {} tokens, more than the budget of {}",
+                        filled.used_tokens, budget
                     );
-                    let mut deduped_matches = HashSet::new();
-                    for (i, (qualified_name, module)) in matches.iter().enumerate() {
-                        if i < 10 && deduped_matches.insert(qualified_name) {
-                            eprintln!("  {} (in {})", qualified_name, module);
-                        }
-                    }
-                    if matches.len() > 10 {
-                        eprintln!("  ... and {} more", matches.len() - 10);
-                    }
-                } else {
-                    eprintln!("Function '{}' not found in project", target_function);
                 }
-
-                process::exit(1);
+                eprintln!(
+                    "Filled {} of {} tokens with {} functions",
+                    filled.used_tokens,
+                    budget,
+                    filled.functions.len()
+                );
+                print_omitted(&filled.omitted);
+                filled.functions
             }
+            None => budget::traverse(index, &tokenizer, &roots, max_depth, traversal.strategy),
         };
+        trim::filter(
+            index,
+            gathered_names,
+            &traversal.within,
+            &traversal.exclude_module,
+            &selected_functions,
+        )
+    } else {
+        if traversal.fill_budget.is_some() {
+            eprintln!(
+                "--fill-budget expands from a single function and cannot be used with --seed"
+            );
+            process::exit(1);
+        }
+        if options.layered_output.is_some() {
+            eprintln!(
+                "--layered-output layers by distance from a single function and cannot be used with --seed"
+            );
+            process::exit(1);
+        }
 
-    eprintln!("Selected function: {}", selected_function);
+        if callers {
+            eprintln!("callers traverses from a single function and cannot be used with --seed");
+            process::exit(1);
+        }
+        if traversal.strategy != Strategy::Bfs {
+            eprintln!("--strategy orders a walk from the target and cannot be used with --seed");
+            process::exit(1);
+        }
 
-    // Find our target function and recursively gather all context
-    let mut output = String::new();
+        // Function seeds are resolved first so unknown names get suggestions
+        let seeds: Vec<Seed> = seeds
+            .into_iter()
+            .map(|seed| match seed {
+                Seed::Function(name) => {
+                    Seed::Function(select_function(index, &name, None, interactive))
+                }
+                seed => seed,
+            })
+            .collect();
+        outline_roots = seeds
+            .iter()
+            .filter_map(|seed| match seed {
+                Seed::Function(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        if options.inline_thin_wrappers {
+            wrappers::inline(index, &outline_roots);
+        }
+        let seeded =
+            seed::gather(index, &seeds, traversal.combine, max_depth).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                process::exit(1);
+            });
+        for note in &seeded.notes {
+            eprintln!("{}", note);
+        }
+        eprintln!("Combined seeds: {} functions", seeded.functions.len());
+        trim::filter(
+            index,
+            seeded.functions,
+            &traversal.within,
+            &traversal.exclude_module,
+            &[],
+        )
+    };
+    let output_file = options.file;
+
+    let gathered_names = match traversal.max_tokens {
+        Some(max_tokens) => {
+            let truncated = budget::truncate(index, &tokenizer, gathered_names, max_tokens);
+            eprintln!(
+                "Kept {} of {} tokens with {} functions",
+                truncated.used_tokens,
+                max_tokens,
+                truncated.functions.len()
+            );
+            print_omitted(&truncated.omitted);
+            truncated.functions
+        }
+        None => gathered_names,
+    };
+    if options.inline_thin_wrappers {
+        let inlined = gathered_names
+            .iter()
+            .filter(|name| wrappers::is_inlined(&index.function_definitions[*name].definition))
+            .count();
+        eprintln!("Inlined {} thin wrappers", inlined);
+    }
+
+    let gathered_names = match options.interactive_prune {
+        true => match prune::run(index, &outline_roots, &gathered_names, callers)? {
+            Some(pruned) => {
+                eprintln!(
+                    "Kept {} of {} functions",
+                    pruned.kept.len(),
+                    gathered_names.len()
+                );
+                if replaying.is_none() {
+                    record_left_out(&project_root, &pruned.deselected);
+                }
+                pruned.kept
+            }
+            None => {
+                eprintln!("Pruning cancelled; no output written");
+                process::exit(1);
+            }
+        },
+        false => gathered_names,
+    };
 
-    // Start with target function
-    let mut queue = VecDeque::new();
-    queue.push_back(selected_function.clone());
-    let mut visited = HashSet::new();
+    if let Some(name) = &explained {
+        let explained = select_function(index, name, None, interactive);
+        print!(
+            "{}",
+            explain::render(index, &tokenizer, &gathered_names, &explained)
+        );
+        return Ok(gathered_names);
+    }
 
-    while let Some(current_function) = queue.pop_front() {
-        if visited.contains(&current_function) {
-            continue;
+    if options.dry_run {
+        print_dry_run(index, &tokenizer, &gathered_names);
+        print!(
+            "\n{}",
+            trim::report(index, &tokenizer, &gathered_names, traversal.budget)
+        );
+        return Ok(gathered_names);
+    }
+
+    if let Some(token_budget) = traversal.budget {
+        let total: usize = gathered_names
+            .iter()
+            .map(|name| budget::function_tokens(index, &tokenizer, name))
+            .sum();
+        if total > token_budget {
+            eprint!(
+                "{}",
+                trim::report(index, &tokenizer, &gathered_names, Some(token_budget))
+            );
         }
+    }
 
-        visited.insert(current_function.clone());
+    // Held until the output is written
+    let _output_lock = match options.layered_output.as_ref().or(output_file.as_ref()) {
+        Some(path) if options.output_lock => Some(atomic::OutputLock::acquire(path)?),
+        _ => None,
+    };
 
-        if let Some(function_info) = function_definitions.get(&current_function) {
-            let path_str = function_info.path.to_string_lossy();
+    let graph_format = match options.format {
+        Some(OutputFormat::Graph(format)) => Some(format),
+        _ => options.graph,
+    };
+    if let Some(format) = graph_format {
+        let output = graph::render(format, index, &gathered_names);
+        if let Some(output_path) = output_file {
+            atomic::write(&output_path, &output)?;
+            println!("Output written to file");
+        } else {
+            print!("{}", output);
+        }
+        return Ok(gathered_names);
+    }
 
-            output.push_str(&format!("\n=== {} ===\n", path_str));
-            output.push_str(&function_info.definition);
-            output.push_str("\n\n");
+    if replaying.is_some() || options.lock.is_some() {
+        let current = Lock::record(invocation, &project_root, index, &gathered_names);
 
-            // Add all functions called by this function to the queue
-            if let Some(called_fns) = function_calls.get(&current_function) {
-                for called_fn in called_fns {
-                    queue.push_back(called_fn.clone());
+        if let Some(recorded) = &replaying {
+            let changes = recorded.changes(&current);
+            if !changes.is_empty() {
+                eprintln!("The code changed since the lock was recorded:");
+                for change in &changes {
+                    eprintln!("  {}", change);
                 }
+                process::exit(1);
+            }
+            eprintln!(
+                "Replayed {} functions, all unchanged",
+                current.functions.len()
+            );
+        }
+
+        if let Some(lock_path) = &options.lock {
+            current.write(lock_path)?;
+            eprintln!(
+                "Recorded {} functions in {}",
+                current.functions.len(),
+                lock_path.display()
+            );
+        }
+    }
+
+    if let Some(months) = options.churn {
+        if let Some(note) = churn::annotate(index, &project_root, &gathered_names, months) {
+            eprintln!("{}", note);
+        }
+    }
+    let table_of_contents = options.outline.then(|| {
+        let depths = outline::depths(index, &outline_roots, &gathered_names, callers);
+        outline::annotate(index, &depths);
+        outline::render(index, &gathered_names, &depths)
+    });
+
+    let gathered: Vec<&FunctionInfo> = gathered_names
+        .iter()
+        .map(|name| &index.function_definitions[name])
+        .collect();
+
+    // Sections that come ahead of the code
+    let mut prelude: Vec<(&str, String)> = Vec::new();
+    if let Some(table_of_contents) = table_of_contents {
+        prelude.push(("OUTLINE", table_of_contents));
+    }
+    // What a target method's `self` is, ahead of the method itself
+    let receivers = receiver::collect(index, &outline_roots, !options.no_types);
+    if !receivers.is_empty() {
+        prelude.push(("RECEIVER", receiver::render(&receivers)));
+    }
+    let receiver_types: Vec<&TypeInfo> = receivers.iter().filter_map(|r| r.type_info).collect();
+    if options.glossary {
+        prelude.push((
+            "GLOSSARY",
+            glossary::render(index, &gathered_names, &gathered),
+        ));
+    }
+    if options.with_manifest {
+        prelude.push(("MANIFEST", cargo::manifest_context(&gathered)));
+    }
+    let module_docs = if options.module_docs {
+        module_docs::collect(&gathered)
+    } else {
+        HashMap::new()
+    };
+
+    let prompt_preset = options.prompt;
+    let markdown = options.format == Some(OutputFormat::Markdown);
+    let render_functions = |names: &[String], functions: &[&FunctionInfo]| {
+        if markdown {
+            markdown::render(names, functions, &module_docs)
+        } else {
+            render_with_module_docs(functions, &module_docs)
+        }
+    };
+    let render_context = |names: &[String], functions: &[&FunctionInfo]| match prompt_preset {
+        Some(preset) => prompt::render(preset, index, names, functions, &prelude, &module_docs),
+        None if markdown => {
+            let mut output = String::new();
+            for (title, section) in &prelude {
+                output.push_str(&markdown::section(title, section));
+            }
+            output.push_str(&render_functions(names, functions));
+            output
+        }
+        None => {
+            let mut output = String::new();
+            for (title, section) in &prelude {
+                output.push_str(&format!("=== {} ===\n{}\n", title, section));
+            }
+            output.push_str(&render_functions(names, functions));
+            output
+        }
+    };
+
+    // The full framing (instructions, prelude, types, aliases, conversions,
+    // serialization, stubs, panics, usage, questions) goes with the entry point
+    let render = |names: &[String], functions: &[&FunctionInfo]| {
+        let mut output = render_context(names, functions);
+        let mut sections = String::new();
+        let mut shown_types = Vec::new();
+        // test-gen already lists every signature type
+        if prompt_preset != Some(PromptPreset::TestGen) {
+            if !options.no_types {
+                sections.push_str(&types::render(
+                    index,
+                    functions,
+                    type_closure,
+                    &receiver_types,
+                ));
+                shown_types = types::collect(index, functions, type_closure, &receiver_types);
+                shown_types.extend(&receiver_types);
+            }
+            sections.push_str(&aliases::render(index, functions));
+            shown_types.extend(aliases::small_types(index, functions));
+        }
+        sections.push_str(&conversions::render(index, functions));
+        sections.push_str(&serialization::render(index, functions, &shown_types));
+        if options.stubs {
+            sections.push_str(&stubs::render(index, names, functions));
+        }
+        if options.with_deps {
+            if let Some(signatures) = deps::render(index, names) {
+                eprintln!(
+                    "Found the signatures of {} of {} dependency calls",
+                    signatures.found, signatures.calls
+                );
+                sections.push_str(&signatures.text);
+            }
+        }
+        if options.panics {
+            sections.push_str(&panics::render(names, functions));
+        }
+        if let Some(usage) = &orphan_usage {
+            sections.push_str(usage);
+        }
+        if options.questions {
+            sections.push_str(&questions::render(index, names, functions));
+        }
+        // Markdown keeps the plain-text sections in one fenced block
+        if markdown {
+            output.push_str(&markdown::section("Related definitions", &sections));
+        } else {
+            output.push_str(&sections);
+        }
+        output
+    };
+
+    let summary = (!options.no_summary).then(|| {
+        // Cargo mode supplies the root itself
+        let from_cargo = env::var_os("GATHER_CONTEXT_FROM_CARGO").is_some();
+        let root = project_root.to_string_lossy();
+        let hints = summary::Hints {
+            program: if from_cargo {
+                "cargo gather-context"
+            } else {
+                "gather-context"
+            },
+            project_root: (!from_cargo).then_some(&*root),
+            target: single_target.as_deref(),
+            callers,
+            depth: max_depth,
+            budget: traversal
+                .fill_budget
+                .or(traversal.max_tokens)
+                .or(traversal.budget),
+            show_unresolved: options.show_unresolved,
+        };
+        summary::render(index, &tokenizer, &gathered_names, &hints)
+    });
+
+    if let Some(dir) = &options.layered_output {
+        let layer_sizes = layers::write(dir, index, &gathered_names, |layer, names| {
+            let functions: Vec<&FunctionInfo> = names
+                .iter()
+                .map(|name| &index.function_definitions[name])
+                .collect();
+            if layer == 0 {
+                render(names, &functions)
+            } else {
+                render_functions(names, &functions)
             }
+        })?;
+        for (path, count) in layer_sizes {
+            eprintln!("Wrote {} functions to {}", count, path.display());
         }
+        if !options.no_context_manifest {
+            let path = sidecar::write(dir, index, &project_root, &gathered_names)?;
+            eprintln!("Wrote manifest to {}", path.display());
+        }
+        if let Some(summary) = &summary {
+            eprint!("{}", summary);
+        }
+        return Ok(gathered_names);
+    }
+
+    let mut output = render(&gathered_names, &gathered);
+    if options.json {
+        let result = GatherResult {
+            target: gathered_names.first().cloned().unwrap_or_default(),
+            functions: gathered_names
+                .iter()
+                .zip(&gathered)
+                .map(|(name, info)| GatheredFunction::new(name, info))
+                .collect(),
+            already_sent: Vec::new(),
+            output,
+        };
+        output = serde_json::to_string_pretty(&result.to_json())?;
+        output.push('\n');
     }
 
     // Either print to stdout or write to file
     if let Some(output_path) = output_file {
-        let mut file = File::create(output_path)?;
-        file.write_all(output.as_bytes())?;
+        atomic::write(&output_path, &output)?;
         println!("Output written to file");
+        // JSON output already carries the same information
+        if !options.no_context_manifest && !options.json {
+            let path = sidecar::write(&output_path, index, &project_root, &gathered_names)?;
+            eprintln!("Wrote manifest to {}", path.display());
+        }
     } else {
         print!("{}", output);
     }
+    if let Some(summary) = &summary {
+        eprint!("{}", summary);
+    }
 
-    Ok(())
+    Ok(gathered_names)
+}
+
+/// Index options from the flags. The stored index is used once `index` has
+/// created it.
+fn index_options(flags: &IndexFlags, project_root: &Path) -> IndexOptions {
+    let config = load_config(project_root);
+    IndexOptions {
+        extract: ExtractOptions {
+            expand_combinators: flags.expand_combinators,
+            rust_backend: RustBackend::Auto,
+        },
+        annotate_owners: flags.owned_by.is_some() || flags.show_owners,
+        owned_by: flags.owned_by.clone(),
+        operators: flags.operators,
+        no_ignore: flags.no_ignore,
+        all_variants: flags.all_variants,
+        verbose: flags.verbose,
+        include: flags.include.clone(),
+        exclude: config
+            .exclude
+            .into_iter()
+            .chain(flags.exclude.clone())
+            .collect(),
+        impls: flags.impls.clone(),
+        languages: config.languages,
+        skip_calls: config
+            .skip_calls
+            .into_iter()
+            .chain(
+                flags
+                    .ban
+                    .iter()
+                    .map(|name| learned::simple_name(name).to_string()),
+            )
+            .chain(
+                flags
+                    .learned_excludes
+                    .then(|| learned::learned(project_root))
+                    .into_iter()
+                    .flatten(),
+            )
+            .collect(),
+        license_allow: if flags.license_allow.is_empty() {
+            config.license_allow
+        } else {
+            flags.license_allow.clone()
+        },
+        expand: flags.expand,
+        profile: flags.profile.clone(),
+        cache: !flags.no_cache && cache::path_for(project_root).is_file(),
+    }
+}
+
+/// Counts `names` as left out by this run toward --learned-excludes, and
+/// suggests configuring the ones left out often.
+fn record_left_out(project_root: &Path, names: &[String]) {
+    let learned = match learned::record(project_root, names) {
+        Ok(learned) => learned,
+        Err(err) => {
+            eprintln!(
+                "Could not update {}: {}",
+                learned::path_for(project_root).display(),
+                err
+            );
+            return;
+        }
+    };
+    let configured = load_config(project_root).skip_calls;
+    for (name, runs) in learned {
+        if !configured.contains(&name) {
+            eprintln!(
+                "'{}' was left out in {} runs; add it to [calls] skip in {} to always skip it, \
+                 or pass --learned-excludes",
+                name,
+                runs,
+                config::FILE_NAME
+            );
+        }
+    }
+}
+
+/// The project's `.gather-context.toml`, or the defaults without one.
+fn load_config(project_root: &Path) -> Config {
+    Config::load(project_root).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    })
+}
+
+/// Fills in the output settings of `.gather-context.toml` the command line
+/// leaves open.
+fn apply_config(gather: &mut Gather) {
+    let config = load_config(&gather.project_root);
+    let output = &mut gather.output;
+    if let Some(format) = config.format {
+        if output.format.is_none() && output.graph.is_none() && output.prompt.is_none() {
+            output.format = Some(cli::parse_format(&format).unwrap_or_else(|err| {
+                eprintln!("{}: output.format: {}", config::FILE_NAME, err);
+                process::exit(1);
+            }));
+        }
+    }
+    let traversal = &mut gather.traversal;
+    if traversal.max_tokens.is_none() && traversal.fill_budget.is_none() {
+        traversal.max_tokens = config.max_tokens;
+    }
+}
+
+fn load_tokenizer(name: &str, file: Option<&Path>) -> Tokenizer {
+    Tokenizer::load(name, file).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    })
+}
+
+/// Lists the functions a token budget left out.
+fn print_omitted(omitted: &[String]) {
+    if omitted.is_empty() {
+        return;
+    }
+    eprintln!("Omitted {} functions that did not fit:", omitted.len());
+    for name in omitted {
+        eprintln!("  {}", name);
+    }
+}
+
+/// `--dry-run`: what a run would include, with the size of each function,
+/// without rendering or writing anything.
+fn print_dry_run(index: &ProjectIndex, tokenizer: &Tokenizer, gathered_names: &[String]) {
+    let name_width = gathered_names.iter().map(String::len).max().unwrap_or(0);
+    let mut total_lines = 0;
+    let mut total_tokens = 0;
+
+    for name in gathered_names {
+        let info = &index.function_definitions[name];
+        let lines = info.definition.lines().count();
+        let tokens = budget::function_tokens(index, tokenizer, name);
+        total_lines += lines;
+        total_tokens += tokens;
+        println!(
+            "{:<width$}  {:>6} lines  {:>7} tokens  {}:{}",
+            name,
+            lines,
+            tokens,
+            info.path.to_string_lossy(),
+            info.line_number,
+            width = name_width
+        );
+    }
+    println!(
+        "{} functions, {} lines, ~{} tokens",
+        gathered_names.len(),
+        total_lines,
+        total_tokens
+    );
+}
+
+/// Inserts the crate root reported by cargo where the project root argument
+/// goes: right after the subcommand, or first in the old subcommand-less
+/// form.
+fn insert_cargo_project_root(args: &mut Vec<String>) {
+    let no_root = [
+        "help",
+        "-h",
+        "--help",
+        "-V",
+        "--version",
+        "replay",
+        "compose",
+        "changed-since",
+    ];
+    if args.len() > 1 && no_root.contains(&args[1].as_str()) {
+        return;
+    }
+
+    let manifest_path = take_flag_value(args, "--manifest-path");
+    // Cargo's own `--release` is the release profile
+    if let Some(position) = args.iter().position(|arg| arg == "--release") {
+        args.splice(
+            position..=position,
+            ["--profile".to_string(), "release".to_string()],
+        );
+    }
+    let project_root = cargo::locate_project(manifest_path.as_deref()).unwrap_or_else(|err| {
+        eprintln!("Could not locate the cargo project: {}", err);
+        process::exit(1);
+    });
+
+    let position = if args.len() > 1 && Command::names().contains(&args[1]) {
+        2
+    } else {
+        1
+    };
+    args.insert(position, project_root.to_string_lossy().into_owned());
+}
+
+/// Removes `flag <value>` or `flag=value` from `args` and returns the value.
+fn take_flag_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let prefix = format!("{}=", flag);
+    let position = args
+        .iter()
+        .position(|arg| arg == flag || arg.starts_with(&prefix))?;
+    let arg = args.remove(position);
+
+    if let Some(value) = arg.strip_prefix(&prefix) {
+        return Some(value.to_string());
+    }
+    if position < args.len() {
+        return Some(args.remove(position));
+    }
+
+    eprintln!("Missing value for {}", flag);
+    process::exit(1);
+}
+
+fn resolve_root_or_exit(path: &Path) -> PathBuf {
+    let root = index::resolve_project_root(path).unwrap_or_else(|err| {
+        eprintln!("Cannot use '{}' as project root: {}", path.display(), err);
+        process::exit(1);
+    });
+    if root != path {
+        eprintln!("Using project root {}", root.to_string_lossy());
+    }
+    root
+}
+
+/// Builds the index of `project_root`, writing what the build reports to
+/// stderr.
+fn build_index(project_root: &Path, options: &IndexOptions) -> io::Result<ProjectIndex> {
+    let index = ProjectIndex::build(project_root, options)?;
+    for note in &index.notes {
+        eprintln!("{}", note);
+    }
+    Ok(index)
 }
 
-fn find_function(
+/// Tells which of several functions named `target_function` was picked, and
+/// how to pick another.
+fn report_ambiguity(
     target_function: &str,
     preferred_module: Option<&String>,
-    module_functions: &HashMap<String, Vec<(String, String)>>,
-) -> Option<String> {
-    // Check if the function exists
-    if let Some(variants) = module_functions.get(target_function) {
-        if variants.len() == 1 {
-            // Only one variant exists
-            return Some(variants[0].0.clone());
-        }
-
-        // Multiple variants - try to match preferred module
-        if let Some(module) = preferred_module {
-            for (qualified_name, mod_path) in variants {
-                if mod_path.contains(module) {
-                    eprintln!("Found function in preferred module: {}", mod_path);
-                    return Some(qualified_name.clone());
-                }
-            }
-
-            // Print available modules
+    found: &index::FunctionMatch,
+) {
+    if found.candidates.is_empty() {
+        return;
+    }
+    match preferred_module {
+        Some(_) if found.in_preferred_module => {
+            let module = found
+                .candidates
+                .iter()
+                .find(|(qualified_name, _)| *qualified_name == found.qualified_name)
+                .map_or("", |(_, module)| module.as_str());
+            eprintln!("Found function in preferred module: {}", module);
+        }
+        Some(module) => {
             eprintln!(
                 "Function '{}' not found in module '{}'. Available in:",
                 target_function, module
             );
-            for (_, mod_path) in variants {
-                eprintln!("  {}", mod_path);
+            for (_, module) in &found.candidates {
+                eprintln!("  {}", module);
             }
-
-            // Default to first one
             eprintln!("Using first available implementation");
-            return Some(variants[0].0.clone());
         }
+        None => {
+            eprintln!("Multiple implementations of '{}' found:", target_function);
+            for (i, (qualified_name, module)) in found.candidates.iter().enumerate() {
+                eprintln!("  {}. In {} ({})", i + 1, module, qualified_name);
+            }
+            eprintln!("Please specify a preferred module with --module");
+        }
+    }
+}
 
-        // No preferred module - list options
-        eprintln!("Multiple implementations of '{}' found:", target_function);
-        for (i, (_, module)) in variants.iter().enumerate() {
-            eprintln!("  {}. In {}", i + 1, module);
+/// Resolves the function to start from, or explains what is available and
+/// exits when nothing matches. With `interactive`, a name several functions
+/// share is resolved by asking.
+fn select_function(
+    index: &ProjectIndex,
+    target_function: &str,
+    preferred_module: Option<&String>,
+    interactive: bool,
+) -> String {
+    if interactive {
+        if let Some(func) = choose_function(index, target_function, preferred_module) {
+            return func;
+        }
+    }
+    if let Some(found) = index.find_function(target_function, preferred_module) {
+        report_ambiguity(target_function, preferred_module, &found);
+        return found.qualified_name;
+    }
+
+    // Try to find a partial match
+    let matches = index.partial_matches(target_function);
+
+    if !matches.is_empty() {
+        eprintln!(
+            "Function '{}' not found. Did you mean one of these?",
+            target_function
+        );
+        let mut deduped_matches = HashSet::new();
+        for (i, (qualified_name, module)) in matches.iter().enumerate() {
+            if i < 10 && deduped_matches.insert(qualified_name) {
+                eprintln!("  {} (in {})", qualified_name, module);
+            }
+        }
+        if matches.len() > 10 {
+            eprintln!("  ... and {} more", matches.len() - 10);
         }
-        eprintln!("Please specify a preferred module with the third argument");
-        return Some(variants[0].0.clone());
+    } else {
+        eprintln!("Function '{}' not found in project", target_function);
     }
 
-    None
+    process::exit(1);
 }
 
-fn collect_rust_files(root: &Path) -> io::Result<Vec<PathBuf>> {
-    let mut rust_files = Vec::new();
+/// Lists the functions named `target_function` with their locations and
+/// reads the number of the one meant from stdin. `None` when the name is
+/// unique, the preferred module settles it, or stdin closes.
+fn choose_function(
+    index: &ProjectIndex,
+    target_function: &str,
+    preferred_module: Option<&String>,
+) -> Option<String> {
+    let variants = index.module_functions.get(target_function)?;
+    let settled = preferred_module.is_some_and(|module| {
+        variants
+            .iter()
+            .any(|(_, module_path)| module_path.contains(module.as_str()))
+    });
+    if variants.len() < 2 || settled {
+        return None;
+    }
 
-    for entry in WalkDir::new(root)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| !e.file_type().is_dir())
-    {
-        let path = entry.path();
-        if let Some(extension) = path.extension() {
-            if extension == "rs" {
-                rust_files.push(path.to_path_buf());
+    eprintln!("Multiple functions are named '{}':", target_function);
+    for (i, (qualified_name, _)) in variants.iter().enumerate() {
+        let info = &index.function_definitions[qualified_name];
+        eprintln!(
+            "  {}. {} ({}:{})",
+            i + 1,
+            qualified_name,
+            info.path.to_string_lossy(),
+            info.line_number
+        );
+    }
+    loop {
+        eprint!("Select 1-{}: ", variants.len());
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer).ok()? == 0 {
+            return None;
+        }
+        match answer.trim().parse::<usize>() {
+            Ok(choice) if (1..=variants.len()).contains(&choice) => {
+                return Some(variants[choice - 1].0.clone());
             }
+            _ => eprintln!("Enter a number from 1 to {}", variants.len()),
         }
     }
-
-    Ok(rust_files)
 }
 
-fn extract_module_path(file_path: &Path, project_root: &Path) -> String {
-    let rel_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
-    let path_str = rel_path.to_string_lossy();
+/// `index <project_root>`: stores the parsed files in
+/// `.gather-context/index.bin` for later runs to reuse, and summarizes what
+/// the index holds as a quick check of what a gather will see.
+fn run_index(args: cli::IndexArgs) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let options = IndexOptions {
+        cache: !args.index.no_cache,
+        ..index_options(&args.index, &project_root)
+    };
+    let index = build_index(&project_root, &options)?;
 
-    // Convert path to Rust module path format
-    let mut module_path = path_str
-        .replace('/', "::")
-        .replace('\\', "::")
-        .replace(".rs", "");
+    let functions = index.function_definitions.len();
+    let methods = index
+        .function_definitions
+        .values()
+        .filter(|info| info.self_type.is_some())
+        .count();
+    let tests = index
+        .function_definitions
+        .values()
+        .filter(|info| info.is_test)
+        .count();
+    let modules: HashSet<&String> = index
+        .function_definitions
+        .values()
+        .map(|info| &info.module_path)
+        .collect();
+    let types: usize = index.type_definitions.values().map(Vec::len).sum();
+    let impls: usize = index.impls.values().map(Vec::len).sum();
+    let edges: usize = index.function_calls.values().map(HashSet::len).sum();
 
-    // Special case for lib.rs or mod.rs
-    if module_path.ends_with("::lib") {
-        module_path = module_path[..module_path.len() - 5].to_string();
-    } else if module_path.ends_with("::mod") {
-        module_path = module_path[..module_path.len() - 5].to_string();
+    if args.json {
+        let summary = json!({
+            "project_root": project_root.to_string_lossy(),
+            "functions": functions,
+            "methods": methods,
+            "tests": tests,
+            "modules": modules.len(),
+            "types": types,
+            "impls": impls,
+            "call_edges": edges,
+        });
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        println!(
+            "Functions:   {} ({} methods, {} tests)",
+            functions, methods, tests
+        );
+        println!("Modules:     {}", modules.len());
+        println!("Types:       {}", types);
+        println!("Impl blocks: {}", impls);
+        println!("Call edges:  {}", edges);
     }
 
-    module_path
+    if let Some(tags_path) = &args.export_tags {
+        let tags_dir = tags_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let tags = tags::render(&index, &project_root, tags_dir);
+        atomic::write(tags_path, &tags)?;
+        eprintln!(
+            "Wrote {} tags to {}",
+            tags.lines()
+                .filter(|line| !line.starts_with("!_TAG_"))
+                .count(),
+            tags_path.display()
+        );
+    }
+    Ok(())
+}
+
+/// `list <project_root> [--within module]`: every indexed function by
+/// qualified name.
+fn run_list(args: cli::ListArgs) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let index = build_index(&project_root, &index_options(&args.index, &project_root))?;
+    let output = search::list(&index, args.within.as_deref(), args.tests);
+    eprintln!("{} functions", output.lines().count());
+    print!("{}", output);
+    Ok(())
 }
 
-fn process_file(
-    path: &Path,
-    module_path: &str,
-) -> io::Result<(HashMap<String, FunctionInfo>, HashSet<String>)> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let content: String = reader
-        .lines()
-        .filter_map(Result::ok)
-        .collect::<Vec<String>>()
-        .join("\n");
+/// `stats <project_root>`: project metrics and rankings for picking where
+/// to start a gather.
+fn run_stats(args: cli::StatsArgs) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let index = build_index(&project_root, &index_options(&args.index, &project_root))?;
+    let stats = stats::collect(&index, args.top, args.tests);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&stats.json(&index))?);
+    } else {
+        print!("{}", stats.render(&index));
+    }
+    Ok(())
+}
 
-    let mut function_info: HashMap<String, FunctionInfo> = HashMap::new();
-    let mut types: HashSet<String> = HashSet::new();
+/// `changed-since <manifest>`: lists what changed in the functions a saved
+/// context holds, exiting with 1 when anything did.
+fn run_changed_since(args: cli::ChangedSinceArgs) -> io::Result<()> {
+    let manifest = sidecar::load(&args.manifest).unwrap_or_else(|err| {
+        eprintln!("Could not read {}: {}", args.manifest.display(), err);
+        process::exit(1);
+    });
+    let project_root = match (&args.project_root, manifest["project_root"].as_str()) {
+        (Some(project_root), _) => project_root.clone(),
+        (None, Some(project_root)) => PathBuf::from(project_root),
+        (None, None) => {
+            eprintln!(
+                "{} records no project root; pass --project-root",
+                args.manifest.display()
+            );
+            process::exit(1);
+        }
+    };
+    let project_root = resolve_root_or_exit(&project_root);
+    let index = build_index(&project_root, &index_options(&args.index, &project_root))?;
 
-    // Extract function definitions with their body
-    let fn_regex =
-        Regex::new(r"(?m)^\s*(pub\s+)?(async\s+)?fn\s+([a-zA-Z0-9_]+)\s*(<.*?>)?\s*\(").unwrap();
+    let changes = sidecar::changes_since(&index, &manifest);
+    let count = manifest["functions"].as_array().map_or(0, Vec::len);
+    if changes.is_empty() {
+        eprintln!("None of the {} gathered functions changed", count);
+        return Ok(());
+    }
+    for change in &changes {
+        println!("{}", change);
+    }
+    eprintln!(
+        "{} changes to the {} gathered functions; regenerate the context",
+        changes.len(),
+        count
+    );
+    process::exit(1);
+}
 
-    for captures in fn_regex.captures_iter(&content) {
-        let function_name = captures.get(3).unwrap().as_str();
-        let line_number = content[..captures.get(0).unwrap().start()].lines().count() + 1;
+/// `impact <project_root> <function>`: the tests that reach the function
+/// through the call graph, which are the ones a change to it can break.
+fn run_impact(args: cli::ImpactArgs, interactive: bool) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let index = build_index(&project_root, &index_options(&args.index, &project_root))?;
+    let function = select_function(&index, &args.function, args.module.as_ref(), interactive);
+    let tests = index.tests_reaching(std::slice::from_ref(&function));
+    eprintln!("{} tests reach {}", tests.len(), function);
 
-        let mut def_start = captures.get(0).unwrap().start();
-        while def_start > 0 && !content[def_start - 1..def_start].contains('\n') {
-            def_start -= 1;
+    if args.json {
+        let tests: Vec<serde_json::Value> = tests
+            .iter()
+            .map(|(name, depth)| {
+                let info = &index.function_definitions[name];
+                json!({
+                    "name": name,
+                    "path": info.path.to_string_lossy(),
+                    "line": info.line_number,
+                    "depth": depth,
+                })
+            })
+            .collect();
+        let report = json!({ "function": function, "tests": tests });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for (name, depth) in &tests {
+            let info = &index.function_definitions[name];
+            println!(
+                "{} ({}:{}, {} {} away)",
+                name,
+                info.path.to_string_lossy(),
+                info.line_number,
+                depth,
+                if *depth == 1 { "call" } else { "calls" }
+            );
         }
+    }
+    Ok(())
+}
 
-        // Find the function's closing brace by properly tracking nested braces
-        let mut brace_count = 0;
-        let mut found_opening_brace = false;
-        let mut def_end = captures.get(0).unwrap().end();
+/// `compare <project_root> --roots-a foo,bar --roots-b baz`: reports which
+/// functions are reachable from one set of roots but not the other.
+fn run_compare(args: cli::CompareArgs, interactive: bool) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let index = build_index(&project_root, &index_options(&args.index, &project_root))?;
 
-        for (i, c) in content[def_end..].chars().enumerate() {
-            if c == '{' {
-                found_opening_brace = true;
-                brace_count += 1;
-            } else if c == '}' {
-                brace_count -= 1;
-                if brace_count == 0 && found_opening_brace {
-                    def_end += i + 1;
-                    break;
-                }
-            }
-        }
+    let resolve = |roots: &[String]| -> Vec<String> {
+        roots
+            .iter()
+            .map(|root| select_function(&index, root, None, interactive))
+            .collect()
+    };
+    let reachable_a = index.reachable(&resolve(&args.roots_a));
+    let reachable_b = index.reachable(&resolve(&args.roots_b));
 
-        // If we couldn't find the end properly, just use a large chunk
-        if !found_opening_brace || brace_count != 0 {
-            def_end = std::cmp::min(def_end + 5000, content.len());
-        }
-
-        let fn_body = content[def_start..def_end].trim().to_string();
-
-        // Extract function calls within this function body
-        let mut calls = HashSet::new();
-
-        // Look for method calls (.method())
-        let method_regex = Regex::new(r"\.([a-zA-Z0-9_]+)\s*\(").unwrap();
-        for method_captures in method_regex.captures_iter(&fn_body) {
-            let method_name = method_captures.get(1).unwrap().as_str();
-            // Skip common built-ins and add the rest
-            if ![
-                "is_empty",
-                "len",
-                "clone",
-                "unwrap",
-                "unwrap_or",
-                "unwrap_or_else",
-                "expect",
-                "map",
-                "map_err",
-                "and_then",
-                "or_else",
-                "filter",
-                "collect",
-                "to_string",
-                "to_str",
-                "parse",
-                "as_str",
-                "as_ref",
-                "display",
-                "send",
-                "await",
-                "lock",
-                "get",
-                "push",
-                "pop",
-                "clear",
-                "insert",
-                "contains_key",
-            ]
-            .contains(&method_name)
-            {
-                calls.insert(method_name.to_string());
-            }
-        }
+    let set_a: HashSet<&String> = reachable_a.iter().collect();
+    let set_b: HashSet<&String> = reachable_b.iter().collect();
 
-        // Look for function calls (function())
-        let call_regex = Regex::new(r"[^a-zA-Z0-9_\.]([a-zA-Z0-9_]+)\s*\(").unwrap();
-        for call_captures in call_regex.captures_iter(&fn_body) {
-            let called_function = call_captures.get(1).unwrap().as_str();
-
-            // Skip known keywords, macros, and builtins
-            if [
-                "if", "for", "while", "match", "return", "assert", "println", "panic", "format",
-                "print", "info", "error", "warn", "debug", "trace", "let", "break", "continue",
-                "loop", "async", "await", "move", "static", "const", "struct", "enum", "trait",
-                "impl", "type", "pub", "self", "map", "filter", "as", "is", "mut", "ref", "vec",
-                "super", "use", "extern", "spawn", "process", "eprintln", "unwrap",
-            ]
-            .contains(&called_function)
-            {
-                continue;
-            }
+    let only_a: Vec<&String> = reachable_a.iter().filter(|f| !set_b.contains(f)).collect();
+    let only_b: Vec<&String> = reachable_b.iter().filter(|f| !set_a.contains(f)).collect();
+    let shared: Vec<&String> = reachable_a.iter().filter(|f| set_b.contains(f)).collect();
 
-            calls.insert(called_function.to_string());
+    let mut output = String::new();
+    for (title, functions) in [
+        (
+            format!("Reachable only from A ({})", args.roots_a.join(", ")),
+            &only_a,
+        ),
+        (
+            format!("Reachable only from B ({})", args.roots_b.join(", ")),
+            &only_b,
+        ),
+        ("Reachable from both".to_string(), &shared),
+    ] {
+        output.push_str(&format!("{}: {} functions\n", title, functions.len()));
+        for name in functions {
+            let info = &index.function_definitions[*name];
+            output.push_str(&format!(
+                "  {} ({}:{})\n",
+                name,
+                info.path.to_string_lossy(),
+                info.line_number
+            ));
         }
+        output.push('\n');
+    }
 
-        // Look for AWS SDK builder pattern calls
-        let builder_regex = Regex::new(r"([a-zA-Z0-9_]+)\s*\(\s*\)").unwrap();
-        for builder_captures in builder_regex.captures_iter(&fn_body) {
-            let builder_fn = builder_captures.get(1).unwrap().as_str();
-            if ![
-                "Ok", "Err", "Some", "None", "Arc", "Vec", "HashMap", "HashSet", "String",
-            ]
-            .contains(&builder_fn)
-            {
-                calls.insert(builder_fn.to_string());
-            }
-        }
+    print!("{}", output);
+    Ok(())
+}
 
-        function_info.insert(
-            function_name.to_string(),
-            FunctionInfo {
-                path: path.to_path_buf(),
-                module_path: module_path.to_string(),
-                definition: fn_body,
-                line_number,
-                calls,
-            },
+/// `fuzz-prep <project_root> <function> [--module M]`: gathers what is
+/// needed to write a cargo-fuzz target for a function.
+fn run_fuzz_prep(args: cli::FuzzPrepArgs, interactive: bool) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let index = build_index(&project_root, &IndexOptions::default())?;
+    let selected_function =
+        select_function(&index, &args.function, args.module.as_ref(), interactive);
+    eprintln!("Selected function: {}", selected_function);
+
+    print!("{}", fuzz::render(&index, &selected_function));
+    Ok(())
+}
+
+/// `dataset <project_root> [--output file] [--documented-only]`: JSONL
+/// records of every public function for fine-tuning corpora.
+fn run_dataset(args: cli::DatasetArgs) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let index = build_index(&project_root, &IndexOptions::default())?;
+    let output = dataset::render(&index, args.documented_only);
+    eprintln!("Wrote {} records", output.lines().count());
+
+    if let Some(output_path) = &args.file {
+        atomic::write(output_path, &output)?;
+    } else {
+        print!("{}", output);
+    }
+    Ok(())
+}
+
+/// `api <project_root> <crate>`: the public surface of one crate of the
+/// project.
+fn run_api(args: cli::ApiArgs) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let members = cargo::workspace_members(&project_root);
+    let wanted = args.crate_name.replace('-', "_");
+    let given_dir = project_root.join(&args.crate_name);
+    let Some((name, crate_dir)) = members.iter().find(|(name, dir)| {
+        *name == wanted || dir.canonicalize().ok() == given_dir.canonicalize().ok()
+    }) else {
+        let names: Vec<&str> = members.iter().map(|(name, _)| name.as_str()).collect();
+        eprintln!(
+            "No crate '{}' in {}; crates: {}",
+            args.crate_name,
+            project_root.display(),
+            if names.is_empty() {
+                "none".to_string()
+            } else {
+                names.join(", ")
+            }
         );
+        process::exit(1);
+    };
+
+    // Every cfg variant keeps its condition, to tell apart their signatures
+    let options = IndexOptions {
+        all_variants: true,
+        ..index_options(&args.index, &project_root)
+    };
+    let index = build_index(&project_root, &options)?;
+    let briefing = api::render(&index, name, crate_dir);
+    eprintln!(
+        "{} public functions and {} public types in {}",
+        briefing.functions, briefing.types, name
+    );
+    if let Some(output_path) = &args.file {
+        atomic::write(output_path, &briefing.text)?;
+        println!("Output written to file");
+    } else {
+        print!("{}", briefing.text);
     }
+    Ok(())
+}
 
-    // Also extract struct/enum/type definitions
-    let type_regex =
-        Regex::new(r"(?m)^\s*(pub\s+)?(struct|enum|type|trait)\s+([a-zA-Z0-9_]+)").unwrap();
+/// `compose <fragment.json>... [--output file]`: merges gathers saved with
+/// `--json` into one document.
+fn run_compose(args: cli::ComposeArgs) -> io::Result<()> {
+    let fragments: Vec<GatherResult> = args
+        .fragments
+        .iter()
+        .map(|path| {
+            compose::load(path).unwrap_or_else(|err| {
+                eprintln!("Could not read fragment {}: {}", path.display(), err);
+                process::exit(1);
+            })
+        })
+        .collect();
+    let (functions, warnings) = compose::merge(&fragments);
+    for warning in &warnings {
+        eprintln!("{}", warning);
+    }
+    eprintln!(
+        "Composed {} functions from {} fragments",
+        functions.len(),
+        fragments.len()
+    );
 
-    for captures in type_regex.captures_iter(&content) {
-        let type_name = captures.get(3).unwrap().as_str();
-        types.insert(type_name.to_string());
+    let output = compose::render(&functions);
+    if let Some(output_path) = &args.file {
+        atomic::write(output_path, &output)?;
+    } else {
+        print!("{}", output);
     }
+    Ok(())
+}
 
-    Ok((function_info, types))
+/// `validate <project_root> [--sample N] [--rust-analyzer PATH]`: developer
+/// check of call-edge precision/recall against rust-analyzer.
+fn run_validate(args: cli::ValidateArgs) -> io::Result<()> {
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let index = build_index(&project_root, &IndexOptions::default())?;
+    let options = validate::ValidateOptions {
+        sample_size: args.sample_size,
+        rust_analyzer: args.rust_analyzer,
+    };
+
+    let (sample, candidates) = validate::sample(&index, &options);
+    eprintln!(
+        "Validating {} of {} Rust functions against {}",
+        sample.len(),
+        candidates,
+        options.rust_analyzer
+    );
+    let report = validate::run(&index, &project_root, &options, &sample).unwrap_or_else(|err| {
+        eprintln!("Validation failed: {}", err);
+        process::exit(1);
+    });
+    print!("{}", report);
+    Ok(())
 }
 
-fn print_help() {
-    println!("Function Context Analyzer - Extract function call trees from Rust projects");
-    println!("\nUSAGE:");
-    println!("  context-analyzer <project_root> <function_name> [preferred_module] [output_file]");
-    println!("\nARGUMENTS:");
-    println!("  <project_root>     Path to the Rust project root directory");
-    println!("  <function_name>    Name of the function to analyze");
-    println!("  [preferred_module] Optional module name to disambiguate functions");
-    println!("  [output_file]      Optional output file path (defaults to stdout)");
-    println!("\nEXAMPLES:");
-    println!("  context-analyzer ./my-project process_queue transform_writer output.txt");
-    println!("  context-analyzer ./my-project main");
+/// `search <project_root> <regex> [--bodies]`: lists matching functions by
+/// qualified name.
+fn run_search(args: cli::SearchArgs) -> io::Result<()> {
+    let pattern = Regex::new(&args.pattern).unwrap_or_else(|err| {
+        eprintln!("Invalid search pattern: {}", err);
+        process::exit(1);
+    });
+    let project_root = resolve_root_or_exit(&args.project_root);
+    let index = build_index(&project_root, &IndexOptions::default())?;
+    let output = search::render(&index, &pattern, args.bodies);
+    eprintln!("{} matching functions", output.lines().count());
+    print!("{}", output);
+    Ok(())
 }
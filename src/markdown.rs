@@ -0,0 +1,54 @@
+//! `--format markdown`: the gathered functions as Markdown, each in a
+//! language-tagged code fence under a `### name (path:line)` heading, ready
+//! to paste into a chat or a pull request description.
+
+use crate::module_docs::ModuleDoc;
+use crate::{annotations, code_fence, FunctionInfo};
+use std::collections::{HashMap, HashSet};
+
+/// Renders the gathered functions, introducing each module that has
+/// documentation with that document.
+pub fn render(
+    gathered_names: &[String],
+    gathered: &[&FunctionInfo],
+    module_docs: &HashMap<String, ModuleDoc>,
+) -> String {
+    let mut documented = HashSet::new();
+    let mut output = String::new();
+    for (name, info) in gathered_names.iter().zip(gathered) {
+        if let Some(doc) = module_docs.get(&info.module_path) {
+            if documented.insert(&info.module_path) {
+                output.push_str(&format!(
+                    "\n## Module {} ({})\n\n{}\n",
+                    info.module_path,
+                    doc.path.to_string_lossy(),
+                    doc.text.trim()
+                ));
+            }
+        }
+        let fence = code_fence(&info.definition);
+        output.push_str(&format!(
+            "\n### {} ({}:{}){}\n\n{}{}\n{}\n{}\n",
+            name,
+            info.path.to_string_lossy(),
+            info.line_number,
+            annotations(info),
+            fence,
+            info.language.fence(),
+            info.definition,
+            fence
+        ));
+    }
+    output
+}
+
+/// A plain-text section (glossary, type definitions, stubs) as a fenced
+/// block under a heading; empty when the section is.
+pub fn section(title: &str, text: &str) -> String {
+    let text = text.trim_matches('\n');
+    if text.is_empty() {
+        return String::new();
+    }
+    let fence = code_fence(text);
+    format!("\n## {}\n\n{}\n{}\n{}\n", title, fence, text, fence)
+}
@@ -0,0 +1,82 @@
+//! `--module-docs`: architectural prose that lives next to the code, either
+//! a `README.md` in the module's directory or a markdown file pulled in with
+//! `#[doc = include_str!("...")]`.
+
+use crate::{FunctionInfo, Language};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longer documents are cut here; the start of a README usually carries the
+/// overview that matters.
+const MAX_LINES: usize = 60;
+
+#[derive(Debug, Clone)]
+pub struct ModuleDoc {
+    pub path: PathBuf,
+    pub text: String,
+}
+
+/// The documentation of every Rust module the gathered functions live in,
+/// keyed by module path.
+pub fn collect(gathered: &[&FunctionInfo]) -> HashMap<String, ModuleDoc> {
+    let include_regex =
+        Regex::new(r#"#!?\[doc\s*=\s*include_str!\(\s*"([^"]+)"\s*\)\s*\]"#).unwrap();
+
+    let mut docs = HashMap::new();
+    for info in gathered {
+        if info.language != Language::Rust || docs.contains_key(&info.module_path) {
+            continue;
+        }
+        if let Some(path) = doc_file(&info.path, &include_regex) {
+            if let Ok(text) = fs::read_to_string(&path) {
+                docs.insert(
+                    info.module_path.clone(),
+                    ModuleDoc {
+                        path,
+                        text: truncate(&text),
+                    },
+                );
+            }
+        }
+    }
+    docs
+}
+
+/// An `include_str!` doc in the file wins over a README, since the author
+/// chose it explicitly.
+fn doc_file(source: &Path, include_regex: &Regex) -> Option<PathBuf> {
+    let dir = source.parent()?;
+
+    if let Ok(content) = fs::read_to_string(source) {
+        if let Some(captures) = include_regex.captures(&content) {
+            let included = dir.join(&captures[1]);
+            if included.is_file() {
+                return Some(included);
+            }
+        }
+    }
+
+    // `foo/mod.rs` and `lib.rs` own their directory; `foo.rs` owns `foo/`
+    let stem = source.file_stem()?.to_str()?;
+    let module_dir = if matches!(stem, "mod" | "lib" | "main") {
+        dir.to_path_buf()
+    } else {
+        dir.join(stem)
+    };
+    let readme = module_dir.join("README.md");
+    readme.is_file().then_some(readme)
+}
+
+fn truncate(text: &str) -> String {
+    let lines: Vec<&str> = text.trim().lines().collect();
+    if lines.len() <= MAX_LINES {
+        return lines.join("\n");
+    }
+    format!(
+        "{}\n[... {} more lines in the file]",
+        lines[..MAX_LINES].join("\n"),
+        lines.len() - MAX_LINES
+    )
+}
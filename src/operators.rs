@@ -0,0 +1,103 @@
+//! `--operators`: call edges hidden behind operator sugar. `a + b` runs
+//! `Add::add` and `map[key]` runs `Index::index`, but neither names the
+//! method, so without type inference the best available signal is a
+//! function that both mentions a project type and uses an operator that
+//! type overloads.
+
+use crate::index::{body, ProjectIndex};
+use crate::Language;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+/// Overloadable traits, the method they provide, and the sugar that invokes
+/// it. Binary operators must follow an operand so `-1` and `*ptr` are not
+/// read as subtraction or multiplication.
+const OPERATOR_TRAITS: &[(&str, &str, &str)] = &[
+    ("Add", "add", r"[\w)\]]\s*\+[^=+]"),
+    ("Sub", "sub", r"[\w)\]]\s*-[^=>]"),
+    ("Mul", "mul", r"[\w)\]]\s*\*[^=]"),
+    ("Div", "div", r"[\w)\]]\s*/[^=/*]"),
+    ("Rem", "rem", r"[\w)\]]\s*%[^=]"),
+    ("AddAssign", "add_assign", r"\+="),
+    ("SubAssign", "sub_assign", r"-="),
+    ("MulAssign", "mul_assign", r"\*="),
+    ("DivAssign", "div_assign", r"/="),
+    ("RemAssign", "rem_assign", r"%="),
+    ("Neg", "neg", r"(?m)(^|[=(,\[{;]|\breturn)\s*-\s*[\w(]"),
+    ("Not", "not", r"(^|[^\w!)\]])!\s*[\w(]"),
+    ("PartialEq", "eq", r"[=!]="),
+    ("Index", "index", r"[\w)\]]\["),
+    ("IndexMut", "index_mut", r"[\w)\]]\["),
+    (
+        "Deref",
+        "deref",
+        r"(?m)(^|[=(,\[{;!&|]|\breturn)\s*\*\s*[\w(]",
+    ),
+    (
+        "DerefMut",
+        "deref_mut",
+        r"(?m)(^|[=(,\[{;!&|]|\breturn)\s*\*\s*[\w(]",
+    ),
+];
+
+/// Operator impl methods each Rust function (or `only` the given ones)
+/// implicitly calls, keyed by the calling function's qualified name.
+pub fn operator_calls(
+    index: &ProjectIndex,
+    only: Option<&HashSet<String>>,
+) -> HashMap<String, Vec<String>> {
+    let usages: Vec<(&str, &str, Regex)> = OPERATOR_TRAITS
+        .iter()
+        .map(|(trait_name, method, pattern)| (*trait_name, *method, Regex::new(pattern).unwrap()))
+        .collect();
+    let noise = Regex::new(r#"//[^\n]*|"(\\.|[^"\\])*""#).unwrap();
+
+    let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+    for (name, info) in &index.function_definitions {
+        if info.language != Language::Rust || only.is_some_and(|only| !only.contains(name)) {
+            continue;
+        }
+        let body = body(&info.definition);
+        if body.is_empty() {
+            continue;
+        }
+        // Comments and string literals are full of operator-like characters
+        let body = noise.replace_all(body, " ");
+
+        let mut type_names: Vec<&str> = info
+            .definition
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|word| index.type_definitions.contains_key(*word))
+            .collect();
+        type_names.sort_unstable();
+        type_names.dedup();
+
+        for type_name in type_names {
+            for impl_info in index.impls.get(type_name).into_iter().flatten() {
+                let Some(trait_name) = &impl_info.trait_name else {
+                    continue;
+                };
+                for (_, method, usage) in usages.iter().filter(|(t, _, _)| t == trait_name) {
+                    if !usage.is_match(&body) {
+                        continue;
+                    }
+                    // The method defined inside this particular impl block
+                    let target = index.module_functions.get(*method).and_then(|variants| {
+                        variants.iter().find(|(qualified_name, _)| {
+                            let method_info = &index.function_definitions[qualified_name];
+                            method_info.path == impl_info.path
+                                && impl_info.line_number < method_info.line_number
+                                && method_info.line_number <= impl_info.end_line
+                        })
+                    });
+                    if let Some((target, _)) = target {
+                        if target != name {
+                            edges.entry(name.clone()).or_default().push(target.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    edges
+}
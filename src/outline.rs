@@ -0,0 +1,118 @@
+//! `--outline`: a table of contents ahead of the code, listing the gathered
+//! functions by file with their call depth and size, so the shape of the
+//! context is clear before reading any body.
+
+use crate::index::ProjectIndex;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Call distance of each gathered function from the nearest of `roots`,
+/// following only edges between gathered functions, backwards for a
+/// `callers` gather. Functions no root reaches (a type or doc seed's
+/// matches) have no depth.
+pub fn depths(
+    index: &ProjectIndex,
+    roots: &[String],
+    gathered_names: &[String],
+    callers: bool,
+) -> HashMap<String, usize> {
+    let gathered: HashSet<&String> = gathered_names.iter().collect();
+    let mut edges: HashMap<&String, Vec<&String>> = HashMap::new();
+    for caller in gathered_names {
+        for callee in index.function_calls.get(caller).into_iter().flatten() {
+            if !gathered.contains(callee) {
+                continue;
+            }
+            match callers {
+                true => edges.entry(callee).or_default().push(caller),
+                false => edges.entry(caller).or_default().push(callee),
+            }
+        }
+    }
+
+    let mut depths = HashMap::new();
+    let mut queue = VecDeque::new();
+    for root in roots {
+        if gathered.contains(root) && !depths.contains_key(root) {
+            depths.insert(root.clone(), 0);
+            queue.push_back(root);
+        }
+    }
+    while let Some(current) = queue.pop_front() {
+        let depth = depths[current];
+        for next in edges.get(current).into_iter().flatten() {
+            if !depths.contains_key(*next) {
+                depths.insert((*next).clone(), depth + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    depths
+}
+
+/// Records `depths` on the index for the output headers, clearing those of
+/// an earlier gather (`--watch` reuses the index).
+pub fn annotate(index: &mut ProjectIndex, depths: &HashMap<String, usize>) {
+    for (name, info) in index.function_definitions.iter_mut() {
+        info.depth = depths.get(name).copied();
+    }
+}
+
+/// The table of contents: each file in order of its first gathered
+/// function, with its function and line counts, then its functions in
+/// output order with their depth, first line and length.
+pub fn render(
+    index: &ProjectIndex,
+    gathered_names: &[String],
+    depths: &HashMap<String, usize>,
+) -> String {
+    let mut files: Vec<(String, Vec<&String>)> = Vec::new();
+    for name in gathered_names {
+        let path = index.function_definitions[name]
+            .path
+            .to_string_lossy()
+            .into_owned();
+        match files.iter_mut().find(|(file, _)| *file == path) {
+            Some((_, names)) => names.push(name),
+            None => files.push((path, vec![name])),
+        }
+    }
+    let lines = |name: &String| index.function_definitions[name].definition.lines().count();
+
+    let total: usize = gathered_names.iter().map(lines).sum();
+    let mut output = format!(
+        "{}, {} in {}\n",
+        count(gathered_names.len(), "function"),
+        count(total, "line"),
+        count(files.len(), "file")
+    );
+    for (file, names) in &files {
+        let file_lines: usize = names.iter().map(|name| lines(name)).sum();
+        output.push_str(&format!(
+            "{} ({}, {})\n",
+            file,
+            count(names.len(), "function"),
+            count(file_lines, "line")
+        ));
+        for name in names {
+            let depth = depths
+                .get(*name)
+                .map_or("-".to_string(), |depth| depth.to_string());
+            output.push_str(&format!(
+                "  [{}] {} (line {}, {})\n",
+                depth,
+                name,
+                index.function_definitions[*name].line_number,
+                count(lines(name), "line")
+            ));
+        }
+    }
+    output
+}
+
+/// `n` of `noun`, plural unless there is one.
+pub(crate) fn count(n: usize, noun: &str) -> String {
+    match n {
+        1 => format!("1 {}", noun),
+        n => format!("{} {}s", n, noun),
+    }
+}
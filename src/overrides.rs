@@ -0,0 +1,100 @@
+//! `gather-overrides.toml`: committed corrections for calls the name-based
+//! resolution gets wrong, so nobody has to re-pin them on every run.
+//!
+//! ```toml
+//! # Every call to `process` goes to the queue worker's
+//! [calls]
+//! process = "queue::worker::process"
+//!
+//! # ...except the ones made by this function
+//! [sites."api::handlers::submit"]
+//! process = "queue::inline::process"
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const FILE_NAME: &str = "gather-overrides.toml";
+
+#[derive(Debug, Default)]
+pub struct Overrides {
+    /// Call name to target, for every caller
+    calls: HashMap<String, String>,
+    /// Caller's qualified name to its own call name to target mapping
+    sites: HashMap<String, HashMap<String, String>>,
+}
+
+impl Overrides {
+    /// Reads the overrides committed at the project root, if there are any.
+    pub fn load(project_root: &Path) -> io::Result<Option<Overrides>> {
+        let path = project_root.join(FILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let invalid = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), message),
+            )
+        };
+        let table: toml::Table = fs::read_to_string(&path)?
+            .parse()
+            .map_err(|err: toml::de::Error| invalid(err.to_string()))?;
+
+        let targets = |value: &toml::Value, section: &str| {
+            let table = value
+                .as_table()
+                .ok_or_else(|| invalid(format!("[{}] must be a table", section)))?;
+            table
+                .iter()
+                .map(|(name, target)| match target.as_str() {
+                    Some(target) => Ok((name.clone(), target.to_string())),
+                    None => Err(invalid(format!(
+                        "[{}] {} must be a \"module::function\" string",
+                        section, name
+                    ))),
+                })
+                .collect::<io::Result<HashMap<String, String>>>()
+        };
+
+        let mut overrides = Overrides::default();
+        for (section, value) in &table {
+            match section.as_str() {
+                "calls" => overrides.calls = targets(value, "calls")?,
+                "sites" => {
+                    let sites = value
+                        .as_table()
+                        .ok_or_else(|| invalid("[sites] must be a table".to_string()))?;
+                    for (caller, calls) in sites {
+                        let section = format!("sites.\"{}\"", caller);
+                        overrides
+                            .sites
+                            .insert(caller.clone(), targets(calls, &section)?);
+                    }
+                }
+                _ => return Err(invalid(format!("unknown section [{}]", section))),
+            }
+        }
+        Ok(Some(overrides))
+    }
+
+    /// The pinned target of `called_fn` when made from `caller`; call-site
+    /// pins win over name-wide ones.
+    pub fn target(&self, caller: &str, called_fn: &str) -> Option<&str> {
+        self.sites
+            .get(caller)
+            .and_then(|calls| calls.get(called_fn))
+            .or_else(|| self.calls.get(called_fn))
+            .map(String::as_str)
+    }
+
+    /// Every pinned target, for checking that they still exist.
+    pub fn targets(&self) -> impl Iterator<Item = &String> {
+        self.calls
+            .values()
+            .chain(self.sites.values().flat_map(HashMap::values))
+    }
+}
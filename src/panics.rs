@@ -0,0 +1,71 @@
+//! `--panics`: every `panic!`, `unwrap`, `expect`, `todo!` and similar
+//! failure point in the gathered Rust code, listed with its location and
+//! message, since they are easy to miss in long bodies and are often what
+//! the question is about.
+
+use crate::lang::call_arguments;
+use crate::{FunctionInfo, Language};
+use regex::Regex;
+
+/// The failure point section to append after the code; empty when the
+/// gathered functions have none.
+pub fn render(gathered_names: &[String], gathered: &[&FunctionInfo]) -> String {
+    let site_regex =
+        Regex::new(r"\b(panic|todo|unimplemented|unreachable)!\s*\(|\.(unwrap|expect)\s*\(")
+            .unwrap();
+
+    let mut sections = Vec::new();
+    for (name, info) in gathered_names
+        .iter()
+        .zip(gathered)
+        .filter(|(_, info)| info.language == Language::Rust)
+    {
+        let definition = &info.definition;
+        let mut sites = Vec::new();
+        for captures in site_regex.captures_iter(definition) {
+            let whole = captures.get(0).unwrap();
+            let line_start = definition[..whole.start()].rfind('\n').map_or(0, |i| i + 1);
+            if definition[line_start..whole.start()].contains("//") {
+                continue;
+            }
+            let line_end = definition[whole.start()..]
+                .find('\n')
+                .map_or(definition.len(), |i| whole.start() + i);
+
+            let (kind, is_macro) = match captures.get(1) {
+                Some(name) => (format!("{}!", name.as_str()), true),
+                None => (captures[2].to_string(), false),
+            };
+            let message = call_arguments(&definition[whole.end()..])
+                .first()
+                .map(|argument| one_line(argument))
+                .filter(|argument| !argument.is_empty());
+            // A bare `unwrap()` or `todo!()` is explained by the code around it
+            let detail = match message {
+                Some(message) if is_macro || kind == "expect" => message,
+                _ => definition[line_start..line_end].trim().to_string(),
+            };
+
+            let line_number = info.line_number + definition[..whole.start()].matches('\n').count();
+            sites.push(format!(
+                "  {}:{}  {:<14} {}\n",
+                info.path.to_string_lossy(),
+                line_number,
+                kind,
+                detail
+            ));
+        }
+        if !sites.is_empty() {
+            sections.push(format!("\n{}\n{}", name, sites.concat()));
+        }
+    }
+
+    if sections.is_empty() {
+        return String::new();
+    }
+    format!("\n=== PANIC POINTS ===\n{}\n", sections.concat())
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,294 @@
+//! Rust function extraction from a real syntax tree (`syn`), used by the
+//! Rust backend whenever a file parses. Unlike line regexes it sees trait
+//! default methods, functions nested in other bodies, and signatures whose
+//! `where` clauses or parameters span several lines, and it attributes each
+//! call to the innermost function containing it. Methods carry the type
+//! whose `impl` block (or the trait whose definition) they are written in.
+//!
+//! The parser only reports syntax; which calls are worth recording is left
+//! to the backend, so both extraction paths share one policy.
+
+use proc_macro2::{Delimiter, LineColumn, TokenStream, TokenTree};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Block, Expr, Signature, Token, Type, Visibility};
+
+/// A function definition with its location in the source text.
+#[derive(Debug, Clone)]
+pub struct ParsedFunction {
+    pub name: String,
+    /// Simple name of the type of the enclosing `impl` block, or of the
+    /// enclosing trait for default methods
+    pub self_type: Option<String>,
+    /// Byte offset of the start of the line the signature begins on
+    pub start: usize,
+    /// Byte offset just past the closing brace (or `;` of an `extern`
+    /// declaration)
+    pub end: usize,
+    pub calls: Vec<Call>,
+}
+
+/// One call site in a function body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Call {
+    /// `receiver.name(...)`, with the arguments that are plain paths
+    /// (`.map(parse)`, `.and_then(Self::check)`) by path segment
+    Method {
+        name: String,
+        path_arguments: Vec<Vec<String>>,
+        /// The receiver is `self`
+        on_self: bool,
+    },
+    /// `name(...)` or `path::to::name(...)`, by path segment
+    Path(Vec<String>),
+    /// `name!(...)`
+    Macro(String),
+}
+
+/// The functions defined in `content`, or `None` when it doesn't parse (for
+/// example nightly-only syntax), in which case the caller falls back to
+/// regex extraction.
+pub fn parse_functions(content: &str) -> Option<Vec<ParsedFunction>> {
+    let parsed = syn::parse_file(content);
+    let functions = parsed.ok().map(|file| {
+        let mut collector = FunctionCollector {
+            content,
+            line_starts: std::iter::once(0)
+                .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+            functions: Vec::new(),
+            self_types: Vec::new(),
+        };
+        collector.visit_file(&file);
+        collector.functions
+    });
+    // Span locations are kept per thread until released
+    proc_macro2::extra::invalidate_current_thread_spans();
+    functions
+}
+
+struct FunctionCollector<'a> {
+    content: &'a str,
+    line_starts: Vec<usize>,
+    functions: Vec<ParsedFunction>,
+    /// Enclosing impl or trait types, innermost last; `None` inside a free
+    /// function, whose nested functions are not methods
+    self_types: Vec<Option<String>>,
+}
+
+impl FunctionCollector<'_> {
+    fn offset(&self, location: LineColumn) -> usize {
+        let line_start = self.line_starts[location.line - 1];
+        self.content[line_start..]
+            .char_indices()
+            .nth(location.column)
+            .map_or(self.content.len(), |(i, _)| line_start + i)
+    }
+
+    fn push(
+        &mut self,
+        vis: Option<&Visibility>,
+        sig: &Signature,
+        block: Option<&Block>,
+        end: LineColumn,
+        method: bool,
+    ) {
+        let start = match vis {
+            Some(Visibility::Inherited) | None => sig.span().start(),
+            Some(vis) => vis.span().start(),
+        };
+        let mut calls = CallCollector::default();
+        if let Some(block) = block {
+            calls.visit_block(block);
+        }
+        let self_type = if method {
+            self.self_types.last().cloned().flatten()
+        } else {
+            None
+        };
+        self.functions.push(ParsedFunction {
+            name: sig.ident.to_string(),
+            self_type,
+            start: self.line_starts[start.line - 1],
+            end: self.offset(end),
+            calls: calls.calls,
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for FunctionCollector<'_> {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        let end = item.block.brace_token.span.close().end();
+        self.push(Some(&item.vis), &item.sig, Some(&item.block), end, false);
+        self.self_types.push(None);
+        visit::visit_item_fn(self, item);
+        self.self_types.pop();
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        self.self_types.push(type_name(&item.self_ty));
+        visit::visit_item_impl(self, item);
+        self.self_types.pop();
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast syn::ItemTrait) {
+        self.self_types.push(Some(item.ident.to_string()));
+        visit::visit_item_trait(self, item);
+        self.self_types.pop();
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        let end = item.block.brace_token.span.close().end();
+        self.push(Some(&item.vis), &item.sig, Some(&item.block), end, true);
+        self.self_types.push(None);
+        visit::visit_impl_item_fn(self, item);
+        self.self_types.pop();
+    }
+
+    fn visit_trait_item_fn(&mut self, item: &'ast syn::TraitItemFn) {
+        // Required methods are kept as their declaration, which calls
+        // through the trait reach
+        if let Some(block) = &item.default {
+            let end = block.brace_token.span.close().end();
+            self.push(None, &item.sig, Some(block), end, true);
+        } else if let Some(semi) = &item.semi_token {
+            self.push(None, &item.sig, None, semi.span.end(), true);
+        }
+        self.self_types.push(None);
+        visit::visit_trait_item_fn(self, item);
+        self.self_types.pop();
+    }
+
+    fn visit_foreign_item_fn(&mut self, item: &'ast syn::ForeignItemFn) {
+        let end = item.semi_token.span.end();
+        self.push(Some(&item.vis), &item.sig, None, end, false);
+    }
+}
+
+/// Collects the calls in one body, stopping at nested items, which are
+/// definitions of their own.
+#[derive(Default)]
+struct CallCollector {
+    calls: Vec<Call>,
+}
+
+impl<'ast> Visit<'ast> for CallCollector {
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let Expr::Path(path) = &*call.func {
+            let segments = path
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect();
+            self.calls.push(Call::Path(segments));
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let path_arguments = call
+            .args
+            .iter()
+            .filter_map(|argument| match argument {
+                Expr::Path(path) => Some(
+                    path.path
+                        .segments
+                        .iter()
+                        .map(|segment| segment.ident.to_string())
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .collect();
+        let on_self = matches!(&*call.receiver, Expr::Path(path) if path.path.is_ident("self"));
+        self.calls.push(Call::Method {
+            name: call.method.to_string(),
+            path_arguments,
+            on_self,
+        });
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if let Some(segment) = mac.path.segments.last() {
+            self.calls.push(Call::Macro(segment.ident.to_string()));
+        }
+        // Most macros take expressions (`vec![..]`, `format!(..)`,
+        // `assert_eq!(..)`), whose calls count too. Others (`json!({..})`)
+        // get a token-level scan.
+        let arguments = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated);
+        match arguments {
+            Ok(arguments) => {
+                for argument in &arguments {
+                    self.visit_expr(argument);
+                }
+            }
+            Err(_) => self.scan_tokens(mac.tokens.clone()),
+        }
+    }
+}
+
+impl CallCollector {
+    /// Records `name(..)`, `path::name(..)` and `.name(..)` in tokens that
+    /// don't parse as expressions.
+    fn scan_tokens(&mut self, tokens: TokenStream) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        for (i, token) in tokens.iter().enumerate() {
+            let TokenTree::Group(group) = token else {
+                continue;
+            };
+            if group.delimiter() == Delimiter::Parenthesis && i > 0 {
+                if let TokenTree::Ident(name) = &tokens[i - 1] {
+                    self.calls
+                        .push(token_call(&tokens[..i - 1], name.to_string()));
+                }
+            }
+            self.scan_tokens(group.stream());
+        }
+    }
+}
+
+/// The call `name` makes given the tokens before it: a method call after
+/// `.`, otherwise a path call with any `segment ::` qualifiers.
+fn token_call(before: &[TokenTree], name: String) -> Call {
+    if matches!(before.last(), Some(TokenTree::Punct(punct)) if punct.as_char() == '.') {
+        let on_self = matches!(before, [.., TokenTree::Ident(receiver), _] if receiver == "self");
+        return Call::Method {
+            name,
+            path_arguments: Vec::new(),
+            on_self,
+        };
+    }
+    let mut segments = vec![name];
+    let mut rest = before;
+    while let [head @ .., TokenTree::Ident(qualifier), TokenTree::Punct(first), TokenTree::Punct(second)] =
+        rest
+    {
+        if first.as_char() != ':' || second.as_char() != ':' {
+            break;
+        }
+        segments.insert(0, qualifier.to_string());
+        rest = head;
+    }
+    Call::Path(segments)
+}
+
+/// Simple name of an impl's self type: `Foo` for `Foo<T>`, `crate::a::Foo`
+/// or `&mut Foo`. `None` for tuples, slices and other unnamed types.
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        Type::Reference(reference) => type_name(&reference.elem),
+        Type::Paren(paren) => type_name(&paren.elem),
+        Type::Group(group) => type_name(&group.elem),
+        _ => None,
+    }
+}
@@ -0,0 +1,124 @@
+//! Built-in prompt presets that frame the gathered context for common LLM
+//! tasks.
+
+use crate::index::ProjectIndex;
+use crate::module_docs::ModuleDoc;
+use crate::{render_output, render_with_module_docs, FunctionInfo, TypeInfo};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptPreset {
+    Review,
+    Refactor,
+    TestGen,
+    Explain,
+}
+
+impl PromptPreset {
+    pub const NAMES: &'static [&'static str] = &["review", "refactor", "test-gen", "explain"];
+
+    pub fn parse(name: &str) -> Option<PromptPreset> {
+        match name {
+            "review" => Some(PromptPreset::Review),
+            "refactor" => Some(PromptPreset::Refactor),
+            "test-gen" => Some(PromptPreset::TestGen),
+            "explain" => Some(PromptPreset::Explain),
+            _ => None,
+        }
+    }
+
+    fn instructions(self) -> &'static str {
+        match self {
+            PromptPreset::Review => {
+                "Review the code below. The first function is the entry point under review; \
+                 the functions after it are everything it calls, directly or transitively. \
+                 Look for bugs, unhandled errors, panics, incorrect edge-case handling, and \
+                 unclear logic. For each finding, name the function and file, explain the \
+                 problem, and suggest a concrete fix. Do not comment on style alone."
+            }
+            PromptPreset::Refactor => {
+                "Refactor the entry point function below and, where it helps, the functions \
+                 it calls. Preserve the observable behavior exactly. Reduce duplication, \
+                 simplify control flow, and improve naming where it is misleading. Show the \
+                 complete updated functions and explain each change briefly."
+            }
+            PromptPreset::TestGen => {
+                "Write unit tests for the entry point function below. Its callees, the \
+                 existing tests that exercise this code, and the type definitions used in \
+                 the signatures are included for reference. Follow the style of the existing \
+                 tests, cover the happy path, error paths, and edge cases, and only use \
+                 functions and types that appear in this context."
+            }
+            PromptPreset::Explain => {
+                "Explain what the entry point function below does. Walk through its control \
+                 flow step by step, describing what each function it calls contributes. \
+                 Point out side effects, error handling, and any non-obvious assumptions."
+            }
+        }
+    }
+}
+
+/// Prepends the preset's instructions to the gathered context, adding the
+/// extra sections the task needs, the `prelude` sections (glossary,
+/// manifest) ahead of the code, and module documentation within it.
+pub fn render(
+    preset: PromptPreset,
+    index: &ProjectIndex,
+    gathered_names: &[String],
+    gathered: &[&FunctionInfo],
+    prelude: &[(&str, String)],
+    module_docs: &HashMap<String, ModuleDoc>,
+) -> String {
+    let mut output = String::new();
+
+    output.push_str(preset.instructions());
+    output.push_str("\n\n");
+
+    if let (Some(name), Some(target)) = (gathered_names.first(), gathered.first()) {
+        output.push_str(&format!(
+            "Entry point: {} ({}:{})\n",
+            name,
+            target.path.to_string_lossy(),
+            target.line_number
+        ));
+    }
+
+    for (title, section) in prelude {
+        output.push_str(&format!("\n----- {} -----\n", title));
+        output.push_str(section);
+    }
+
+    output.push_str("\n----- CODE -----\n");
+    output.push_str(&render_with_module_docs(gathered, module_docs));
+
+    if preset == PromptPreset::TestGen {
+        let tests: Vec<&FunctionInfo> = index
+            .tests_calling(gathered_names)
+            .iter()
+            .map(|name| &index.function_definitions[name])
+            .collect();
+
+        output.push_str("\n----- EXISTING TESTS -----\n");
+        if tests.is_empty() {
+            output.push_str("\n(no existing tests call these functions)\n");
+        } else {
+            output.push_str(&render_output(&tests));
+        }
+
+        let mut seen = HashSet::new();
+        let types: Vec<&TypeInfo> = gathered
+            .iter()
+            .flat_map(|info| index.signature_types(info))
+            .filter(|t| seen.insert((&t.path, t.line_number)))
+            .collect();
+
+        output.push_str("\n----- TYPE DEFINITIONS -----\n");
+        for type_info in types {
+            output.push_str(&format!("\n=== {} ===\n", type_info.path.to_string_lossy()));
+            output.push_str(&type_info.definition);
+            output.push_str("\n\n");
+        }
+    }
+
+    output
+}
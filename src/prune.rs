@@ -0,0 +1,252 @@
+//! `--interactive-prune`: the gathered functions as a call tree in the
+//! terminal, with a checkbox each, so whole subtrees can be deselected
+//! before the output is written instead of iterating on exclude flags.
+
+use crate::index::ProjectIndex;
+use crate::outline::count;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Terminal;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+
+/// One function in the tree, under the gathered function that first
+/// reached it.
+struct Node {
+    name: String,
+    depth: usize,
+    lines: usize,
+    /// Index of the node's last descendant, inclusive; the subtree is the
+    /// node and the ones after it up to there
+    subtree_end: usize,
+    selected: bool,
+    /// Whether the user toggled this node itself, rather than a subtree
+    /// holding it
+    toggled: bool,
+}
+
+/// What the user kept of the gathered functions.
+pub struct Pruned {
+    /// The functions kept, in their gathered order
+    pub kept: Vec<String>,
+    /// The functions the user deselected themselves, leaving out the rest
+    /// of their subtrees
+    pub deselected: Vec<String>,
+}
+
+/// The call tree of `gathered_names` from `roots`, in depth-first order:
+/// each function under the first caller that reached it in a breadth-first
+/// walk (callee to caller for a `callers` gather). Gathered functions no
+/// root reaches follow as trees of their own.
+fn tree(
+    index: &ProjectIndex,
+    roots: &[String],
+    gathered_names: &[String],
+    callers: bool,
+) -> Vec<Node> {
+    let gathered: HashSet<&String> = gathered_names.iter().collect();
+    let mut edges: HashMap<&String, Vec<&String>> = HashMap::new();
+    for caller in gathered_names {
+        let mut callees: Vec<&String> = index
+            .function_calls
+            .get(caller)
+            .into_iter()
+            .flatten()
+            .filter(|callee| gathered.contains(callee))
+            .collect();
+        callees.sort();
+        for callee in callees {
+            match callers {
+                true => edges.entry(callee).or_default().push(caller),
+                false => edges.entry(caller).or_default().push(callee),
+            }
+        }
+    }
+
+    // Breadth-first, so each function hangs under its closest caller
+    let mut children: HashMap<&String, Vec<&String>> = HashMap::new();
+    let mut reached: HashSet<&String> = HashSet::new();
+    let mut tops: Vec<&String> = Vec::new();
+    let starts = roots
+        .iter()
+        .filter(|root| gathered.contains(root))
+        .chain(gathered_names);
+    for start in starts {
+        if !reached.insert(start) {
+            continue;
+        }
+        tops.push(start);
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            for next in edges.get(current).into_iter().flatten() {
+                if reached.insert(next) {
+                    children.entry(current).or_default().push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut stack: Vec<(&String, usize)> = tops.into_iter().rev().map(|top| (top, 0)).collect();
+    // Where each open subtree starts, closed when the walk leaves it
+    let mut open: Vec<usize> = Vec::new();
+    while let Some((name, depth)) = stack.pop() {
+        while open
+            .last()
+            .is_some_and(|&start| nodes[start].depth >= depth)
+        {
+            let start = open.pop().unwrap();
+            nodes[start].subtree_end = nodes.len() - 1;
+        }
+        open.push(nodes.len());
+        nodes.push(Node {
+            name: name.clone(),
+            depth,
+            lines: index.function_definitions[name].definition.lines().count(),
+            subtree_end: 0,
+            selected: true,
+            toggled: false,
+        });
+        for child in children.get(name).into_iter().flatten().rev() {
+            stack.push((child, depth + 1));
+        }
+    }
+    for start in open {
+        nodes[start].subtree_end = nodes.len() - 1;
+    }
+    nodes
+}
+
+/// Shows the tree of `gathered_names` and lets the user deselect functions
+/// and their subtrees. Returns what was kept, or `None` when the user quits
+/// without confirming.
+pub fn run(
+    index: &ProjectIndex,
+    roots: &[String],
+    gathered_names: &[String],
+    callers: bool,
+) -> io::Result<Option<Pruned>> {
+    let mut nodes = tree(index, roots, gathered_names, callers);
+    if nodes.is_empty() {
+        return Ok(Some(Pruned {
+            kept: Vec::new(),
+            deselected: Vec::new(),
+        }));
+    }
+
+    // Drawn on stderr, so the output can still be redirected
+    enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+    let confirmed = select(&mut terminal, &mut nodes);
+    disable_raw_mode()?;
+    execute!(io::stderr(), LeaveAlternateScreen)?;
+
+    if !confirmed? {
+        return Ok(None);
+    }
+    let kept: HashSet<&String> = nodes
+        .iter()
+        .filter(|node| node.selected)
+        .map(|node| &node.name)
+        .collect();
+    Ok(Some(Pruned {
+        kept: gathered_names
+            .iter()
+            .filter(|name| kept.contains(name))
+            .cloned()
+            .collect(),
+        deselected: nodes
+            .iter()
+            .filter(|node| node.toggled && !node.selected)
+            .map(|node| node.name.clone())
+            .collect(),
+    }))
+}
+
+/// The selection loop: whether the user confirmed.
+fn select(
+    terminal: &mut Terminal<CrosstermBackend<io::Stderr>>,
+    nodes: &mut [Node],
+) -> io::Result<bool> {
+    let mut state = ListState::default().with_selected(Some(0));
+    loop {
+        terminal.draw(|frame| {
+            let [list_area, help_area] =
+                Layout::vertical([Constraint::Min(1), Constraint::Length(2)]).areas(frame.area());
+            let items: Vec<ListItem> = nodes
+                .iter()
+                .map(|node| {
+                    ListItem::new(format!(
+                        "{}[{}] {} ({})",
+                        "  ".repeat(node.depth),
+                        if node.selected { 'x' } else { ' ' },
+                        node.name,
+                        count(node.lines, "line")
+                    ))
+                })
+                .collect();
+            let selected: Vec<&Node> = nodes.iter().filter(|node| node.selected).collect();
+            let title = format!(
+                " {} of {}, {} ",
+                selected.len(),
+                count(nodes.len(), "function"),
+                count(selected.iter().map(|node| node.lines).sum(), "line")
+            );
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list, list_area, &mut state);
+            frame.render_widget(
+                Paragraph::new(vec![
+                    Line::from("space: toggle with subtree   t: toggle one   a: select all"),
+                    Line::from("enter: write the output   q: quit without output"),
+                ]),
+                help_area,
+            );
+        })?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let current = state.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => state.select(Some(current.saturating_sub(1))),
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.select(Some((current + 1).min(nodes.len().saturating_sub(1))))
+            }
+            KeyCode::PageUp => state.select(Some(current.saturating_sub(20))),
+            KeyCode::PageDown => {
+                state.select(Some((current + 20).min(nodes.len().saturating_sub(1))))
+            }
+            KeyCode::Char(' ') => {
+                let selected = !nodes[current].selected;
+                let end = nodes[current].subtree_end;
+                for node in &mut nodes[current..=end] {
+                    node.selected = selected;
+                }
+                nodes[current].toggled = true;
+            }
+            KeyCode::Char('t') => {
+                nodes[current].selected = !nodes[current].selected;
+                nodes[current].toggled = true;
+            }
+            KeyCode::Char('a') => nodes.iter_mut().for_each(|node| node.selected = true),
+            KeyCode::Enter => return Ok(true),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            _ => {}
+        }
+    }
+}
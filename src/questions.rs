@@ -0,0 +1,116 @@
+//! `--questions`: the blind spots of a gather phrased as questions (calls
+//! into code that isn't included, resolutions that were a guess, code behind
+//! `#[cfg]`, unfinished parts), so the prompt can brief the model on what
+//! the context doesn't settle.
+
+use crate::index::{ProjectIndex, Resolution};
+use crate::lang::call_arguments;
+use crate::stubs::STD_CRATES;
+use crate::{FunctionInfo, Language};
+use regex::Regex;
+use std::collections::HashSet;
+
+/// The question section to append after the code; empty when the gathered
+/// functions leave nothing open.
+pub fn render(
+    index: &ProjectIndex,
+    gathered_names: &[String],
+    gathered: &[&FunctionInfo],
+) -> String {
+    let cfg_regex = Regex::new(r"#\[cfg\s*\(|\bcfg!\s*\(").unwrap();
+    let todo_regex = Regex::new(r"(?://|/\*)\s*(TODO|FIXME|XXX|HACK)\b:?\s*(.*)").unwrap();
+
+    let mut external = Vec::new();
+    let mut ambiguous = Vec::new();
+    let mut gated = Vec::new();
+    let mut unfinished = Vec::new();
+    let mut asked_external: HashSet<String> = HashSet::new();
+    for (name, info) in gathered_names.iter().zip(gathered) {
+        for (call, resolution) in index.call_resolutions(name) {
+            match resolution {
+                None => {
+                    // Calls by crate path; bare names are mostly methods
+                    let Some((first, _)) = call.split_once("::") else {
+                        continue;
+                    };
+                    if first.starts_with(char::is_lowercase)
+                        && !STD_CRATES.contains(&first)
+                        && !["crate", "self", "super"].contains(&first)
+                        && asked_external.insert(call.clone())
+                    {
+                        external.push(format!(
+                            "What does `{}` (called from `{}`) do, and can it fail? Its code \
+                             is not included.",
+                            call, name
+                        ));
+                    }
+                }
+                Some((target, Resolution::FirstOfMany { candidates })) => {
+                    ambiguous.push(format!(
+                        "Is `{}` the `{}` that `{}` calls? {} functions have that name and \
+                         the first one indexed was assumed.",
+                        target, call, name, candidates
+                    ));
+                }
+                Some((target, Resolution::Trait)) => {
+                    ambiguous.push(format!(
+                        "Which implementation of `{}` does `{}` call at runtime? It calls \
+                         the trait method, so only the trait's declaration is certain.",
+                        target, name
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let definition = &info.definition;
+        if info.language == Language::Rust {
+            let mut conditions: Vec<String> = Vec::new();
+            for found in cfg_regex.find_iter(definition) {
+                let condition = call_arguments(&definition[found.end()..]).join(", ");
+                if !condition.is_empty() && !conditions.contains(&condition) {
+                    conditions.push(condition);
+                }
+            }
+            for condition in conditions {
+                gated.push(format!(
+                    "Is `{}` set in the build this is about? Part of `{}` is only compiled \
+                     under it.",
+                    condition, name
+                ));
+            }
+        }
+
+        for captures in todo_regex.captures_iter(definition) {
+            let start = captures.get(0).unwrap().start();
+            let line_number = info.line_number + definition[..start].matches('\n').count();
+            let note = captures[2].trim().trim_end_matches("*/").trim();
+            let note = if note.is_empty() {
+                captures[1].to_string()
+            } else {
+                format!("{}: {}", &captures[1], note)
+            };
+            unfinished.push(format!(
+                "Does the unfinished part of `{}` at {}:{} (\"{}\") matter here?",
+                name,
+                info.path.to_string_lossy(),
+                line_number,
+                note
+            ));
+        }
+    }
+
+    let questions: Vec<String> = [external, ambiguous, gated, unfinished]
+        .into_iter()
+        .flatten()
+        .collect();
+    if questions.is_empty() {
+        return String::new();
+    }
+    let mut output = String::from("\n=== OPEN QUESTIONS ===\n\n");
+    for (number, question) in questions.iter().enumerate() {
+        output.push_str(&format!("{}. {}\n", number + 1, question));
+    }
+    output.push('\n');
+    output
+}
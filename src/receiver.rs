@@ -0,0 +1,73 @@
+//! The `impl` header and the type definition of a target method, placed
+//! ahead of its code, so the fields it reaches through `self` are known
+//! before its body is read.
+
+use crate::index::{signature, ProjectIndex};
+use crate::{ImplInfo, TypeInfo};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// What a root method belongs to: the impl block it is defined in, and the
+/// definition of the type (or trait) it is a method of.
+pub struct Receiver<'a> {
+    pub impl_info: Option<&'a ImplInfo>,
+    pub type_info: Option<&'a TypeInfo>,
+}
+
+/// The receivers of the `roots` that are methods, in root order, each impl
+/// block and type once. Without `with_types`, only the impl headers.
+pub fn collect<'a>(
+    index: &'a ProjectIndex,
+    roots: &[String],
+    with_types: bool,
+) -> Vec<Receiver<'a>> {
+    let mut seen: HashSet<(&PathBuf, usize)> = HashSet::new();
+    let mut receivers = Vec::new();
+    for root in roots {
+        let Some(info) = index.function_definitions.get(root) else {
+            continue;
+        };
+        let Some(self_type) = &info.self_type else {
+            continue;
+        };
+        let impl_info = index
+            .enclosing_impl(info)
+            .filter(|i| seen.insert((&i.path, i.line_number)));
+        let type_info = with_types
+            .then(|| index.types_in(self_type, info.language).into_iter().next())
+            .flatten()
+            .filter(|t| seen.insert((&t.path, t.line_number)));
+        if impl_info.is_some() || type_info.is_some() {
+            receivers.push(Receiver {
+                impl_info,
+                type_info,
+            });
+        }
+    }
+    receivers
+}
+
+/// The impl headers and type definitions of `receivers`, each under its
+/// location.
+pub fn render(receivers: &[Receiver]) -> String {
+    let mut blocks = Vec::new();
+    for receiver in receivers {
+        if let Some(impl_info) = receiver.impl_info {
+            blocks.push(format!(
+                "=== {}:{} ===\n{}\n",
+                impl_info.path.to_string_lossy(),
+                impl_info.line_number,
+                signature(&impl_info.definition).trim_end()
+            ));
+        }
+        if let Some(type_info) = receiver.type_info {
+            blocks.push(format!(
+                "=== {}:{} ===\n{}\n",
+                type_info.path.to_string_lossy(),
+                type_info.line_number,
+                type_info.definition
+            ));
+        }
+    }
+    blocks.join("\n")
+}
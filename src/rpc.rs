@@ -0,0 +1,487 @@
+//! `rpc`: a JSON-RPC 2.0 server over stdin/stdout for editor plugins,
+//! which spawn `gather-context rpc <project_root>` as a child process and
+//! keep the index warm between requests.
+//!
+//! One server can hold the indexes of several projects, given on the
+//! command line or added with `register`, for a long-running context
+//! service over many repositories. Requests name theirs in a `project`
+//! parameter, which may be left out while only one is registered.
+//!
+//! Messages are newline-delimited: one JSON object per line in each
+//! direction. Logs go to stderr.
+//!
+//! ```text
+//! → {"jsonrpc":"2.0","id":1,"method":"gather","params":{"function":"process_queue","prompt":"review"}}
+//! ← {"jsonrpc":"2.0","id":1,"result":{"target":"src::worker::process_queue","functions":[...],"output":"..."}}
+//! → {"jsonrpc":"2.0","id":2,"method":"functions","params":{"query":"queue"}}
+//! ← {"jsonrpc":"2.0","id":2,"result":{"functions":["src::worker::process_queue", ...]}}
+//! → {"jsonrpc":"2.0","method":"fileChanged","params":{"path":"src/worker.rs"}}
+//! → {"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":1}}
+//! → {"jsonrpc":"2.0","id":3,"method":"register","params":{"root":"../billing","name":"billing"}}
+//! ← {"jsonrpc":"2.0","id":3,"result":{"name":"billing","root":"/src/billing","functions":812}}
+//! → {"jsonrpc":"2.0","id":4,"method":"gather","params":{"project":"billing","function":"settle"}}
+//! → {"jsonrpc":"2.0","id":5,"method":"gather","params":{"project":"billing","function":"settle","session":"chat-7","delta":true}}
+//! ← {"jsonrpc":"2.0","id":5,"result":{"target":"...","functions":[...],"already_sent":["src::ledger::post", ...],"output":"..."}}
+//! → {"jsonrpc":"2.0","id":6,"method":"shutdown"}
+//! ```
+//!
+//! A `gather` with a `session` remembers what it sent; with `delta` too,
+//! functions the session already has unchanged are listed in
+//! `already_sent` instead of being sent again, so an agent's follow-up
+//! requests only add to its context.
+//!
+//! Requests are handled one at a time in arrival order. A request cancelled
+//! before its response is written is answered with error `-32800` instead,
+//! and a session isn't recorded as having been sent what it never got.
+//! Cancelling an id that isn't pending, already answered or never sent,
+//! does nothing.
+//! `gather` takes and returns the library's [`GatherParams`] and
+//! [`GatherResult`] in their JSON form.
+
+use crate::budget::Tokenizer;
+use crate::gatherer::{ContextGatherer, GatherParams, GatherResult};
+use crate::index::ProjectIndex;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const REQUEST_CANCELLED: i64 = -32800;
+/// The requested function is not in the index
+pub const FUNCTION_NOT_FOUND: i64 = -32001;
+/// No project is registered under the requested name
+pub const PROJECT_NOT_FOUND: i64 = -32002;
+
+/// Builds the index of a project root, with the server's index options.
+pub type IndexBuilder = Box<dyn Fn(&Path) -> io::Result<ProjectIndex>>;
+
+/// A JSON-RPC error object.
+#[derive(Debug, Clone)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> RpcError {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Serves requests for `projects`, each a name and a canonical root, until
+/// stdin closes or `shutdown` is received.
+pub fn serve(
+    projects: Vec<(String, PathBuf)>,
+    build: IndexBuilder,
+    tokenizer: Tokenizer,
+) -> io::Result<()> {
+    let in_flight: Arc<Mutex<InFlight>> = Arc::default();
+    let (sender, receiver) = mpsc::channel::<Value>();
+
+    // Reading happens on its own thread so cancellations arrive while a
+    // request is being worked on
+    let reader_in_flight = Arc::clone(&in_flight);
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message: Value = match serde_json::from_str(&line) {
+                Ok(message) => message,
+                Err(err) => {
+                    let error = RpcError::new(PARSE_ERROR, err.to_string());
+                    write_message(&error_response(&Value::Null, &error));
+                    continue;
+                }
+            };
+            let mut in_flight = reader_in_flight.lock().unwrap();
+            if message["method"] == "$/cancelRequest" {
+                // As in LSP, cancelling a request that was already answered,
+                // or never sent, does nothing
+                let id = message["params"]["id"].to_string();
+                if in_flight.pending.contains(&id) {
+                    in_flight.cancelled.insert(id);
+                }
+                continue;
+            }
+            if let Some(id) = message.get("id") {
+                in_flight.pending.insert(id.to_string());
+            }
+            drop(in_flight);
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut server = Server::new(build, tokenizer);
+    for (name, root) in projects {
+        server.register(name, root)?;
+    }
+    eprintln!("Serving JSON-RPC on stdin/stdout");
+
+    for message in receiver {
+        let id = message.get("id").cloned();
+        let is_cancelled = || {
+            id.as_ref().is_some_and(|id| {
+                let in_flight = in_flight.lock().unwrap();
+                in_flight.cancelled.contains(&id.to_string())
+            })
+        };
+        if let Some(response) = server.handle(&message, &is_cancelled) {
+            write_message(&response);
+        }
+        if let Some(id) = &id {
+            let mut in_flight = in_flight.lock().unwrap();
+            in_flight.pending.remove(&id.to_string());
+            in_flight.cancelled.remove(&id.to_string());
+        }
+        if message["method"] == "shutdown" {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// The ids of the requests read but not answered yet, and of those the
+/// client cancelled.
+#[derive(Default)]
+struct InFlight {
+    pending: HashSet<String>,
+    cancelled: HashSet<String>,
+}
+
+/// The functions a `gather` sent to a session, recorded once the response
+/// goes out.
+struct Sent {
+    project: String,
+    session: String,
+    functions: Vec<(String, String)>,
+}
+
+/// Answers requests for the registered projects, one message at a time;
+/// [`serve`] runs one over stdin/stdout.
+pub struct Server {
+    projects: BTreeMap<String, Project>,
+    build: IndexBuilder,
+    tokenizer: Tokenizer,
+}
+
+impl Server {
+    pub fn new(build: IndexBuilder, tokenizer: Tokenizer) -> Server {
+        Server {
+            projects: BTreeMap::new(),
+            build,
+            tokenizer,
+        }
+    }
+
+    /// The response to one message, or `None` for a notification.
+    /// `cancelled` tells whether the client has cancelled the request; it
+    /// is asked before the request runs and again before it is answered,
+    /// and a cancelled request is answered with [`REQUEST_CANCELLED`] and
+    /// leaves no trace, such as functions recorded as sent to a session.
+    pub fn handle(&mut self, message: &Value, cancelled: &dyn Fn() -> bool) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let method = message["method"].as_str().unwrap_or("");
+        let cancelled_error = || RpcError::new(REQUEST_CANCELLED, "request cancelled");
+        if let Some(id) = &id {
+            if cancelled() {
+                return Some(error_response(id, &cancelled_error()));
+            }
+        }
+
+        let result = self.run(method, &message["params"]);
+
+        // Notifications get no response
+        let Some(id) = id else {
+            if let Err(err) = result {
+                eprintln!("{}: {}", method, err.message);
+            }
+            return None;
+        };
+        Some(match result {
+            _ if cancelled() => error_response(&id, &cancelled_error()),
+            Ok((result, sent)) => {
+                if let Some(sent) = sent {
+                    self.record(sent);
+                }
+                json!({"jsonrpc": "2.0", "id": id, "result": result})
+            }
+            Err(err) => error_response(&id, &err),
+        })
+    }
+
+    /// Runs a request's method, returning its result and the functions it
+    /// would record as sent.
+    fn run(&mut self, method: &str, params: &Value) -> Result<(Value, Option<Sent>), RpcError> {
+        if method == "gather" {
+            let name = self.project_name_in(params)?;
+            return self.projects[&name].gather_request(&self.tokenizer, params);
+        }
+        let result = match method {
+            "shutdown" => Ok(Value::Null),
+            "functions" => self
+                .project(params)
+                .map(|project| project.functions(params["query"].as_str().unwrap_or(""))),
+            "fileChanged" => match params["path"].as_str() {
+                Some(path) => self.project_mut(params).and_then(|project| {
+                    project
+                        .file_changed(Path::new(path))
+                        .map(|_| Value::Null)
+                        .map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))
+                }),
+                None => Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "missing string parameter 'path'",
+                )),
+            },
+            "endSession" => match params["session"].as_str() {
+                Some(session) => self.project_mut(params).map(|project| {
+                    project.sessions.remove(session);
+                    Value::Null
+                }),
+                None => Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "missing string parameter 'session'",
+                )),
+            },
+            "projects" => Ok(self.projects()),
+            "register" => match params["root"].as_str() {
+                Some(root) => self.register_request(Path::new(root), params["name"].as_str()),
+                None => Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "missing string parameter 'root'",
+                )),
+            },
+            "unregister" => self.project_name_in(params).map(|name| {
+                self.projects.remove(&name);
+                eprintln!("Unregistered {}", name);
+                Value::Null
+            }),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{}'", method),
+            )),
+        };
+        result.map(|result| (result, None))
+    }
+
+    /// Remembers what a session was sent, once it has been answered.
+    fn record(&mut self, sent: Sent) {
+        if let Some(project) = self.projects.get_mut(&sent.project) {
+            project
+                .sessions
+                .entry(sent.session)
+                .or_default()
+                .extend(sent.functions);
+        }
+    }
+
+    /// Indexes `root` and answers for it as `name`.
+    pub fn register(&mut self, name: String, root: PathBuf) -> io::Result<()> {
+        let index = (self.build)(&root)?;
+        eprintln!("Registered {} ({})", name, root.display());
+        self.projects.insert(
+            name.clone(),
+            Project {
+                name,
+                root,
+                index,
+                sessions: HashMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    /// `register`: a root relative to the server's working directory, named
+    /// after its directory unless `name` is given.
+    fn register_request(&mut self, root: &Path, name: Option<&str>) -> Result<Value, RpcError> {
+        let root = root
+            .canonicalize()
+            .map_err(|err| RpcError::new(INVALID_PARAMS, format!("{}: {}", root.display(), err)))?;
+        if !root.is_dir() {
+            let message = format!("{} is not a directory", root.display());
+            return Err(RpcError::new(INVALID_PARAMS, message));
+        }
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => project_name(&root),
+        };
+        if self.projects.contains_key(&name) {
+            let message = format!("a project named '{}' is already registered", name);
+            return Err(RpcError::new(INVALID_PARAMS, message));
+        }
+        self.register(name.clone(), root)
+            .map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))?;
+        Ok(self.projects[&name].describe())
+    }
+
+    /// The registered projects, by name.
+    fn projects(&self) -> Value {
+        let projects: Vec<Value> = self.projects.values().map(Project::describe).collect();
+        json!({ "projects": projects })
+    }
+
+    /// The project a request's `project` parameter names, or the only one.
+    fn project(&self, params: &Value) -> Result<&Project, RpcError> {
+        let name = self.project_name_in(params)?;
+        Ok(&self.projects[&name])
+    }
+
+    fn project_mut(&mut self, params: &Value) -> Result<&mut Project, RpcError> {
+        let name = self.project_name_in(params)?;
+        Ok(self.projects.get_mut(&name).unwrap())
+    }
+
+    fn project_name_in(&self, params: &Value) -> Result<String, RpcError> {
+        match params["project"].as_str() {
+            Some(name) if self.projects.contains_key(name) => Ok(name.to_string()),
+            Some(name) => Err(RpcError::new(
+                PROJECT_NOT_FOUND,
+                format!("no project named '{}' is registered", name),
+            )),
+            None if self.projects.len() == 1 => Ok(self.projects.keys().next().unwrap().clone()),
+            None if self.projects.is_empty() => {
+                Err(RpcError::new(PROJECT_NOT_FOUND, "no project is registered"))
+            }
+            None => {
+                let names: Vec<&str> = self.projects.keys().map(String::as_str).collect();
+                Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!(
+                        "several projects are registered; pass 'project' ({})",
+                        names.join(", ")
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+/// A project the server answers for.
+struct Project {
+    name: String,
+    root: PathBuf,
+    index: ProjectIndex,
+    /// The functions each session was sent, with the content hash of the
+    /// definition it was sent
+    sessions: HashMap<String, HashMap<String, String>>,
+}
+
+impl Project {
+    fn describe(&self) -> Value {
+        json!({
+            "name": self.name,
+            "root": self.root.to_string_lossy(),
+            "functions": self.index.function_definitions.len(),
+        })
+    }
+
+    /// `gather`: with a `session`, the functions sent are to be remembered
+    /// for it, and with `delta` the ones the session already has unchanged
+    /// are named in `already_sent` instead of sent again.
+    fn gather_request(
+        &self,
+        tokenizer: &Tokenizer,
+        params: &Value,
+    ) -> Result<(Value, Option<Sent>), RpcError> {
+        let mut gather_params =
+            GatherParams::from_json(params).map_err(|err| RpcError::new(INVALID_PARAMS, err))?;
+        let session = params["session"].as_str();
+        if params["delta"].as_bool() == Some(true) {
+            let Some(session) = session else {
+                return Err(RpcError::new(INVALID_PARAMS, "'delta' needs a 'session'"));
+            };
+            gather_params.already_sent = self.sessions.get(session).cloned().unwrap_or_default();
+        }
+        let result = self.gather(tokenizer, &gather_params)?;
+        let sent = session.map(|session| Sent {
+            project: self.name.clone(),
+            session: session.to_string(),
+            functions: result
+                .functions
+                .iter()
+                .map(|function| (function.name.clone(), function.hash.clone()))
+                .collect(),
+        });
+        Ok((result.to_json(), sent))
+    }
+
+    fn gather(
+        &self,
+        tokenizer: &Tokenizer,
+        params: &GatherParams,
+    ) -> Result<GatherResult, RpcError> {
+        ContextGatherer::new(&self.index, tokenizer)
+            .gather(params)
+            .ok_or_else(|| {
+                let message = match &params.at {
+                    Some((path, line)) => format!("no function encloses {}:{}", path, line),
+                    None => format!("function '{}' not found", params.function),
+                };
+                RpcError::new(FUNCTION_NOT_FOUND, message)
+            })
+    }
+
+    /// Qualified names of the functions whose simple name contains `query`.
+    fn functions(&self, query: &str) -> Value {
+        let mut names: Vec<String> = self
+            .index
+            .partial_matches(query)
+            .into_iter()
+            .map(|(qualified_name, _)| qualified_name)
+            .collect();
+        names.sort();
+        names.dedup();
+        json!({ "functions": names })
+    }
+
+    /// Re-indexes a file the editor saved, created or deleted. Paths may be
+    /// relative to the project root.
+    fn file_changed(&mut self, path: &Path) -> io::Result<()> {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.root.join(path)
+        };
+        // Deleted files can't be canonicalized, but their directory can
+        let path = match (path.parent(), path.file_name()) {
+            (Some(dir), Some(name)) => dir.canonicalize()?.join(name),
+            _ => path,
+        };
+        self.index.update_file(&path)
+    }
+}
+
+/// The name a project root is registered under by default: its directory
+/// name.
+pub fn project_name(root: &Path) -> String {
+    root.file_name()
+        .map_or_else(|| root.to_string_lossy(), |name| name.to_string_lossy())
+        .into_owned()
+}
+
+fn error_response(id: &Value, error: &RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+fn write_message(message: &Value) {
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{}", message);
+    let _ = stdout.flush();
+}
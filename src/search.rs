@@ -0,0 +1,89 @@
+//! `search <project_root> <regex>`: finds indexed functions by qualified
+//! name, signature or (with `--bodies`) body, listing their qualified names
+//! ready to pass to a gather, and `list`, which lists all of them.
+
+use crate::index::{body, signature, ProjectIndex};
+use crate::trim::in_module;
+use regex::Regex;
+
+/// Where a function matched, in the order results are ranked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Field {
+    Name,
+    Signature,
+    Body,
+}
+
+/// One line per matching function: name matches first, then signature and
+/// body matches, each sorted by qualified name. Body matches show the first
+/// matching line.
+pub fn render(index: &ProjectIndex, pattern: &Regex, bodies: bool) -> String {
+    let mut matches: Vec<(Field, &String, String)> = Vec::new();
+    for (name, info) in &index.function_definitions {
+        let header = signature(&info.definition);
+        if pattern.is_match(name) {
+            matches.push((Field::Name, name, String::new()));
+        } else if pattern.is_match(header) {
+            matches.push((Field::Signature, name, "signature".to_string()));
+        } else if bodies {
+            let body = body(&info.definition);
+            let body_line = body
+                .lines()
+                .enumerate()
+                .find(|(_, line)| pattern.is_match(line));
+            if let Some((offset, line)) = body_line {
+                let before_body = &info.definition[..info.definition.len() - body.len()];
+                let line_number = info.line_number + before_body.matches('\n').count() + offset;
+                matches.push((
+                    Field::Body,
+                    name,
+                    format!("line {}: {}", line_number, line.trim()),
+                ));
+            }
+        }
+    }
+    matches.sort();
+
+    let lines: Vec<(&String, String)> = matches
+        .into_iter()
+        .map(|(_, name, detail)| (name, detail))
+        .collect();
+    format_lines(index, &lines)
+}
+
+/// `list <project_root>`: every indexed function sorted by qualified name,
+/// only those inside `within` when given, and tests only with `tests`.
+pub fn list(index: &ProjectIndex, within: Option<&str>, tests: bool) -> String {
+    let mut names: Vec<&String> = index
+        .function_definitions
+        .iter()
+        .filter(|(_, info)| tests || !info.is_test)
+        .filter(|(_, info)| within.is_none_or(|spec| in_module(&info.module_path, spec)))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+
+    let lines: Vec<(&String, String)> = names
+        .into_iter()
+        .map(|name| (name, String::new()))
+        .collect();
+    format_lines(index, &lines)
+}
+
+/// `name  path:line  detail`, with names padded to a common width.
+pub(crate) fn format_lines(index: &ProjectIndex, lines: &[(&String, String)]) -> String {
+    let width = lines
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut output = String::new();
+    for (name, detail) in lines {
+        let info = &index.function_definitions[*name];
+        let location = format!("{}:{}", info.path.to_string_lossy(), info.line_number);
+        let line = format!("{:<width$}  {}  {}", name, location, detail, width = width);
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
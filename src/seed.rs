@@ -0,0 +1,164 @@
+//! Composable traversal seeds: `--seed fn:process_queue --seed type:Invoice
+//! --combine intersect`.
+
+use crate::index::ProjectIndex;
+use regex::RegexBuilder;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Seed {
+    /// The call tree of one function
+    Function(String),
+    /// The call trees of every function whose signature or body names a type
+    Type(String),
+    /// The call trees of every function whose doc comment matches a regex,
+    /// ignoring case
+    Doc(String),
+}
+
+impl Seed {
+    pub fn parse(spec: &str) -> Option<Seed> {
+        match spec.split_once(':') {
+            Some(("fn", name)) if !name.is_empty() => Some(Seed::Function(name.to_string())),
+            Some(("type", name)) if !name.is_empty() => Some(Seed::Type(name.to_string())),
+            Some(("doc", pattern)) if !pattern.is_empty() => Some(Seed::Doc(pattern.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combine {
+    Union,
+    Intersect,
+}
+
+impl Combine {
+    pub fn parse(name: &str) -> Option<Combine> {
+        match name {
+            "union" => Some(Combine::Union),
+            "intersect" => Some(Combine::Intersect),
+            _ => None,
+        }
+    }
+}
+
+/// The combined trees of the seeds.
+pub struct Seeded {
+    pub functions: Vec<String>,
+    /// What each seed started from, one line per seed
+    pub notes: Vec<String>,
+}
+
+/// Gathers each seed's tree and combines them, keeping the traversal order
+/// of the first tree a function appears in. Fails on a seed naming no
+/// project function or type, or matching no doc comment.
+pub fn gather(
+    index: &ProjectIndex,
+    seeds: &[Seed],
+    combine: Combine,
+    max_depth: Option<usize>,
+) -> Result<Seeded, String> {
+    let mut notes = Vec::new();
+    let trees: Vec<Vec<String>> = seeds
+        .iter()
+        .map(|seed| tree(index, seed, max_depth, &mut notes))
+        .collect::<Result<_, _>>()?;
+
+    let mut seen = HashSet::new();
+    let combined = match combine {
+        Combine::Union => trees
+            .iter()
+            .flatten()
+            .filter(|name| seen.insert(*name))
+            .cloned()
+            .collect(),
+        Combine::Intersect => {
+            let others: Vec<HashSet<&String>> =
+                trees.iter().skip(1).map(|t| t.iter().collect()).collect();
+            trees
+                .first()
+                .into_iter()
+                .flatten()
+                .filter(|name| others.iter().all(|other| other.contains(name)))
+                .filter(|name| seen.insert(*name))
+                .cloned()
+                .collect()
+        }
+    };
+    Ok(Seeded {
+        functions: combined,
+        notes,
+    })
+}
+
+fn tree(
+    index: &ProjectIndex,
+    seed: &Seed,
+    max_depth: Option<usize>,
+    notes: &mut Vec<String>,
+) -> Result<Vec<String>, String> {
+    match seed {
+        Seed::Function(name) => {
+            let selected_function = index
+                .find_function(name, None)
+                .ok_or_else(|| format!("Function '{}' not found in project", name))?
+                .qualified_name;
+            notes.push(format!("Seed fn:{} -> {}", name, selected_function));
+            Ok(index.reachable_within(&[selected_function], max_depth))
+        }
+        Seed::Type(type_name) => {
+            if !index.type_definitions.contains_key(type_name) {
+                return Err(format!("Type '{}' not found in project", type_name));
+            }
+
+            let mut users: Vec<String> = index
+                .function_definitions
+                .iter()
+                .filter(|(_, info)| {
+                    info.definition
+                        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .any(|word| word == type_name)
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            users.sort();
+            notes.push(format!(
+                "Seed type:{} -> {} functions use it",
+                type_name,
+                users.len()
+            ));
+            Ok(index.reachable_within(&users, max_depth))
+        }
+        Seed::Doc(pattern) => {
+            let doc_regex = RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|err| format!("Invalid doc pattern '{}': {}", pattern, err))?;
+
+            let mut documented: Vec<String> = index
+                .function_definitions
+                .iter()
+                .filter(|(_, info)| {
+                    info.doc
+                        .as_deref()
+                        .is_some_and(|doc| doc_regex.is_match(doc))
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            if documented.is_empty() {
+                return Err(format!(
+                    "No doc comment in the project matches '{}'",
+                    pattern
+                ));
+            }
+            documented.sort();
+            notes.push(format!(
+                "Seed doc:{} -> {} functions documented so",
+                pattern,
+                documented.len()
+            ));
+            Ok(index.reachable_within(&documented, max_depth))
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! The serde side of the project types gathered functions serialize:
+//! manual `Serialize`/`Deserialize` impls, the `#[serde(with = ...)]`
+//! helpers their fields name, and the serialized types nested in their
+//! fields, since wire-format questions hinge on exactly those and none of
+//! them are reached through the call graph.
+
+use crate::index::ProjectIndex;
+use crate::{FunctionInfo, ImplInfo, TypeInfo};
+use regex::Regex;
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+
+const SERDE_TRAITS: &[&str] = &["Serialize", "Deserialize"];
+
+/// The serialization section for the types the serializing functions among
+/// `gathered` name, and the serialized types their fields use in turn.
+/// Definitions already in `shown` (the type definitions section) are not
+/// repeated. Empty when no gathered function serializes a project type.
+pub fn render(index: &ProjectIndex, gathered: &[&FunctionInfo], shown: &[&TypeInfo]) -> String {
+    let serializer_regex = Regex::new(
+        r"\b(?:serde|serde_json|serde_yaml|serde_cbor|toml|bincode|postcard|rmp_serde|ciborium|ron|csv)::|\.(?:de)?serialize\(",
+    )
+    .unwrap();
+    let derive_regex = Regex::new(r"#\[derive\(([^)]*)\)\]").unwrap();
+    let is_serialized = |name: &str| {
+        let derived = index.type_definitions[name].iter().any(|t| {
+            derive_regex.captures_iter(&t.definition).any(|captures| {
+                captures[1]
+                    .split(',')
+                    .map(|derive| derive.trim().rsplit("::").next().unwrap_or_default())
+                    .any(|derive| SERDE_TRAITS.contains(&derive))
+            })
+        });
+        derived || !serde_impls(index, name).is_empty()
+    };
+
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut queue: VecDeque<&str> = gathered
+        .iter()
+        .filter(|info| serializer_regex.is_match(&info.definition))
+        .flat_map(|info| type_names(index, &info.definition))
+        .filter(|name| is_serialized(name) && seen.insert(name))
+        .collect();
+    let mut serialized = Vec::new();
+    while let Some(name) = queue.pop_front() {
+        serialized.push(name);
+        for definition in &index.type_definitions[name] {
+            for field_type in type_names(index, &definition.definition) {
+                if is_serialized(field_type) && seen.insert(field_type) {
+                    queue.push_back(field_type);
+                }
+            }
+        }
+    }
+    if serialized.is_empty() {
+        return String::new();
+    }
+
+    let mut printed: HashSet<(&PathBuf, usize)> =
+        shown.iter().map(|t| (&t.path, t.line_number)).collect();
+    let mut output = String::from("\n=== SERIALIZATION ===\n");
+    let mut push = |path: &PathBuf, line_number: usize, definition: &str| {
+        output.push_str(&format!(
+            "\n=== {}:{} ===\n{}\n",
+            path.to_string_lossy(),
+            line_number,
+            definition
+        ));
+    };
+    for name in serialized {
+        for definition in &index.type_definitions[name] {
+            if printed.insert((&definition.path, definition.line_number)) {
+                push(
+                    &definition.path,
+                    definition.line_number,
+                    &definition.definition,
+                );
+            }
+        }
+        for impl_info in serde_impls(index, name) {
+            push(
+                &impl_info.path,
+                impl_info.line_number,
+                &impl_info.definition,
+            );
+        }
+        for definition in &index.type_definitions[name] {
+            for helper in serde_helpers(index, definition) {
+                let info = &index.function_definitions[helper];
+                if printed.insert((&info.path, info.line_number)) {
+                    push(&info.path, info.line_number, &info.definition);
+                }
+            }
+        }
+    }
+    output.push('\n');
+    output
+}
+
+/// Project type names mentioned in `text`, in order of first mention.
+fn type_names<'a>(index: &'a ProjectIndex, text: &str) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter_map(|word| index.type_definitions.get_key_value(word))
+        .map(|(name, _)| name.as_str())
+        .filter(|name| seen.insert(*name))
+        .collect()
+}
+
+/// Handwritten `Serialize` and `Deserialize` impls of a type.
+fn serde_impls<'a>(index: &'a ProjectIndex, name: &str) -> Vec<&'a ImplInfo> {
+    index
+        .impls
+        .get(name)
+        .into_iter()
+        .flatten()
+        .filter(|impl_info| {
+            impl_info
+                .trait_name
+                .as_deref()
+                .is_some_and(|trait_name| SERDE_TRAITS.contains(&trait_name))
+        })
+        .collect()
+}
+
+/// The project functions a type's `#[serde(with = "...")]`,
+/// `serialize_with` and `deserialize_with` attributes name.
+fn serde_helpers<'a>(index: &'a ProjectIndex, type_info: &TypeInfo) -> Vec<&'a String> {
+    let helper_regex =
+        Regex::new(r#"\b(with|serialize_with|deserialize_with)\s*=\s*"([^"]+)""#).unwrap();
+    let mut helpers = Vec::new();
+    for captures in helper_regex.captures_iter(&type_info.definition) {
+        let path = &captures[2];
+        let paths = match &captures[1] {
+            // A module providing both halves
+            "with" => vec![
+                format!("{}::serialize", path),
+                format!("{}::deserialize", path),
+            ],
+            _ => vec![path.to_string()],
+        };
+        for path in paths {
+            if let Some(helper) = resolve_path(index, &path, type_info) {
+                if !helpers.contains(&helper) {
+                    helpers.push(helper);
+                }
+            }
+        }
+    }
+    helpers
+}
+
+/// The function a path written in an attribute of `type_info` refers to:
+/// the one whose qualified name ends with the path, or else the only free
+/// function of that name in the type's file (inline modules are not part
+/// of qualified names).
+fn resolve_path<'a>(
+    index: &'a ProjectIndex,
+    path: &str,
+    type_info: &TypeInfo,
+) -> Option<&'a String> {
+    let path = path
+        .trim_start_matches("crate::")
+        .trim_start_matches("self::")
+        .trim_start_matches("super::");
+    let name = path.rsplit("::").next()?;
+    let suffix = format!("::{}", path);
+    let candidates: Vec<&String> = index
+        .module_functions
+        .get(name)?
+        .iter()
+        .map(|(qualified_name, _)| qualified_name)
+        .collect();
+    if let Some(exact) = candidates.iter().find(|name| name.ends_with(&suffix)) {
+        return Some(exact);
+    }
+    let in_file: Vec<&String> = candidates
+        .into_iter()
+        .filter(|name| {
+            let info = &index.function_definitions[*name];
+            info.path == type_info.path && info.self_type.is_none()
+        })
+        .collect();
+    match in_file.as_slice() {
+        [only] => Some(only),
+        _ => None,
+    }
+}
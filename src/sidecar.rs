@@ -0,0 +1,212 @@
+//! The context manifest written next to every text output file
+//! (`context.txt.manifest.json`): the included functions with their spans
+//! and content hashes, and the resolved call edges among them, so review
+//! bots and agents can reason about a context document without parsing it.
+//! `changed-since` compares a manifest with the project as it is now.
+
+use crate::atomic;
+use crate::index::{ProjectIndex, Resolution};
+use crate::layers;
+use crate::lock::content_hash;
+use crate::wrappers;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `context.txt.manifest.json` for `context.txt`, and `manifest.json` inside
+/// a `--layered-output` directory.
+pub fn path_for(output: &Path) -> PathBuf {
+    if output.is_dir() {
+        return output.join("manifest.json");
+    }
+    let name = output.file_name().unwrap_or_default().to_string_lossy();
+    output.with_file_name(format!("{}.manifest.json", name))
+}
+
+/// The manifest of a gather. Functions are in output order; each one's
+/// `depth` is its call distance from the target through gathered functions,
+/// or null when the target does not call it (other seeds, `callers`), and
+/// its `callees` are all the project functions it calls, gathered or not.
+pub fn render(index: &ProjectIndex, project_root: &Path, gathered_names: &[String]) -> Value {
+    let depths = layers::depths_within(index, gathered_names);
+    let functions: Vec<Value> = gathered_names
+        .iter()
+        .map(|name| {
+            let info = &index.function_definitions[name];
+            let mut callees: Vec<&String> = index
+                .function_calls
+                .get(name)
+                .into_iter()
+                .flatten()
+                .collect();
+            callees.sort();
+            json!({
+                "name": name,
+                "path": info.path.to_string_lossy(),
+                "start_line": info.line_number,
+                "end_line": info.line_number + info.definition.lines().count().max(1) - 1,
+                "language": info.language.fence(),
+                "hash": content_hash(&info.definition),
+                "depth": depths.get(name),
+                "churn": info.churn,
+                "callees": callees,
+                "inlined": wrappers::is_inlined(&info.definition),
+            })
+        })
+        .collect();
+
+    let gathered: HashSet<&String> = gathered_names.iter().collect();
+    let mut edges = Vec::new();
+    for caller in gathered_names {
+        let mut callees: Vec<&String> = index
+            .function_calls
+            .get(caller)
+            .into_iter()
+            .flatten()
+            .filter(|callee| gathered.contains(callee))
+            .collect();
+        callees.sort();
+        for callee in callees {
+            let (call, rule) = match index.resolution(caller, callee) {
+                Some((call, resolution)) => (Some(call), rule_name(resolution)),
+                None => (None, "unknown"),
+            };
+            edges.push(json!({
+                "caller": caller,
+                "callee": callee,
+                "call": call,
+                "rule": rule,
+            }));
+        }
+    }
+
+    // Absolute, so `changed-since` finds the project from any directory
+    let project_root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    json!({
+        "project_root": project_root.to_string_lossy(),
+        "target": gathered_names.first(),
+        "functions": functions,
+        "edges": edges,
+    })
+}
+
+/// Writes the manifest for `output`, returning where it went.
+pub fn write(
+    output: &Path,
+    index: &ProjectIndex,
+    project_root: &Path,
+    gathered_names: &[String],
+) -> io::Result<PathBuf> {
+    let path = path_for(output);
+    let mut text = serde_json::to_string_pretty(&render(index, project_root, gathered_names))?;
+    text.push('\n');
+    atomic::write(&path, text)?;
+    Ok(path)
+}
+
+/// Reads a manifest written next to an output file.
+pub fn load(path: &Path) -> Result<Value, String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let manifest: Value = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+    if !manifest["functions"].is_array() {
+        return Err("missing array field 'functions'".to_string());
+    }
+    Ok(manifest)
+}
+
+/// Describes every gathered function of `manifest` whose body changed, that
+/// no longer exists, or that calls project functions it did not call then;
+/// empty when the context is still current. Manifests without `callees`
+/// only recorded the calls among the gathered functions, so only those are
+/// compared.
+pub fn changes_since(index: &ProjectIndex, manifest: &Value) -> Vec<String> {
+    let functions = manifest["functions"].as_array().into_iter().flatten();
+    let gathered: HashSet<&str> = functions
+        .clone()
+        .filter_map(|function| function["name"].as_str())
+        .collect();
+    let strings = |value: &Value| -> Option<HashSet<String>> {
+        let array = value.as_array()?;
+        Some(
+            array
+                .iter()
+                .filter_map(|name| name.as_str().map(str::to_string))
+                .collect(),
+        )
+    };
+
+    let mut changes = Vec::new();
+    for function in functions {
+        let Some(name) = function["name"].as_str() else {
+            continue;
+        };
+        let Some(info) = index.function_definitions.get(name) else {
+            changes.push(format!("removed: {}", name));
+            continue;
+        };
+        let location = format!("{}:{}", info.path.to_string_lossy(), info.line_number);
+        // A wrapper recorded in its one-line form is compared in that form
+        let hash = match function["inlined"].as_bool() {
+            Some(true) => wrappers::one_line(index, name).map_or_else(
+                || content_hash(&info.definition),
+                |line| content_hash(&line),
+            ),
+            _ => content_hash(&info.definition),
+        };
+        if function["hash"].as_str() != Some(hash.as_str()) {
+            changes.push(format!("changed: {} ({})", name, location));
+        }
+
+        let (recorded, only_gathered) = match strings(&function["callees"]) {
+            Some(callees) => (callees, false),
+            None => {
+                let edges = manifest["edges"].as_array().into_iter().flatten();
+                let callees = edges
+                    .filter(|edge| edge["caller"].as_str() == Some(name))
+                    .filter_map(|edge| edge["callee"].as_str().map(str::to_string))
+                    .collect();
+                (callees, true)
+            }
+        };
+        let mut added: Vec<&String> = index
+            .function_calls
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|callee| !recorded.contains(*callee))
+            .filter(|callee| !only_gathered || gathered.contains(callee.as_str()))
+            .collect();
+        if !added.is_empty() {
+            added.sort();
+            let added: Vec<&str> = added.iter().map(|callee| callee.as_str()).collect();
+            changes.push(format!(
+                "new callees: {} ({}) -> {}",
+                name,
+                location,
+                added.join(", ")
+            ));
+        }
+    }
+    changes
+}
+
+/// The rule that produced an edge, as `explain` describes it.
+fn rule_name(resolution: Resolution) -> &'static str {
+    match resolution {
+        Resolution::Pinned => "pinned",
+        Resolution::Unique => "unique",
+        Resolution::Path => "path",
+        Resolution::Trait => "trait",
+        Resolution::Implementation => "implementation",
+        Resolution::SameModule { .. } => "same_module",
+        Resolution::FirstOfMany { .. } => "first_of_many",
+        Resolution::Derive => "derive",
+        Resolution::Global => "global",
+        Resolution::Operator => "operator",
+        Resolution::CfgVariant => "cfg_variant",
+    }
+}
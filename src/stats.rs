@@ -0,0 +1,135 @@
+//! `stats <project_root>`: project-wide metrics for deciding where to start
+//! a gather: the most called functions, the largest ones, and the ones
+//! nothing calls, which are the likely entry points.
+
+use crate::index::ProjectIndex;
+use crate::outline::count;
+use crate::search::format_lines;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+pub struct Stats<'a> {
+    pub files: usize,
+    pub functions: usize,
+    pub methods: usize,
+    pub tests: usize,
+    /// The functions with the most callers, with their number, most first
+    pub most_called: Vec<(&'a String, usize)>,
+    /// The functions with the most lines, with their number, most first
+    pub largest: Vec<(&'a String, usize)>,
+    /// Every function without callers, largest first
+    pub uncalled: Vec<(&'a String, usize)>,
+}
+
+/// The metrics of `index`, with `top` functions in each ranking (all of
+/// the uncalled ones). Test functions are counted, but only ranked with
+/// `tests`.
+pub fn collect(index: &ProjectIndex, top: usize, tests: bool) -> Stats<'_> {
+    let definitions = &index.function_definitions;
+    let files: HashSet<_> = definitions
+        .values()
+        .map(|info| &info.path)
+        .chain(index.type_definitions.values().flatten().map(|t| &t.path))
+        .collect();
+    let callers = index.callers_graph();
+
+    let ranked: Vec<&String> = definitions
+        .iter()
+        .filter(|(_, info)| tests || !info.is_test)
+        .map(|(name, _)| name)
+        .collect();
+    let lines = |name: &String| definitions[name].definition.lines().count();
+    // Recursion doesn't make a function called
+    let caller_count = |name: &String| {
+        callers
+            .get(name)
+            .map_or(0, |callers| callers.iter().filter(|c| *c != name).count())
+    };
+    let mut most_called = by_count(
+        ranked
+            .iter()
+            .map(|name| (*name, caller_count(name)))
+            .filter(|(_, callers)| *callers > 0)
+            .collect(),
+    );
+    most_called.truncate(top);
+    let mut largest = by_count(ranked.iter().map(|name| (*name, lines(name))).collect());
+    largest.truncate(top);
+    let uncalled = by_count(
+        ranked
+            .iter()
+            .filter(|name| caller_count(name) == 0)
+            .map(|name| (*name, lines(name)))
+            .collect(),
+    );
+
+    Stats {
+        files: files.len(),
+        functions: definitions.len(),
+        methods: definitions
+            .values()
+            .filter(|info| info.self_type.is_some())
+            .count(),
+        tests: definitions.values().filter(|info| info.is_test).count(),
+        most_called,
+        largest,
+        uncalled,
+    }
+}
+
+/// Most first, ties in name order so runs agree.
+fn by_count(mut counted: Vec<(&String, usize)>) -> Vec<(&String, usize)> {
+    counted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    counted
+}
+
+impl Stats<'_> {
+    /// The metrics as text, each ranking in `name  path:line  detail` lines.
+    pub fn render(&self, index: &ProjectIndex) -> String {
+        let mut output = format!(
+            "Files:     {}\nFunctions: {} ({} methods, {} tests)\n",
+            self.files, self.functions, self.methods, self.tests
+        );
+        let sections = [
+            ("Most called", &self.most_called, "caller"),
+            ("Largest", &self.largest, "line"),
+            ("No callers", &self.uncalled, "line"),
+        ];
+        for (title, ranking, noun) in sections {
+            let lines: Vec<(&String, String)> = ranking
+                .iter()
+                .map(|(name, number)| (*name, count(*number, noun)))
+                .collect();
+            output.push_str(&format!("\n{} ({}):\n", title, ranking.len()));
+            output.push_str(&format_lines(index, &lines));
+        }
+        output
+    }
+
+    /// The metrics as JSON, each function with its location.
+    pub fn json(&self, index: &ProjectIndex) -> Value {
+        let ranking = |ranking: &[(&String, usize)], key: &str| {
+            ranking
+                .iter()
+                .map(|(name, number)| {
+                    let info = &index.function_definitions[*name];
+                    json!({
+                        "name": name,
+                        "path": info.path.to_string_lossy(),
+                        "line": info.line_number,
+                        key: number,
+                    })
+                })
+                .collect::<Vec<Value>>()
+        };
+        json!({
+            "files": self.files,
+            "functions": self.functions,
+            "methods": self.methods,
+            "tests": self.tests,
+            "most_called": ranking(&self.most_called, "callers"),
+            "largest": ranking(&self.largest, "lines"),
+            "uncalled": ranking(&self.uncalled, "lines"),
+        })
+    }
+}
@@ -0,0 +1,261 @@
+//! `--stubs`: one-line signatures for the functions gathered code calls but
+//! the output doesn't contain, so a model knows the shape of each missing
+//! piece. Project functions left out by `--fill-budget` use their real
+//! signature; calls into other crates get a declaration found in the
+//! gathered files or one inferred from the call site.
+
+use crate::index::{body, signature, ProjectIndex};
+use crate::lang::{self, call_arguments};
+use crate::{FunctionInfo, Language};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Words followed by `(` that aren't calls.
+const NOT_CALLS: &[&str] = &[
+    "if", "for", "while", "match", "return", "in", "let", "loop", "move", "as", "fn", "where",
+    "impl", "dyn", "mut", "ref", "unsafe", "async", "await", "self", "super", "crate",
+];
+
+/// Crates whose functions a model already knows.
+pub(crate) const STD_CRATES: &[&str] = &["std", "core", "alloc"];
+
+/// The stub section to append after the code; empty when nothing the
+/// gathered functions call is missing.
+pub fn render(
+    index: &ProjectIndex,
+    gathered_names: &[String],
+    gathered: &[&FunctionInfo],
+) -> String {
+    let included: HashSet<&String> = gathered_names.iter().collect();
+
+    // Project functions called by gathered code but not gathered themselves
+    let mut left_out: Vec<&String> = gathered_names
+        .iter()
+        .filter_map(|name| index.function_calls.get(name))
+        .flatten()
+        .filter(|called| !included.contains(called))
+        .filter(|called| index.function_definitions.contains_key(*called))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    left_out.sort();
+
+    let external = external_calls(index, gathered_names, gathered);
+
+    if left_out.is_empty() && external.is_empty() {
+        return String::new();
+    }
+
+    let mut output = String::from("\n=== STUBS FOR FUNCTIONS NOT INCLUDED ===\n\n");
+    for name in left_out {
+        let info = &index.function_definitions[name];
+        output.push_str(&format!(
+            "{}; // not included ({}:{})\n",
+            one_line(declared_signature(&info.definition)),
+            info.path.to_string_lossy(),
+            info.line_number
+        ));
+    }
+    for call in external.values() {
+        output.push_str(&format!("{}; // external, {}\n", call.stub, call.source));
+    }
+    output.push('\n');
+    output
+}
+
+struct ExternalCall {
+    stub: String,
+    /// Where the stub's shape came from
+    source: String,
+}
+
+/// Free and module-path calls (`fetch_rates(..)`, `rates::fetch(..)`) in
+/// gathered Rust functions that name no project function, keyed by the path
+/// as written.
+fn external_calls(
+    index: &ProjectIndex,
+    gathered_names: &[String],
+    gathered: &[&FunctionInfo],
+) -> BTreeMap<String, ExternalCall> {
+    let call_regex = Regex::new(r"\b((?:[a-z_][a-z0-9_]*::)*)([a-z_][A-Za-z0-9_]*)\s*\(").unwrap();
+    let let_regex = Regex::new(r"\blet\s+(?:mut\s+)?([a-z_][A-Za-z0-9_]*)").unwrap();
+    let closure_regex = Regex::new(r"\|([^|]*)\|").unwrap();
+
+    let mut file_texts: HashMap<&PathBuf, String> = HashMap::new();
+    let mut calls = BTreeMap::new();
+
+    for (caller, info) in gathered_names
+        .iter()
+        .zip(gathered)
+        .filter(|(_, info)| info.language == Language::Rust)
+    {
+        let header = signature(&info.definition);
+        let body = body(&info.definition);
+
+        // Locals and parameters called like functions are closures
+        let mut locals: HashSet<&str> = let_regex
+            .captures_iter(body)
+            .map(|captures| captures.get(1).unwrap().as_str())
+            .collect();
+        for text in closure_regex
+            .captures_iter(body)
+            .map(|captures| captures.get(1).unwrap().as_str())
+            .chain(std::iter::once(header))
+        {
+            locals.extend(text.split(|c: char| !(c.is_alphanumeric() || c == '_')));
+        }
+
+        for captures in call_regex.captures_iter(body) {
+            // Method calls, paths through types and nested definitions
+            let before = &body[..captures.get(0).unwrap().start()];
+            if before.ends_with(['.', ':']) || before.trim_end().ends_with("fn") {
+                continue;
+            }
+            let path = captures.get(1).unwrap().as_str();
+            let name = captures.get(2).unwrap().as_str();
+            let written = format!("{}{}", path, name);
+            let first_segment = path.split("::").next().unwrap_or_default();
+
+            if NOT_CALLS.contains(&name)
+                || STD_CRATES.contains(&first_segment)
+                || (path.is_empty() && locals.contains(name))
+                || index.module_functions.contains_key(name)
+                || calls.contains_key(&written)
+            {
+                continue;
+            }
+
+            let file_text = file_texts
+                .entry(&info.path)
+                .or_insert_with(|| lang::read_source(&info.path).unwrap_or_default());
+            let called_as = if path.is_empty() {
+                String::new()
+            } else {
+                format!("called as {}, ", written)
+            };
+            let call = match declaration(file_text, name) {
+                Some(declared) => ExternalCall {
+                    stub: declared,
+                    source: format!("{}declared in {}", called_as, info.path.to_string_lossy()),
+                },
+                None => ExternalCall {
+                    stub: infer_stub(name, body, captures.get(0).unwrap().end()),
+                    source: format!("{}inferred from the call in {}", called_as, caller),
+                },
+            };
+            calls.insert(written, call);
+        }
+    }
+    calls
+}
+
+/// A body-less declaration of `name` (in an `extern` block or a trait), as
+/// one line without the trailing semicolon.
+fn declaration(text: &str, name: &str) -> Option<String> {
+    let declaration_regex = Regex::new(&format!(
+        r"(?m)^\s*((?:pub(?:\([^)]*\))?\s+)?(?:unsafe\s+)?(?:async\s+)?fn\s+{}\b[^;{{]*);",
+        regex::escape(name)
+    ))
+    .unwrap();
+    declaration_regex
+        .captures(text)
+        .map(|captures| one_line(captures.get(1).unwrap().as_str()))
+}
+
+/// A stub for an undeclared call, with parameters named after the
+/// arguments and a return type read from the surrounding code: the type of
+/// the `let` it initializes, `Result` when `?` follows, and `async` when it
+/// is awaited.
+fn infer_stub(name: &str, body: &str, after_paren: usize) -> String {
+    let after_call = &body[after_paren..];
+    let arguments = call_arguments(after_call);
+    let parameters: Vec<String> = arguments
+        .iter()
+        .enumerate()
+        .filter(|(_, argument)| !argument.trim().is_empty())
+        .map(|(i, argument)| format!("{}: _", parameter_name(argument, i)))
+        .collect();
+
+    let rest = closing_paren(after_call).map_or("", |end| after_call[end + 1..].trim_start());
+    let awaited = rest.starts_with(".await");
+    let rest = rest.trim_start_matches(".await").trim_start();
+
+    // `let name: Type = path::call(...)`
+    let call_start = body[..after_paren].rfind(name).unwrap_or(0);
+    let before = body[..call_start]
+        .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_' || c == ':')
+        .trim_end();
+    let let_type = before
+        .strip_suffix('=')
+        .and_then(|binding| binding.rsplit(['\n', ';', '{']).next())
+        .and_then(|binding| binding.trim().strip_prefix("let "))
+        .and_then(|binding| binding.split_once(':'))
+        .map(|(_, ty)| ty.trim().to_string())
+        .filter(|ty| !ty.is_empty());
+
+    let returned = match let_type {
+        Some(ty) if rest.starts_with('?') => Some(format!("Result<{}, _>", ty)),
+        Some(ty) => Some(ty),
+        None if rest.starts_with('?') => Some("Result<_, _>".to_string()),
+        None => None,
+    };
+
+    format!(
+        "{}fn {}({}){}",
+        if awaited { "async " } else { "" },
+        name,
+        parameters.join(", "),
+        returned.map_or(String::new(), |ty| format!(" -> {}", ty))
+    )
+}
+
+/// Position of the `)` closing a call whose arguments start `after_paren`.
+fn closing_paren(after_paren: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in after_paren.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `base` for `base`, `&self.base` or `config.base.clone()`; `argN`
+/// otherwise.
+fn parameter_name(argument: &str, position: usize) -> String {
+    let argument = argument
+        .trim()
+        .trim_start_matches('&')
+        .trim_start_matches("mut ")
+        .trim();
+    let argument = argument
+        .split('.')
+        .rfind(|part| *part != "self" && !part.contains('('))
+        .unwrap_or(argument);
+    if !argument.is_empty()
+        && argument
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        argument.to_string()
+    } else {
+        format!("arg{}", position)
+    }
+}
+
+/// A function's signature, ending at the `;` of a body-less declaration
+/// (`extern` blocks) instead of running on into the next item.
+fn declared_signature(definition: &str) -> &str {
+    let header = signature(definition);
+    header
+        .split_once(';')
+        .map_or(header, |(declared, _)| declared)
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
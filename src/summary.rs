@@ -0,0 +1,175 @@
+//! The summary printed at the end of a gather: what the output holds, what
+//! the call resolution could not settle, and the follow-up commands that
+//! would widen or check it.
+
+use crate::budget::{self, Tokenizer};
+use crate::index::{ProjectIndex, Resolution};
+use std::collections::HashSet;
+
+/// Guessed calls listed without --show-unresolved.
+const MAX_GUESSES_LISTED: usize = 10;
+
+/// What the run was, for suggesting its follow-ups.
+pub struct Hints<'a> {
+    /// How the tool was invoked: `gather-context`, or `cargo gather-context`
+    pub program: &'a str,
+    /// The project root to repeat in suggested commands; `None` where the
+    /// program finds it itself
+    pub project_root: Option<&'a str>,
+    /// The function the gather started from, when it had a single one
+    pub target: Option<&'a str>,
+    /// The gather walked towards callers
+    pub callers: bool,
+    pub depth: Option<usize>,
+    /// The token budget the run was given, if any
+    pub budget: Option<usize>,
+    /// List the unresolved calls instead of suggesting it
+    pub show_unresolved: bool,
+}
+
+/// The summary of the gathered functions, followed by next-step hints.
+pub fn render(
+    index: &ProjectIndex,
+    tokenizer: &Tokenizer,
+    gathered_names: &[String],
+    hints: &Hints,
+) -> String {
+    let tokens: usize = gathered_names
+        .iter()
+        .map(|name| budget::function_tokens(index, tokenizer, name))
+        .sum();
+
+    // Calls naming no project function, and calls resolved by taking the
+    // first of several functions sharing the name. A path call (`fs::read`)
+    // is also recorded by its last segment, which may resolve instead.
+    let mut unresolved: Vec<(&String, Vec<String>)> = Vec::new();
+    let mut guessed: Vec<(&String, String, String, usize)> = Vec::new();
+    for name in gathered_names {
+        let resolutions = index.call_resolutions(name);
+        let resolved: HashSet<&str> = resolutions
+            .iter()
+            .filter(|(_, resolution)| resolution.is_some())
+            .map(|(call, _)| call.as_str())
+            .collect();
+        let mut missing = Vec::new();
+        for (call, resolution) in &resolutions {
+            match resolution {
+                None => {
+                    let last_segment = call.rsplit("::").next().unwrap_or(call);
+                    if !resolved.contains(last_segment) {
+                        missing.push(call.clone());
+                    }
+                }
+                Some((target, Resolution::FirstOfMany { candidates })) => {
+                    guessed.push((name, call.clone(), target.clone(), *candidates))
+                }
+                Some(_) => {}
+            }
+        }
+        if !missing.is_empty() {
+            unresolved.push((name, missing));
+        }
+    }
+    let unresolved_count: usize = unresolved.iter().map(|(_, calls)| calls.len()).sum();
+
+    let mut output = format!(
+        "Summary: {} functions, {} tokens",
+        gathered_names.len(),
+        tokens
+    );
+    if let Some(budget) = hints.budget {
+        output.push_str(&format!(" of a {} token budget", budget));
+    }
+    output.push('\n');
+    output.push_str(&format!(
+        "  {} calls resolve to no project function (std, dependencies, closures)\n",
+        unresolved_count
+    ));
+    if hints.show_unresolved {
+        for (name, calls) in &unresolved {
+            output.push_str(&format!("    {}: {}\n", name, calls.join(", ")));
+        }
+    }
+    output.push_str(&format!(
+        "  {} calls were guessed among functions sharing a name\n",
+        guessed.len()
+    ));
+    let listed = if hints.show_unresolved {
+        guessed.len()
+    } else {
+        MAX_GUESSES_LISTED
+    };
+    for (name, call, target, candidates) in guessed.iter().take(listed) {
+        output.push_str(&format!(
+            "    {}: {} -> {} (first of {})\n",
+            name, call, target, candidates
+        ));
+    }
+
+    if guessed.len() > listed {
+        output.push_str(&format!(
+            "    ... and {} more (--show-unresolved lists them all)\n",
+            guessed.len() - listed
+        ));
+    }
+
+    let mut next_steps: Vec<(String, &str)> = Vec::new();
+    if let Some(target) = hints.target.filter(|_| !hints.callers) {
+        next_steps.push((
+            match hints.project_root {
+                Some(root) => format!("{} callers {} {}", hints.program, root, target),
+                None => format!("{} callers {}", hints.program, target),
+            },
+            "gather what calls the target",
+        ));
+    }
+    if unresolved_count > 0 && !hints.show_unresolved {
+        next_steps.push((
+            "--show-unresolved".to_string(),
+            "list the calls that resolved to nothing",
+        ));
+    }
+    if let Some(depth) = hints.depth {
+        if stops_short(index, gathered_names, hints.callers) {
+            next_steps.push((
+                format!("--depth {}", depth + 1),
+                "follow one more level of calls",
+            ));
+        }
+    }
+    if !guessed.is_empty() {
+        next_steps.push((
+            "gather-overrides.toml".to_string(),
+            "pin the guessed calls to the right functions",
+        ));
+    }
+    if !next_steps.is_empty() {
+        output.push_str("Next steps:\n");
+        let width = next_steps
+            .iter()
+            .map(|(step, _)| step.len())
+            .max()
+            .unwrap_or(0);
+        for (step, purpose) in next_steps {
+            output.push_str(&format!("  {:width$}  {}\n", step, purpose, width = width));
+        }
+    }
+    output
+}
+
+/// Whether a gathered function calls (or, walking callers, is called by) a
+/// project function the gather left out, so a deeper walk would add more.
+fn stops_short(index: &ProjectIndex, gathered_names: &[String], callers: bool) -> bool {
+    let gathered: HashSet<&String> = gathered_names.iter().collect();
+    if callers {
+        index.function_calls.iter().any(|(caller, callees)| {
+            !gathered.contains(caller) && callees.iter().any(|callee| gathered.contains(callee))
+        })
+    } else {
+        gathered_names
+            .iter()
+            .filter_map(|name| index.function_calls.get(name))
+            .flatten()
+            .any(|callee| !gathered.contains(callee))
+    }
+}
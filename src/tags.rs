@@ -0,0 +1,106 @@
+//! `index --export-tags`: the indexed functions and types as a
+//! universal-ctags file, so editors and other tools can jump to
+//! definitions from the same scan the gathers use.
+
+use crate::index::{without_preamble, ProjectIndex};
+use crate::lang::{is_global_name, preamble_len};
+use crate::Language;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// The tags file for `index`, sorted by tag name. File names are relative
+/// to `tags_dir`, where the file is written, when they are inside it.
+pub fn render(index: &ProjectIndex, project_root: &Path, tags_dir: &Path) -> String {
+    let root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    let tags_dir = tags_dir
+        .canonicalize()
+        .unwrap_or_else(|_| tags_dir.to_path_buf());
+    let file_name = |path: &Path| -> String {
+        let absolute: PathBuf = match path.strip_prefix(project_root) {
+            Ok(relative) => root.join(relative),
+            Err(_) => path.to_path_buf(),
+        };
+        absolute
+            .strip_prefix(&tags_dir)
+            .unwrap_or(&absolute)
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    let mut lines = Vec::new();
+    for (qualified_name, info) in &index.function_definitions {
+        // `module::Type::name#2` is the tag `name`; macros are `name!`
+        let key = qualified_name.split('#').next().unwrap_or(qualified_name);
+        let simple = key.rsplit("::").next().unwrap_or(key);
+        let (name, kind) = match simple.strip_suffix('!') {
+            Some(name) => (name, "M"),
+            None if info.self_type.is_some() => (simple, "P"),
+            None if info.language == Language::Rust && is_global_name(simple) => (simple, "v"),
+            None => (simple, "f"),
+        };
+        // Definitions start at their doc comment; the tag points at the
+        // declaration itself
+        let preamble = &info.definition[..preamble_len(&info.definition)];
+        let line_number = info.line_number + preamble.matches('\n').count();
+        let mut fields = format!("{}\tline:{}", kind, line_number);
+        if let Some(self_type) = &info.self_type {
+            fields.push_str(&format!("\timplementation:{}", self_type));
+        }
+        fields.push_str(&format!("\tlanguage:{}", info.language.name()));
+        lines.push(tag_line(name, &file_name(&info.path), line_number, &fields));
+    }
+
+    let keyword_regex =
+        Regex::new(r"\b(struct|enum|trait|type|union|interface|class|protocol|record)\b").unwrap();
+    for (name, definitions) in &index.type_definitions {
+        for type_info in definitions {
+            let keyword = keyword_regex
+                .captures(without_preamble(&type_info.definition))
+                .map(|captures| captures[1].to_string());
+            let kind = match keyword.as_deref() {
+                Some("enum") => "g",
+                Some("trait" | "interface" | "protocol") => "i",
+                Some("type") => "t",
+                Some("union") => "u",
+                Some("class" | "record") => "c",
+                _ => "s",
+            };
+            let fields = format!(
+                "{}\tline:{}\tlanguage:{}",
+                kind,
+                type_info.line_number,
+                type_info.language.name()
+            );
+            lines.push(tag_line(
+                name,
+                &file_name(&type_info.path),
+                type_info.line_number,
+                &fields,
+            ));
+        }
+    }
+    lines.sort();
+    lines.dedup();
+
+    let mut output = String::from(
+        "!_TAG_FILE_FORMAT\t2\t/extended format; --format=1 will not append ;\" to lines/\n\
+         !_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/\n\
+         !_TAG_PROGRAM_NAME\tgather-context\t//\n",
+    );
+    output.push_str(&format!(
+        "!_TAG_PROGRAM_VERSION\t{}\t//\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    for line in lines {
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+/// One tag, addressed by line number, with its extension fields.
+fn tag_line(name: &str, file: &str, line_number: usize, fields: &str) -> String {
+    format!("{}\t{}\t{};\"\t{}", name, file, line_number, fields)
+}
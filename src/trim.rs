@@ -0,0 +1,184 @@
+//! Token usage of a gather grouped by module, with the `--within` and
+//! `--exclude-module` flags that would bring it under `--budget`, so an
+//! oversized gather can be trimmed in one step instead of by trial and error.
+
+use crate::budget::{self, Tokenizer};
+use crate::index::ProjectIndex;
+use std::collections::HashMap;
+
+/// Whether `module_path` is the module `spec` names or one of its
+/// submodules. `spec` matches whole path segments anywhere in the path, so
+/// `queue` matches `src::queue` and `src::queue::worker`.
+pub fn in_module(module_path: &str, spec: &str) -> bool {
+    format!("::{}::", module_path).contains(&format!("::{}::", spec.trim_matches(':')))
+}
+
+/// The gathered functions inside one of the `within` modules (when any are
+/// given) and outside every `excluded` one. The `targets` are always kept.
+pub fn filter(
+    index: &ProjectIndex,
+    gathered_names: Vec<String>,
+    within: &[String],
+    excluded: &[String],
+    targets: &[String],
+) -> Vec<String> {
+    gathered_names
+        .into_iter()
+        .filter(|name| {
+            let module_path = &index.function_definitions[name].module_path;
+            targets.contains(name)
+                || ((within.is_empty() || within.iter().any(|spec| in_module(module_path, spec)))
+                    && !excluded.iter().any(|spec| in_module(module_path, spec)))
+        })
+        .collect()
+}
+
+/// Functions and tokens per module, largest first, and the total tokens.
+/// Over `budget`, ends with the flags that would fit the gather into it.
+pub fn report(
+    index: &ProjectIndex,
+    tokenizer: &Tokenizer,
+    gathered_names: &[String],
+    budget: Option<usize>,
+) -> String {
+    let costs: Vec<(&str, usize)> = gathered_names
+        .iter()
+        .map(|name| {
+            (
+                index.function_definitions[name].module_path.as_str(),
+                budget::function_tokens(index, tokenizer, name),
+            )
+        })
+        .collect();
+    let total: usize = costs.iter().map(|(_, tokens)| tokens).sum();
+
+    let mut modules: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (module_path, tokens) in &costs {
+        let entry = modules.entry(module_path).or_default();
+        entry.0 += 1;
+        entry.1 += tokens;
+    }
+    let mut modules: Vec<(&str, (usize, usize))> = modules.into_iter().collect();
+    modules.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then(a.0.cmp(b.0)));
+
+    let width = modules
+        .iter()
+        .map(|(module_path, _)| module_path.len())
+        .max()
+        .unwrap_or(0);
+    let mut output = String::from("Tokens by module:\n");
+    for (module_path, (functions, tokens)) in &modules {
+        output.push_str(&format!(
+            "  {:<width$}  {:>4} functions  {:>7} tokens  {:>3}%\n",
+            module_path,
+            functions,
+            tokens,
+            tokens * 100 / total.max(1),
+            width = width
+        ));
+    }
+
+    let Some(budget) = budget.filter(|budget| total > *budget) else {
+        return output;
+    };
+    output.push_str(&format!(
+        "~{} tokens is {} over the budget of {}.\n",
+        total,
+        total - budget,
+        budget
+    ));
+    let target_module = costs.first().map_or("", |(module_path, _)| module_path);
+    let mut suggested = false;
+
+    if let Some((spec, tokens)) = broadest_within(&costs, target_module, budget) {
+        output.push_str(&format!("  --within {}  keeps ~{} tokens\n", spec, tokens));
+        suggested = true;
+    }
+    if let Some((specs, tokens)) = exclusions(&costs, target_module, budget) {
+        let flags: Vec<String> = specs
+            .iter()
+            .map(|spec| format!("--exclude-module {}", spec))
+            .collect();
+        output.push_str(&format!(
+            "  {}  keeps ~{} tokens\n",
+            flags.join(" "),
+            tokens
+        ));
+        suggested = true;
+    }
+    if !suggested {
+        output.push_str("  No module filter fits; try --depth or --fill-budget instead.\n");
+    }
+    output
+}
+
+/// The broadest module around the target whose functions fit the budget,
+/// with the tokens they cost. The target itself always stays.
+fn broadest_within(
+    costs: &[(&str, usize)],
+    target_module: &str,
+    budget: usize,
+) -> Option<(String, usize)> {
+    let segments: Vec<&str> = target_module.split("::").collect();
+    // Shortest prefix first; the whole crate is what is already over budget
+    (2..=segments.len())
+        .map(|length| segments[..length].join("::"))
+        .map(|spec| {
+            let tokens: usize = costs
+                .iter()
+                .enumerate()
+                .filter(|(i, (module_path, _))| *i == 0 || in_module(module_path, &spec))
+                .map(|(_, (_, tokens))| tokens)
+                .sum();
+            (spec, tokens)
+        })
+        .find(|(_, tokens)| *tokens <= budget)
+}
+
+/// Modules to exclude to fit the budget, never the target's own module or
+/// one containing it.
+fn exclusions(
+    costs: &[(&str, usize)],
+    target_module: &str,
+    budget: usize,
+) -> Option<(Vec<String>, usize)> {
+    let mut remaining: Vec<(&str, usize)> = costs[1.min(costs.len())..].to_vec();
+    let target_tokens = costs.first().map_or(0, |(_, tokens)| *tokens);
+    let mut excluded = Vec::new();
+
+    loop {
+        let tokens = target_tokens + remaining.iter().map(|(_, tokens)| tokens).sum::<usize>();
+        if tokens <= budget {
+            return Some((excluded, tokens));
+        }
+
+        // Every module and enclosing module of what remains, with its subtree
+        let mut subtrees: HashMap<String, usize> = HashMap::new();
+        for (module_path, tokens) in &remaining {
+            let segments: Vec<&str> = module_path.split("::").collect();
+            for length in 1..=segments.len() {
+                *subtrees.entry(segments[..length].join("::")).or_default() += tokens;
+            }
+        }
+        let candidates: Vec<(String, usize)> = subtrees
+            .into_iter()
+            .filter(|(spec, _)| !in_module(target_module, spec))
+            .collect();
+        // The smallest module that is enough on its own keeps the most,
+        // otherwise the largest one gets closest
+        let over = tokens - budget;
+        let (chosen, _) = match candidates
+            .iter()
+            .filter(|(_, subtree)| *subtree >= over)
+            .min_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)))
+        {
+            Some(enough) => enough,
+            None => candidates
+                .iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))?,
+        };
+
+        remaining.retain(|(module_path, _)| !in_module(module_path, chosen));
+        excluded.push(chosen.clone());
+    }
+}
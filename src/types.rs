@@ -0,0 +1,87 @@
+//! The struct, enum, union and trait definitions gathered functions use in
+//! their signatures or bodies (and the types methods belong to), with
+//! derives and fields, so a model sees the data model the code works on.
+//! `--type-closure` follows their fields and variants to the types those
+//! use in turn, for the complete data model rather than its top level.
+
+use crate::aliases;
+use crate::index::ProjectIndex;
+use crate::{FunctionInfo, TypeInfo};
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+
+/// The referenced type definitions in order of first use, as a section to
+/// append after the code; empty when there are none. Aliases and newtypes
+/// are left to their own section.
+///
+/// With a `closure` depth, the types named in those definitions (and in the
+/// aliases and newtypes) follow, up to that many levels down. Types in
+/// `shown` are already in the output and left out.
+pub fn render(
+    index: &ProjectIndex,
+    gathered: &[&FunctionInfo],
+    closure: Option<usize>,
+    shown: &[&TypeInfo],
+) -> String {
+    let types = collect(index, gathered, closure, shown);
+    if types.is_empty() {
+        return String::new();
+    }
+
+    let mut output = String::from("\n=== TYPE DEFINITIONS ===\n");
+    for type_info in types {
+        output.push_str(&format!(
+            "\n=== {}:{} ===\n{}\n",
+            type_info.path.to_string_lossy(),
+            type_info.line_number,
+            type_info.definition
+        ));
+    }
+    output.push('\n');
+    output
+}
+
+/// The type definitions `render` shows, in order.
+pub fn collect<'a>(
+    index: &'a ProjectIndex,
+    gathered: &[&FunctionInfo],
+    closure: Option<usize>,
+    shown: &[&TypeInfo],
+) -> Vec<&'a TypeInfo> {
+    let small_types = aliases::small_types(index, gathered);
+    let mut seen: HashSet<(&PathBuf, usize)> = small_types
+        .iter()
+        .chain(shown)
+        .map(|t| (&t.path, t.line_number))
+        .collect();
+    let mut types: Vec<&TypeInfo> = gathered
+        .iter()
+        .flat_map(|info| {
+            // A method's own type, then the types its code names
+            let self_type = info.self_type.as_deref().unwrap_or_default();
+            index
+                .types_in(self_type, info.language)
+                .into_iter()
+                .chain(index.types_in(&info.definition, info.language))
+        })
+        .filter(|t| seen.insert((&t.path, t.line_number)))
+        .collect();
+
+    if let Some(max_depth) = closure {
+        let mut queue: VecDeque<(&TypeInfo, usize)> =
+            small_types.iter().chain(&types).map(|t| (*t, 0)).collect();
+        while let Some((type_info, depth)) = queue.pop_front() {
+            if depth >= max_depth {
+                continue;
+            }
+            for field_type in index.types_in(&type_info.definition, type_info.language) {
+                if seen.insert((&field_type.path, field_type.line_number)) {
+                    types.push(field_type);
+                    queue.push_back((field_type, depth + 1));
+                }
+            }
+        }
+    }
+
+    types
+}
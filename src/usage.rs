@@ -0,0 +1,112 @@
+//! Usage framing for orphan functions. A utility nothing in the project
+//! calls is shown without any hint of how it is meant to be used, so the
+//! best invocation from tests, `examples/` or doc tests is added instead.
+
+use crate::index::ProjectIndex;
+use crate::FunctionInfo;
+use std::path::Path;
+
+struct Usage {
+    source: String,
+    snippet: String,
+}
+
+/// The usage section for `target` when no regular project code calls it,
+/// or `None` when it has callers.
+pub fn orphan_usage(index: &ProjectIndex, target: &str) -> Option<String> {
+    let is_example = |info: &FunctionInfo| in_examples(&info.path);
+
+    let calls_target = |name: &String| {
+        name != target
+            && index
+                .function_calls
+                .get(name)
+                .is_some_and(|calls| calls.contains(target))
+    };
+    let has_callers = index
+        .function_definitions
+        .iter()
+        .any(|(name, info)| !info.is_test && !is_example(info) && calls_target(name));
+    if has_callers {
+        return None;
+    }
+
+    let mut candidates: Vec<Usage> = Vec::new();
+
+    // Tests and examples that call it
+    let mut callers: Vec<(&String, &FunctionInfo)> = index
+        .function_definitions
+        .iter()
+        .filter(|(name, info)| (info.is_test || is_example(info)) && calls_target(name))
+        .collect();
+    callers.sort_by_key(|(name, _)| *name);
+    for (name, info) in callers {
+        let kind = if info.is_test { "test" } else { "example" };
+        candidates.push(Usage {
+            source: format!(
+                "{} {} ({}:{})",
+                kind,
+                name,
+                info.path.to_string_lossy(),
+                info.line_number
+            ),
+            snippet: info.definition.clone(),
+        });
+    }
+
+    // Doc tests anywhere in the project that mention it
+    let simple_name = target.rsplit("::").next().unwrap_or(target);
+    let invocation = format!("{}(", simple_name);
+    let mut documented: Vec<(&String, &FunctionInfo)> = index.function_definitions.iter().collect();
+    documented.sort_by_key(|(name, _)| *name);
+    for (name, info) in documented {
+        let Some(doc) = &info.doc else {
+            continue;
+        };
+        for block in code_blocks(doc) {
+            if block.contains(&invocation) {
+                candidates.push(Usage {
+                    source: format!("doc test on {}", name),
+                    snippet: block,
+                });
+            }
+        }
+    }
+
+    // The shortest snippet shows the call with the least noise
+    let best = candidates.iter().min_by_key(|u| u.snippet.lines().count());
+    Some(match best {
+        Some(usage) => format!(
+            "\n=== USAGE: {} has no callers in the project; example from {} ===\n{}\n\n",
+            target, usage.source, usage.snippet
+        ),
+        None => format!(
+            "\n=== USAGE: {} has no callers in the project, and no test, example or doc test uses it ===\n\n",
+            target
+        ),
+    })
+}
+
+fn in_examples(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == "examples")
+}
+
+/// The fenced code blocks of a doc comment, without rustdoc's hidden `# `
+/// lines.
+fn code_blocks(doc: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in doc.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(lines) => blocks.push(lines.join("\n")),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(lines) = current.as_mut() {
+            if line.trim() != "#" && !line.trim_start().starts_with("# ") {
+                lines.push(line);
+            }
+        }
+    }
+    blocks
+}
@@ -0,0 +1,347 @@
+//! `validate`: measures the regex-based call edges against rust-analyzer's
+//! call hierarchy, so heuristic changes can be evaluated with numbers.
+
+use crate::index::{without_preamble, ProjectIndex};
+use crate::FunctionInfo;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for rust-analyzer to finish loading the workspace.
+const INDEXING_TIMEOUT: Duration = Duration::from_secs(300);
+/// How long to wait for a single request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct ValidateOptions {
+    pub sample_size: usize,
+    pub rust_analyzer: String,
+}
+
+/// The Rust functions to validate: `options.sample_size` of them, evenly
+/// spaced so the sample covers the whole project deterministically, and
+/// how many there are to sample from.
+pub fn sample<'a>(index: &'a ProjectIndex, options: &ValidateOptions) -> (Vec<&'a String>, usize) {
+    let mut candidates: Vec<&String> = index
+        .function_definitions
+        .iter()
+        .filter(|(_, info)| info.language == crate::Language::Rust)
+        .map(|(name, _)| name)
+        .collect();
+    candidates.sort();
+
+    let step = (candidates.len() / options.sample_size.max(1)).max(1);
+    let sample = candidates
+        .iter()
+        .step_by(step)
+        .take(options.sample_size)
+        .copied()
+        .collect();
+    (sample, candidates.len())
+}
+
+/// Compares the index's resolved call edges for the `sample` functions with
+/// rust-analyzer's outgoing calls. Returns the report: the functions whose
+/// edges differ, then precision and recall.
+pub fn run(
+    index: &ProjectIndex,
+    project_root: &Path,
+    options: &ValidateOptions,
+    sample: &[&String],
+) -> io::Result<String> {
+    let project_root = project_root.canonicalize()?;
+
+    // Map (file, 0-based line) back to qualified names to translate results
+    let mut by_location: HashMap<(PathBuf, usize), &String> = HashMap::new();
+    for (name, info) in &index.function_definitions {
+        if let Ok(path) = info.path.canonicalize() {
+            by_location.insert((path, fn_line(info) - 1), name);
+        }
+    }
+
+    let mut client = LspClient::start(&options.rust_analyzer, &project_root)?;
+    client.wait_until_ready()?;
+
+    let (mut true_positives, mut false_positives, mut false_negatives) = (0, 0, 0);
+    let mut output = String::new();
+
+    for &name in sample {
+        let info = &index.function_definitions[name];
+        let Some(position) = name_position(&info.path, fn_line(info), name) else {
+            continue;
+        };
+        let path = info.path.canonicalize()?;
+
+        let expected: HashSet<&String> = client
+            .outgoing_calls(&path, position)?
+            .into_iter()
+            .filter_map(|location| by_location.get(&location).copied())
+            .collect();
+        let actual: HashSet<&String> = index
+            .function_calls
+            .get(name)
+            .map(|calls| calls.iter().filter(|c| *c != name).collect())
+            .unwrap_or_default();
+
+        let missing: Vec<&&String> = expected.difference(&actual).collect();
+        let spurious: Vec<&&String> = actual.difference(&expected).collect();
+        true_positives += expected.intersection(&actual).count();
+        false_positives += spurious.len();
+        false_negatives += missing.len();
+
+        if !missing.is_empty() || !spurious.is_empty() {
+            output.push_str(&format!("{}\n", name));
+            for callee in missing {
+                output.push_str(&format!("  missing  {}\n", callee));
+            }
+            for callee in spurious {
+                output.push_str(&format!("  spurious {}\n", callee));
+            }
+        }
+    }
+
+    client.shutdown();
+
+    let ratio = |hits: usize, misses: usize| {
+        if hits + misses == 0 {
+            1.0
+        } else {
+            hits as f64 / (hits + misses) as f64
+        }
+    };
+    output.push_str(&format!(
+        "\nEdges: {} correct, {} spurious, {} missing\n",
+        true_positives, false_positives, false_negatives
+    ));
+    output.push_str(&format!(
+        "Precision: {:.3}\nRecall:    {:.3}\n",
+        ratio(true_positives, false_positives),
+        ratio(true_positives, false_negatives)
+    ));
+
+    Ok(output)
+}
+
+/// The line of a function's `fn` keyword, past its doc comment and
+/// attributes.
+fn fn_line(info: &FunctionInfo) -> usize {
+    let preamble_len = info.definition.len() - without_preamble(&info.definition).len();
+    info.line_number + info.definition[..preamble_len].matches('\n').count()
+}
+
+/// LSP position (0-based line, UTF-16 column) of a function's name.
+fn name_position(path: &Path, line_number: usize, qualified_name: &str) -> Option<(usize, usize)> {
+    let simple_name = qualified_name.rsplit("::").next()?;
+    let content = fs::read_to_string(path).ok()?;
+    let line = content.lines().nth(line_number - 1)?;
+    let column = line.find(&format!("fn {}", simple_name))? + 3;
+    Some((line_number - 1, line[..column].encode_utf16().count()))
+}
+
+/// A minimal LSP client speaking JSON-RPC over rust-analyzer's stdio.
+struct LspClient {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: u64,
+}
+
+impl LspClient {
+    fn start(command: &str, root: &Path) -> io::Result<LspClient> {
+        let mut child = Command::new(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!(
+                        "could not start '{}': {} (is rust-analyzer installed?)",
+                        command, err
+                    ),
+                )
+            })?;
+
+        let stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+        // Messages are read on a separate thread so requests can time out
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(Some(message)) = read_message(&mut stdout) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut client = LspClient {
+            child,
+            stdin,
+            messages,
+            next_id: 0,
+        };
+
+        client.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": path_to_uri(root),
+                "capabilities": {
+                    "window": { "workDoneProgress": true },
+                    "experimental": { "serverStatusNotification": true },
+                },
+            }),
+        )?;
+        client.notify("initialized", json!({}))?;
+
+        Ok(client)
+    }
+
+    /// Blocks until rust-analyzer reports it has finished loading and
+    /// analyzing the workspace.
+    fn wait_until_ready(&mut self) -> io::Result<()> {
+        let deadline = Instant::now() + INDEXING_TIMEOUT;
+        loop {
+            let message = self.receive(deadline)?;
+            if message["method"] == "experimental/serverStatus"
+                && message["params"]["quiescent"] == true
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Resolved `(file, 0-based line)` locations of the functions called by
+    /// the function whose name is at `position`.
+    fn outgoing_calls(
+        &mut self,
+        path: &Path,
+        (line, character): (usize, usize),
+    ) -> io::Result<Vec<(PathBuf, usize)>> {
+        let items = self.request(
+            "textDocument/prepareCallHierarchy",
+            json!({
+                "textDocument": { "uri": path_to_uri(path) },
+                "position": { "line": line, "character": character },
+            }),
+        )?;
+
+        let mut locations = Vec::new();
+        for item in items.as_array().into_iter().flatten() {
+            let calls = self.request("callHierarchy/outgoingCalls", json!({ "item": item }))?;
+            for call in calls.as_array().into_iter().flatten() {
+                let to = &call["to"];
+                let (Some(uri), Some(line)) = (
+                    to["uri"].as_str(),
+                    to["selectionRange"]["start"]["line"].as_u64(),
+                ) else {
+                    continue;
+                };
+                if let Some(path) = uri_to_path(uri).and_then(|p| p.canonicalize().ok()) {
+                    locations.push((path, line as usize));
+                }
+            }
+        }
+
+        Ok(locations)
+    }
+
+    fn shutdown(mut self) {
+        let _ = self.request("shutdown", Value::Null);
+        let _ = self.notify("exit", Value::Null);
+        let _ = self.child.wait();
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> io::Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        loop {
+            let message = self.receive(deadline)?;
+            if message["id"] == id && message.get("method").is_none() {
+                if let Some(error) = message.get("error") {
+                    return Err(io::Error::other(format!("{} failed: {}", method, error)));
+                }
+                return Ok(message["result"].clone());
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    /// Receives the next message, answering any server-to-client requests
+    /// (progress tokens, capability registration) along the way.
+    fn receive(&mut self, deadline: Instant) -> io::Result<Value> {
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let message = self.messages.recv_timeout(timeout).map_err(|_| {
+                io::Error::new(io::ErrorKind::TimedOut, "rust-analyzer did not respond")
+            })?;
+
+            if message.get("id").is_some() && message.get("method").is_some() {
+                self.send(&json!({ "jsonrpc": "2.0", "id": message["id"], "result": null }))?;
+                continue;
+            }
+            return Ok(message);
+        }
+    }
+
+    fn send(&mut self, message: &Value) -> io::Result<()> {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+        self.stdin.flush()
+    }
+}
+
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let Some(length) = content_length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing Content-Length",
+        ));
+    };
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let path = path
+        .to_string_lossy()
+        .replace('%', "%25")
+        .replace(' ', "%20");
+    format!("file://{}", path)
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    Some(PathBuf::from(path.replace("%20", " ").replace("%25", "%")))
+}
@@ -0,0 +1,73 @@
+//! `--inline-thin-wrappers`: functions whose whole body forwards to one
+//! other project function are shown as a single line naming the function
+//! they forward to, leaving the budget to the implementation while the call
+//! chain stays visible.
+
+use crate::index::{self, ProjectIndex};
+use regex::Regex;
+
+/// Ends the one line a wrapper is shortened to, before the qualified name
+/// of the function it forwards to.
+const MARKER: &str = "// thin wrapper, forwards to ";
+
+/// Shortens every thin wrapper in the index to one line, except `roots`,
+/// which are what was asked about.
+pub fn inline(index: &mut ProjectIndex, roots: &[String]) {
+    let forward_regex = forward_regex();
+    let shortened: Vec<(String, String)> = index
+        .function_definitions
+        .keys()
+        .filter(|name| !roots.contains(name))
+        .filter_map(|name| Some((name.clone(), shorten(index, name, &forward_regex)?)))
+        .collect();
+    for (name, definition) in shortened {
+        if let Some(info) = index.function_definitions.get_mut(&name) {
+            info.definition = definition;
+        }
+    }
+}
+
+/// The one line `name` is shown as when it is a thin wrapper: its signature
+/// with the body elided, and the function it forwards to.
+pub fn one_line(index: &ProjectIndex, name: &str) -> Option<String> {
+    shorten(index, name, &forward_regex())
+}
+
+/// Whether a definition is a wrapper shortened by `inline`.
+pub fn is_inlined(definition: &str) -> bool {
+    definition.contains(MARKER)
+}
+
+fn shorten(index: &ProjectIndex, name: &str, forward_regex: &Regex) -> Option<String> {
+    let info = index.function_definitions.get(name)?;
+    // Macro templates and globals have no body to forward from
+    if info.is_test || name.ends_with('!') || is_inlined(&info.definition) {
+        return None;
+    }
+    let callees = index.function_calls.get(name)?;
+    let [target] = callees.iter().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+
+    let body = index::body(&info.definition).trim();
+    let statement = body.strip_prefix('{')?.strip_suffix('}')?.trim();
+    if !forward_regex.is_match(statement) {
+        return None;
+    }
+    Some(format!(
+        "{} {{ ... }} {}{}",
+        index::flat_signature(&info.definition),
+        MARKER,
+        target
+    ))
+}
+
+/// A body that is one call, optionally returned, awaited or followed by
+/// `?`, whose arguments are plain values (`&path`, `id.into()`) rather than
+/// further calls.
+fn forward_regex() -> Regex {
+    Regex::new(
+        r"^(?:return\s+)?(?:try\s+)?(?:await\s+)?(?:[A-Za-z_]\w*(?:::|\.))*[A-Za-z_]\w*(?:::<[^>]*>)?\s*\((?:[^(){};]|\(\))*\)(?:\.await)?\??;?$",
+    )
+    .unwrap()
+}
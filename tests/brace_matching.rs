@@ -0,0 +1,139 @@
+//! Function bodies end at their own closing brace, whatever braces their
+//! literals, comments, closures and default arguments contain, so one
+//! function never bleeds into the next.
+
+#![cfg(feature = "regex-backend")]
+
+mod common;
+
+// Rust files go through the regex extraction and its brace matching even
+// where they would parse
+use common::{calls, definition, regex_items as items};
+
+#[test]
+fn rust_literals_and_comments() {
+    let items = items(
+        "src/lib.rs",
+        r##"
+fn braces() -> Vec<char> {
+    let open = '{';
+    let escaped = '\'';
+    let close = "}}";
+    let raw = r#"}" {"#;
+    // }
+    /* } /* nested } */ */
+    vec![open, escaped]
+}
+
+fn lifetimes<'a>(x: &'a str) -> &'a str {
+    'outer: loop {
+        break 'outer;
+    }
+    x
+}
+
+fn after() {
+    done();
+}
+"##,
+    );
+
+    assert!(definition(&items, "braces").ends_with("vec![open, escaped]\n}"));
+    assert!(definition(&items, "lifetimes").ends_with("    x\n}"));
+    assert_eq!(calls(&items, "after"), ["done"]);
+}
+
+#[test]
+fn rust_async_blocks_and_closures() {
+    let items = items(
+        "src/lib.rs",
+        r#"
+async fn spawn_all() {
+    let task = async move {
+        let s = "{";
+        run(s).await
+    };
+    let callback = || {};
+    let pick = |x: u8| if x > 0 { '}' } else { '{' };
+    task.await;
+}
+
+fn next() {
+    other();
+}
+"#,
+    );
+
+    assert!(definition(&items, "spawn_all").ends_with("task.await;\n}"));
+    assert_eq!(calls(&items, "next"), ["other"]);
+}
+
+#[test]
+fn typescript_default_arguments_and_strings() {
+    let items = items(
+        "web/api.ts",
+        r#"
+function withDefaults(cb = () => {}, opts = { retries: 3 }) {
+  return cb(opts);
+}
+
+function quoted() {
+  const single = '}';
+  const template = `${"{"} }`;
+  return proceed();
+}
+
+function last() {
+  finish();
+}
+"#,
+    );
+
+    assert!(definition(&items, "withDefaults").ends_with("return cb(opts);\n}"));
+    assert!(definition(&items, "quoted").ends_with("return proceed();\n}"));
+    assert!(calls(&items, "last").contains(&"finish".to_string()));
+}
+
+#[test]
+fn typescript_class_members_after_braces_in_strings() {
+    let items = items(
+        "web/socket.ts",
+        r#"
+class Socket {
+  open() {
+    this.send("{");
+    this.log(`}} ${"{"}`); // {
+  }
+
+  close() {
+    this.send("}");
+  }
+}
+"#,
+    );
+
+    assert!(definition(&items, "Socket::open").ends_with("// {\n  }"));
+    assert_eq!(calls(&items, "Socket::close"), ["Self::send"]);
+}
+
+#[test]
+fn go_runes_and_raw_strings() {
+    let items = items(
+        "cmd/main.go",
+        "package main\n\nfunc a() string {\n\tr := '{'\n\ts := `}} \\`\n\treturn s + string(r)\n}\n\nfunc b() {\n\tc()\n}\n",
+    );
+
+    assert!(definition(&items, "a").ends_with("return s + string(r)\n}"));
+    assert_eq!(calls(&items, "b"), ["c"]);
+}
+
+#[test]
+fn swift_default_closures() {
+    let items = items(
+        "App/Loader.swift",
+        "func load(completion: () -> Void = {}) {\n    fetch()\n}\n\nfunc other() {\n    done()\n}\n",
+    );
+
+    assert!(definition(&items, "load").ends_with("fetch()\n}"));
+    assert!(calls(&items, "other").contains(&"done".to_string()));
+}
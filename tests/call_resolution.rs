@@ -0,0 +1,140 @@
+//! Calls resolve the same way on every run: a plain `name()` call never
+//! reaches a method and a `.name()` call never a free function or an
+//! associated function without a `self` receiver, and a tie
+//! among methods goes to the first one in the file and counts as a guess.
+
+mod common;
+
+use common::project;
+use gather_context::index::{IndexOptions, ProjectIndex, Resolution};
+use std::collections::HashSet;
+use std::fs;
+
+fn callees(index: &ProjectIndex, name: &str) -> Vec<String> {
+    let mut callees: Vec<String> = index.function_calls[name].iter().cloned().collect();
+    callees.sort();
+    callees
+}
+
+#[test]
+fn plain_and_method_calls_resolve_by_kind_on_every_run() {
+    let root = project(
+        "call-kinds",
+        &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"kinds\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("src/lib.rs", "pub mod a;\npub mod b;\npub mod c;\n"),
+            (
+                "src/a.rs",
+                r#"
+use crate::b::{Bar, Foo, S};
+
+pub fn entry(f: &Foo, s: &S) {
+    crate::b::helper();
+    f.run();
+    s.inner();
+}
+"#,
+            ),
+            (
+                "src/b.rs",
+                r#"
+pub struct S;
+pub struct Foo;
+pub struct Bar;
+
+impl S {
+    pub fn inner(&self) {}
+}
+
+pub fn inner() {}
+
+pub fn helper() {
+    inner();
+}
+
+impl Foo {
+    pub fn run(&self) {}
+}
+
+impl Bar {
+    pub fn run(&self) {}
+}
+"#,
+            ),
+            ("src/c.rs", "pub fn unrelated() {}\n"),
+        ],
+    );
+
+    let mut runs = HashSet::new();
+    for _ in 0..5 {
+        let index = ProjectIndex::build(&root, &IndexOptions::default()).unwrap();
+        assert_eq!(callees(&index, "src::b::helper"), ["src::b::inner"]);
+        assert_eq!(
+            callees(&index, "src::a::entry"),
+            ["src::b::Foo::run", "src::b::S::inner", "src::b::helper"]
+        );
+        let run = index
+            .call_resolutions("src::a::entry")
+            .into_iter()
+            .find(|(call, _)| call == "run")
+            .and_then(|(_, resolution)| resolution);
+        assert!(matches!(
+            run,
+            Some((_, Resolution::FirstOfMany { candidates: 2 }))
+        ));
+        runs.insert(format!(
+            "{:?}",
+            index.reachable_within(&["src::a::entry".to_string()], None)
+        ));
+    }
+    assert_eq!(
+        runs.len(),
+        1,
+        "the gather order changed between runs: {:?}",
+        runs
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn method_calls_skip_associated_functions() {
+    let root = project(
+        "call-receivers",
+        &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"receivers\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            (
+                "src/lib.rs",
+                r#"
+pub struct Source;
+
+impl Source {
+    pub fn find(path: &str) -> Option<Source> {
+        None
+    }
+
+    pub fn parent<'a>(&'a mut self) -> usize {
+        0
+    }
+}
+
+pub fn first_even(values: &[u32], source: &mut Source) -> Option<u32> {
+    source.parent();
+    values.iter().copied().find(|value| value % 2 == 0)
+}
+"#,
+            ),
+        ],
+    );
+
+    let index = ProjectIndex::build(&root, &IndexOptions::default()).unwrap();
+    assert_eq!(callees(&index, "src::first_even"), ["src::Source::parent"]);
+
+    fs::remove_dir_all(&root).unwrap();
+}
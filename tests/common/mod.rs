@@ -0,0 +1,57 @@
+//! Helpers shared by the integration tests: the items extracted from one
+//! source file, and throwaway projects to index.
+
+// Each test file uses some of them
+#![allow(dead_code)]
+
+use gather_context::lang::{extract, ExtractOptions, FileItems, RustBackend};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The items of `content` as the file `file_name`, extracted with the
+/// default options.
+pub fn items(file_name: &str, content: &str) -> FileItems {
+    extract(
+        content,
+        Path::new(file_name),
+        "src",
+        &ExtractOptions::default(),
+    )
+}
+
+/// Like [`items`], but Rust goes through the regex extraction even where
+/// it would parse.
+pub fn regex_items(file_name: &str, content: &str) -> FileItems {
+    let options = ExtractOptions {
+        rust_backend: RustBackend::Regex,
+        ..ExtractOptions::default()
+    };
+    extract(content, Path::new(file_name), "src", &options)
+}
+
+pub fn definition<'a>(items: &'a FileItems, name: &str) -> &'a str {
+    match items.functions.get(name) {
+        Some(info) => &info.definition,
+        None => panic!("{} not extracted: {:?}", name, items.functions.keys()),
+    }
+}
+
+/// The calls of `name`, sorted.
+pub fn calls(items: &FileItems, name: &str) -> Vec<String> {
+    let mut calls: Vec<String> = items.functions[name].calls.iter().cloned().collect();
+    calls.sort();
+    calls
+}
+
+/// A project of `files` (paths relative to its root, and contents) in the
+/// temporary directory, replacing what an earlier run left there.
+pub fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("gather-context-test-{}", name));
+    let _ = fs::remove_dir_all(&root);
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    root
+}
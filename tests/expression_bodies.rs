@@ -0,0 +1,51 @@
+//! Expression-bodied functions (arrow functions, C# `=>` members) end at
+//! the end of their expression, and Go function literals assigned to a
+//! name are indexed as functions.
+
+#![cfg(feature = "regex-backend")]
+
+mod common;
+
+use common::{calls, definition, items};
+
+#[test]
+fn typescript_expression_bodies() {
+    let items = items(
+        "web/util.ts",
+        r#"
+export const label = (x: number): string => "a;b" + format(x);
+
+const tpl = (name: string) => `hello ${name}; ${greet(name)}`;
+
+export const handler: (req: Request) => Promise<void> = async (req) => {
+  await respond(req);
+};
+"#,
+    );
+
+    assert!(definition(&items, "label").ends_with("format(x)"));
+    assert!(definition(&items, "tpl").ends_with("${greet(name)}`"));
+    assert!(definition(&items, "handler").ends_with("await respond(req);\n}"));
+}
+
+#[test]
+fn csharp_expression_bodies() {
+    let items = items(
+        "Services/Calc.cs",
+        "public class Calc\n{\n    public string Label(int x) => $\"a;{x}\" + Format(x);\n    public void After() { Done(); }\n}\n",
+    );
+
+    assert!(definition(&items, "Calc::Label").ends_with("Format(x);"));
+    assert_eq!(calls(&items, "Calc::After"), ["Done"]);
+}
+
+#[test]
+fn go_function_literals() {
+    let items = items(
+        "svc/main.go",
+        "package svc\n\nvar handler = func(w Writer) {\n\tserve(w)\n}\n\nfunc after() {\n\tdone()\n}\n",
+    );
+
+    assert!(definition(&items, "handler").ends_with("serve(w)\n}"));
+    assert_eq!(calls(&items, "after"), ["done"]);
+}
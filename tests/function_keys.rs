@@ -0,0 +1,33 @@
+//! No function replaces another of the same name in a file: methods are
+//! keyed by their type, and further definitions sharing a key are numbered.
+
+#![cfg(feature = "regex-backend")]
+
+mod common;
+
+use common::{calls, definition, items};
+
+#[test]
+fn methods_sharing_a_name_are_all_kept() {
+    let swift = items(
+        "Sources/Model.swift",
+        "class Foo {\n    func update() { a() }\n}\nstruct Bar {\n    func update() { b() }\n    func update(_ x: Int) { c() }\n}\n",
+    );
+    assert!(definition(&swift, "Foo::update").ends_with("a() }"));
+    assert!(definition(&swift, "Bar::update").ends_with("b() }"));
+    assert!(definition(&swift, "Bar::update#2").ends_with("c() }"));
+
+    let objc = items(
+        "Sources/Model.m",
+        "@implementation Foo\n- (void)setup { a(); }\n@end\n@implementation Bar\n- (void)setup { b(); }\n@end\n",
+    );
+    assert_eq!(calls(&objc, "Foo::setup"), ["a"]);
+    assert_eq!(calls(&objc, "Bar::setup"), ["b"]);
+
+    let typescript = items(
+        "src/box.ts",
+        "class Box {\n  get value() { return read(); }\n  set value(v) { write(v); }\n}\n",
+    );
+    assert_eq!(calls(&typescript, "Box::value"), ["read"]);
+    assert_eq!(calls(&typescript, "Box::value#2"), ["write"]);
+}
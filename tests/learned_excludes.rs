@@ -0,0 +1,102 @@
+//! Functions left out with `--ban` are counted per project, suggested for
+//! `.gather-context.toml` once left out often, and skipped by
+//! `--learned-excludes`.
+
+mod common;
+
+use common::project;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// `process_queue` calling `drain` and `audit`.
+const FILES: &[(&str, &str)] = &[
+    (
+        "Cargo.toml",
+        "[package]\nname = \"learned\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    ),
+    (
+        "src/lib.rs",
+        "pub fn process_queue() {\n    drain();\n    audit();\n}\n\nfn drain() {}\n\nfn audit() {}\n",
+    ),
+];
+
+/// Gathers `process_queue` with `flags`, returning stdout and stderr.
+fn gather(root: &Path, flags: &[&str]) -> (String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_gather-context"))
+        .arg("gather")
+        .arg(root)
+        .arg("process_queue")
+        .arg("--non-interactive")
+        .args(flags)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn repeated_bans_are_suggested_and_learned() {
+    let root = project("learned-excludes", FILES);
+
+    let (stdout, _) = gather(&root, &["--ban", "src::audit"]);
+    assert!(!stdout.contains("fn audit"));
+    assert!(stdout.contains("fn drain"));
+
+    let (_, stderr) = gather(&root, &["--ban", "audit"]);
+    assert!(!stderr.contains("was left out"), "{}", stderr);
+    let (_, stderr) = gather(&root, &["--ban", "audit"]);
+    assert!(
+        stderr.contains("'audit' was left out in 3 runs; add it to [calls] skip"),
+        "{}",
+        stderr
+    );
+
+    // Learned, but only applied when asked for
+    let (stdout, _) = gather(&root, &[]);
+    assert!(stdout.contains("fn audit"));
+    let (stdout, _) = gather(&root, &["--learned-excludes"]);
+    assert!(!stdout.contains("fn audit"));
+    assert!(stdout.contains("fn drain"));
+
+    // Once configured, it isn't suggested again
+    fs::write(
+        root.join(".gather-context.toml"),
+        "[calls]\nskip = [\"audit\"]\n",
+    )
+    .unwrap();
+    let (_, stderr) = gather(&root, &["--ban", "audit"]);
+    assert!(!stderr.contains("was left out"), "{}", stderr);
+
+    let counts = fs::read_to_string(root.join(".gather-context/learned-excludes.json")).unwrap();
+    let counts: serde_json::Value = serde_json::from_str(&counts).unwrap();
+    assert_eq!(counts["audit"], 4);
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn failed_runs_count_nothing() {
+    let root = project("learned-excludes-failed", FILES);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gather-context"))
+        .arg("gather")
+        .arg(&root)
+        .arg("no_such_function")
+        .arg("--non-interactive")
+        .args(["--ban", "audit"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(!root.join(".gather-context/learned-excludes.json").exists());
+
+    gather(&root, &["--ban", "audit"]);
+    let counts = fs::read_to_string(root.join(".gather-context/learned-excludes.json")).unwrap();
+    let counts: serde_json::Value = serde_json::from_str(&counts).unwrap();
+    assert_eq!(counts["audit"], 1);
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,150 @@
+//! The syntax-tree parser finds the functions line regexes miss (trait
+//! default methods, nested functions, signatures spread over several
+//! lines), attributes each call to the innermost function making it, and
+//! leaves files it can't parse to the regex extraction.
+
+#![cfg(feature = "syn-backend")]
+
+use gather_context::parser::{parse_functions, Call, ParsedFunction};
+
+fn parsed(content: &str) -> Vec<ParsedFunction> {
+    parse_functions(content).expect("the source parses")
+}
+
+fn function<'a>(functions: &'a [ParsedFunction], name: &str) -> &'a ParsedFunction {
+    functions
+        .iter()
+        .find(|function| function.name == name)
+        .unwrap_or_else(|| panic!("{} not parsed: {:?}", name, functions))
+}
+
+fn path(segments: &[&str]) -> Call {
+    Call::Path(segments.iter().map(|segment| segment.to_string()).collect())
+}
+
+#[test]
+fn trait_default_methods_belong_to_their_trait() {
+    let content = r#"
+pub trait Store {
+    fn load(&self) -> String;
+
+    fn reload(&self) -> String {
+        self.load()
+    }
+}
+"#;
+    let functions = parsed(content);
+
+    let reload = function(&functions, "reload");
+    assert_eq!(reload.self_type.as_deref(), Some("Store"));
+    assert_eq!(
+        reload.calls,
+        [Call::Method {
+            name: "load".to_string(),
+            path_arguments: Vec::new(),
+            on_self: true,
+        }]
+    );
+    assert!(content[reload.start..reload.end]
+        .trim()
+        .starts_with("fn reload"));
+    assert!(content[reload.start..reload.end].ends_with('}'));
+}
+
+#[test]
+fn nested_functions_own_their_calls() {
+    let content = r#"
+fn outer() {
+    fn inner() {
+        deep();
+    }
+    inner();
+    shallow();
+}
+"#;
+    let functions = parsed(content);
+
+    let outer = function(&functions, "outer");
+    assert_eq!(outer.calls, [path(&["inner"]), path(&["shallow"])]);
+    let inner = function(&functions, "inner");
+    assert_eq!(inner.calls, [path(&["deep"])]);
+    assert!(inner.start > outer.start && inner.end < outer.end);
+}
+
+#[test]
+fn multi_line_where_clauses_are_part_of_the_signature() {
+    let content = r#"
+impl<T> Queue<T> {
+    pub fn drain_into<S>(
+        &mut self,
+        sink: &mut S,
+    ) -> usize
+    where
+        S: Extend<T>,
+        T: Clone,
+    {
+        flush(sink)
+    }
+}
+"#;
+    let functions = parsed(content);
+
+    let drain = function(&functions, "drain_into");
+    assert_eq!(drain.self_type.as_deref(), Some("Queue"));
+    assert_eq!(drain.calls, [path(&["flush"])]);
+    let definition = &content[drain.start..drain.end];
+    assert!(definition.trim_start().starts_with("pub fn drain_into<S>("));
+    assert!(definition.contains("T: Clone,"));
+}
+
+#[test]
+fn calls_inside_macro_arguments_are_recorded() {
+    let content = r#"
+fn report(items: &[u8]) {
+    println!("{}", describe(items));
+    assert_eq!(checksum(items), 0);
+    let body = serde_json::json!({ "total": total(items) });
+}
+"#;
+    let functions = parsed(content);
+
+    let calls = &function(&functions, "report").calls;
+    for expected in [
+        Call::Macro("println".to_string()),
+        path(&["describe"]),
+        Call::Macro("assert_eq".to_string()),
+        path(&["checksum"]),
+        Call::Macro("json".to_string()),
+        path(&["total"]),
+    ] {
+        assert!(
+            calls.contains(&expected),
+            "{:?} not in {:?}",
+            expected,
+            calls
+        );
+    }
+}
+
+#[test]
+fn a_file_that_does_not_parse_is_left_to_the_regexes() {
+    // `box` expressions are nightly-only syntax
+    let content = "fn boxed() -> Box<u8> {\n    let value = box 1;\n    wrap(value)\n}\n";
+    assert!(parse_functions(content).is_none());
+
+    #[cfg(feature = "regex-backend")]
+    {
+        use gather_context::lang::{extract, ExtractOptions};
+        use std::path::Path;
+
+        let items = extract(
+            content,
+            Path::new("src/lib.rs"),
+            "src",
+            &ExtractOptions::default(),
+        );
+        let boxed = &items.functions["boxed"];
+        assert!(boxed.definition.ends_with("wrap(value)\n}"));
+        assert!(boxed.calls.contains("wrap"));
+    }
+}
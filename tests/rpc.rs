@@ -0,0 +1,208 @@
+//! The JSON-RPC protocol: what a session is recorded as having been sent
+//! depends on the responses the client actually got, and `gather-context
+//! rpc` answers requests, cancellations and malformed lines over stdio.
+
+mod common;
+
+use common::project;
+use gather_context::budget::Tokenizer;
+use gather_context::index::{IndexOptions, ProjectIndex};
+use gather_context::rpc::{IndexBuilder, Server, REQUEST_CANCELLED};
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// `process_queue` calling `drain`.
+const FILES: &[(&str, &str)] = &[(
+    "src/lib.rs",
+    "pub fn process_queue() {\n    drain();\n}\n\nfn drain() {}\n",
+)];
+
+fn server(root: &Path) -> Server {
+    let build: IndexBuilder =
+        Box::new(|root: &Path| ProjectIndex::build(root, &IndexOptions::default()));
+    let mut server = Server::new(build, Tokenizer::Chars);
+    server
+        .register("demo".to_string(), root.to_path_buf())
+        .unwrap();
+    server
+}
+
+fn gather(id: u64, delta: bool) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "gather",
+        "params": {"function": "process_queue", "session": "chat", "delta": delta},
+    })
+}
+
+fn names(response: &Value, key: &str) -> Vec<String> {
+    let mut names: Vec<String> = response["result"][key]
+        .as_array()
+        .unwrap_or_else(|| panic!("no {} in {}", key, response))
+        .iter()
+        .map(|entry| {
+            entry["name"]
+                .as_str()
+                .or(entry.as_str())
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn a_gather_cancelled_while_running_leaves_the_session_unchanged() {
+    let root = project("rpc-cancelled-session", FILES);
+    let mut server = server(&root);
+
+    // Not cancelled when it starts, cancelled by the time it is answered
+    let checks = Cell::new(0);
+    let cancelled_midway = || {
+        checks.set(checks.get() + 1);
+        checks.get() > 1
+    };
+    let response = server.handle(&gather(1, false), &cancelled_midway).unwrap();
+    assert_eq!(response["error"]["code"], REQUEST_CANCELLED);
+
+    let response = server.handle(&gather(2, true), &|| false).unwrap();
+    assert_eq!(
+        names(&response, "functions"),
+        ["src::drain", "src::process_queue"]
+    );
+    assert!(names(&response, "already_sent").is_empty());
+
+    // What was answered counts as sent
+    let response = server.handle(&gather(3, true), &|| false).unwrap();
+    assert!(names(&response, "functions").is_empty());
+    assert_eq!(
+        names(&response, "already_sent"),
+        ["src::drain", "src::process_queue"]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+/// Runs `gather-context rpc root`, writes `lines` to it at once and returns
+/// its responses, which end with the one to the final `shutdown`.
+fn round_trip(root: &Path, lines: &[String]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gather-context"))
+        .arg("rpc")
+        .arg(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for line in lines {
+        writeln!(stdin, "{}", line).unwrap();
+    }
+    let shutdown = json!({"jsonrpc": "2.0", "id": "end", "method": "shutdown"});
+    writeln!(stdin, "{}", shutdown).unwrap();
+
+    let responses: Vec<Value> = BufReader::new(child.stdout.take().unwrap())
+        .lines()
+        .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+        .collect();
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+    assert_eq!(responses.last().unwrap()["id"], "end");
+    responses
+}
+
+fn response(responses: &[Value], id: Value) -> &Value {
+    responses
+        .iter()
+        .find(|response| response["id"] == id)
+        .unwrap_or_else(|| panic!("no response to {} in {:?}", id, responses))
+}
+
+fn cancel(id: u64) -> Value {
+    json!({"jsonrpc": "2.0", "method": "$/cancelRequest", "params": {"id": id}})
+}
+
+#[test]
+fn a_gather_is_answered_over_stdio() {
+    let root = project("rpc-round-trip", FILES);
+    let responses = round_trip(&root, &[gather(1, false).to_string()]);
+
+    let gathered = response(&responses, json!(1));
+    assert_eq!(gathered["jsonrpc"], "2.0");
+    assert_eq!(
+        names(gathered, "functions"),
+        ["src::drain", "src::process_queue"]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn a_malformed_line_is_a_parse_error_and_the_server_carries_on() {
+    let root = project("rpc-parse-error", FILES);
+    let responses = round_trip(
+        &root,
+        &["{not json".to_string(), gather(1, false).to_string()],
+    );
+
+    let error = response(&responses, Value::Null);
+    assert_eq!(error["error"]["code"], -32700);
+    assert!(response(&responses, json!(1))["result"].is_object());
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn cancelling_a_request_that_is_not_pending_does_nothing() {
+    let root = project("rpc-stale-cancel", FILES);
+    // The cancel arrives before any request 7 was sent, so the later
+    // request 7 isn't affected by it
+    let responses = round_trip(
+        &root,
+        &[cancel(7).to_string(), gather(7, false).to_string()],
+    );
+
+    let gathered = response(&responses, json!(7));
+    assert!(gathered.get("error").is_none(), "{}", gathered);
+    assert_eq!(
+        names(gathered, "functions"),
+        ["src::drain", "src::process_queue"]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn cancelling_a_pending_request_answers_it_as_cancelled() {
+    let root = project("rpc-pending-cancel", FILES);
+    // Indexing this repository keeps the server busy while the gather
+    // behind it and its cancellation are read
+    let register = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "register",
+        "params": {"name": "self", "root": env!("CARGO_MANIFEST_DIR")},
+    });
+    let responses = round_trip(
+        &root,
+        &[
+            register.to_string(),
+            gather(2, false).to_string(),
+            cancel(2).to_string(),
+        ],
+    );
+
+    assert!(response(&responses, json!(1)).get("error").is_none());
+    assert_eq!(
+        response(&responses, json!(2))["error"]["code"],
+        REQUEST_CANCELLED
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}